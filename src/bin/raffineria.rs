@@ -0,0 +1,189 @@
+#[macro_use]
+extern crate log;
+
+use std::io;
+use std::path::PathBuf;
+use std::process;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use failure::Error;
+use futures::prelude::*;
+use tokio::runtime::Runtime;
+
+use raffineria::cli::Cli;
+use raffineria::config::{self, Format};
+use raffineria::graph::runner::GraphRunner;
+use raffineria::spec::{DiagramFormat, GraphSpec};
+
+enum Command {
+    Run,
+    Validate,
+    Print { format: Option<Format> },
+    Diagram { format: DiagramFormat },
+}
+
+struct RaffineriaCli {
+    command: Command,
+    spec_path: PathBuf,
+}
+
+impl RaffineriaCli {
+    fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        let (command, sub_matches) = match matches.subcommand() {
+            ("run", Some(sub_matches)) => (Command::Run, sub_matches),
+            ("validate", Some(sub_matches)) => (Command::Validate, sub_matches),
+            ("print", Some(sub_matches)) => {
+                let format = sub_matches
+                    .value_of("format")
+                    .map(Format::from_name)
+                    .transpose()?;
+                (Command::Print { format }, sub_matches)
+            }
+            ("diagram", Some(sub_matches)) => {
+                let format = DiagramFormat::from_name(
+                    sub_matches
+                        .value_of("format")
+                        .expect("format has a default value"),
+                )?;
+                (Command::Diagram { format }, sub_matches)
+            }
+            (unknown, _) => Err(failure::err_msg(format!("Unknown command: {:?}", unknown)))?,
+        };
+        let spec_path = sub_matches
+            .value_of("SPEC")
+            .map(PathBuf::from)
+            .expect("SPEC is a required argument");
+
+        Ok(Self { command, spec_path })
+    }
+}
+
+impl Cli for RaffineriaCli {
+    fn use_dotenv(&self) -> bool {
+        true
+    }
+
+    fn name() -> &'static str {
+        "raffineria"
+    }
+
+    fn run(self) -> Result<(), Error> {
+        let graph_spec = config::read_file(&self.spec_path)?;
+
+        match self.command {
+            Command::Run => {
+                let () = validate_graph(&graph_spec)?;
+                run_graph(graph_spec)
+            }
+            Command::Validate => {
+                let () = validate_graph(&graph_spec)?;
+                println!("{}: OK", self.spec_path.display());
+                Ok(())
+            }
+            Command::Print { format } => {
+                let format = match format {
+                    Some(format) => format,
+                    None => Format::from_path(&self.spec_path)?,
+                };
+                config::write(io::stdout(), graph_spec, format)
+            }
+            Command::Diagram { format } => {
+                print!("{}", graph_spec.to_diagram(format));
+                Ok(())
+            }
+        }
+    }
+}
+
+fn run_graph(graph_spec: GraphSpec) -> Result<(), Error> {
+    let mut runtime = Runtime::new()?;
+    let result = runtime.block_on(GraphRunner::top_level(graph_spec).into_future());
+
+    match result {
+        Ok(()) => {
+            let _ = runtime.shutdown_on_idle().wait();
+            Ok(())
+        }
+        Err(reason) => {
+            error!("Graph failed: {:?}", reason);
+            let _ = runtime.shutdown_now().wait();
+            Err(reason.into())
+        }
+    }
+}
+
+fn validate_graph(graph_spec: &GraphSpec) -> Result<(), Error> {
+    graph_spec.validate().map_err(|errors| {
+        for error in errors.iter() {
+            eprintln!("{}", error);
+        }
+        failure::err_msg(format!(
+            "{} problem(s) found in the graph spec",
+            errors.len()
+        ))
+    })
+}
+
+fn spec_arg() -> Arg<'static, 'static> {
+    Arg::with_name("SPEC")
+        .help("Path to the graph spec (*.yaml, *.yml or *.json)")
+        .required(true)
+}
+
+fn app() -> App<'static, 'static> {
+    App::new(RaffineriaCli::name())
+        .version(env!("CARGO_PKG_VERSION"))
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Runs the graph")
+                .arg(spec_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Checks the graph spec without running it")
+                .arg(spec_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("print")
+                .about("Prints the graph spec with the defaults filled in")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "yaml"])
+                        .help("Output format (defaults to the format of SPEC)"),
+                )
+                .arg(spec_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("diagram")
+                .about("Renders the graph as a Graphviz or Mermaid diagram")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["dot", "mermaid"])
+                        .default_value("dot")
+                        .help("Diagram format"),
+                )
+                .arg(spec_arg()),
+        )
+}
+
+fn main() {
+    let matches = app().get_matches();
+
+    let exit_code = match RaffineriaCli::from_matches(&matches)
+        .map_err(|reason| reason.into())
+        .and_then(raffineria::util::run)
+    {
+        Ok(()) => 0,
+        Err(reason) => {
+            eprintln!("{}: {}", RaffineriaCli::name(), reason);
+            1
+        }
+    };
+
+    process::exit(exit_code)
+}
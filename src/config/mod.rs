@@ -1,3 +1,8 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::spec::GraphSpec;
+
 pub mod json;
 pub mod yaml;
 
@@ -8,3 +13,59 @@ pub trait FormatSupport<Format>: Sized {
     fn save(&self) -> Result<Format, Self::SaveError>;
     fn load(data: &Format) -> Result<Self, Self::LoadError>;
 }
+
+#[derive(Fail, Debug)]
+pub enum ConfigError {
+    #[fail(display = "ConfigError::UnknownFileExtension: {:?}", _0)]
+    UnknownFileExtension(PathBuf),
+
+    #[fail(display = "ConfigError::UnknownFormat: {}", _0)]
+    UnknownFormat(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Self, ConfigError> {
+        match name {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            unknown => Err(ConfigError::UnknownFormat(unknown.to_owned())),
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .ok_or(ConfigError::UnknownFileExtension(path.to_owned()))
+            .and_then(|ext| {
+                Self::from_name(&ext.to_lowercase())
+                    .map_err(|_| ConfigError::UnknownFileExtension(path.to_owned()))
+            })
+    }
+}
+
+pub fn read_file<P: AsRef<Path>>(path: P) -> Result<GraphSpec, failure::Error> {
+    let format = Format::from_path(&path)?;
+    let file = File::open(&path)?;
+    read(file, format)
+}
+
+pub fn read<R: std::io::Read>(r: R, format: Format) -> Result<GraphSpec, failure::Error> {
+    match format {
+        Format::Json => json::read(r),
+        Format::Yaml => yaml::read(r),
+    }
+}
+
+pub fn write<W: std::io::Write>(w: W, gs: GraphSpec, format: Format) -> Result<(), failure::Error> {
+    match format {
+        Format::Json => json::write(w, gs),
+        Format::Yaml => yaml::write(w, gs),
+    }
+}
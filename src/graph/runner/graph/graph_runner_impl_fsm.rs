@@ -98,7 +98,12 @@ impl FSM for GraphRunner {
                         (vertex_name, vertex_spec, in_chans, out_chans)
                     })
                     .map(|(_vertex_name, vertex_spec, in_chans, out_chans)| {
-                        VertexRunner::new(&vertex_spec.run, in_chans, out_chans)
+                        VertexRunner::new(
+                            &vertex_spec.run,
+                            &vertex_spec.restart_strategy,
+                            in_chans,
+                            out_chans,
+                        )
                     })
                     .collect::<Vec<_>>();
 
@@ -2,6 +2,7 @@ use futures::sync::mpsc;
 use futures::sync::mpsc::{Receiver, Sender};
 
 use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::transcode::Transcoder;
 use crate::protocol::{Schema, SchemaResolution, SchemaResolutionError};
 
 const MPSC_BUFFER_SIZE: usize = 32;
@@ -71,6 +72,10 @@ impl<In, Out> ChannelsWithResolution<In, Out> {
             tx,
         })
     }
+
+    pub fn transcoder(&self) -> Transcoder {
+        Transcoder::new(&self.writer_schema, &self.reader_schema)
+    }
 }
 
 pub fn pipes(schema: &Schema) -> (ProducerChannels, ConsumerChannels) {
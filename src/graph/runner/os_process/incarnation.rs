@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::process::ExitStatus;
+
+use futures::future;
+use futures::future::Either;
+use futures::prelude::*;
+
+use crate::futures::SendBoxedFuture;
+use crate::spec::LogSpec;
+
+use super::*;
+
+/// Spawns the process once, performs the handshake and wires it up to the given channels.
+/// Resolves when the process terminates.
+pub fn incarnation(
+    cmd: Vec<String>,
+    env: HashMap<String, String>,
+    log: LogSpec,
+    inlets: Vec<graph_channels::ConsumerChannels>,
+    outlets: Vec<graph_channels::ProducerChannels>,
+) -> SendBoxedFuture<(), OsProcessError> {
+    let spawned = future::result(spawn::spawn(cmd, env, log))
+        .map_err(|err| Into::<OsProcessError>::into(err));
+
+    let process_polled = spawned.and_then(|mut spawned| {
+        spawned
+            .process_handle
+            .poll()
+            .map_err(|io_err| OsProcessError::ProcessRunError(io_err))
+            .and_then(|poll| match poll {
+                Async::Ready(exit_status) => {
+                    Err(OsProcessError::UnexpectedProcessExit(exit_status))
+                }
+                Async::NotReady => Ok(spawned),
+            })
+    });
+
+    let logging_spawned = process_polled.map(|spawned| {
+        let _log_capture_spawn = tokio::spawn(
+            spawned
+                .log_capture
+                .map(|()| info!("Logging complete"))
+                .map_err(|reason| error!("Logging failure: {:?}", reason)),
+        );
+        (
+            spawned.process_handle,
+            spawned.from_process,
+            spawned.to_process,
+        )
+    });
+
+    let handshake_done =
+        logging_spawned.and_then(move |(process_handle, from_process, to_process)| {
+            handshake::handshake(process_handle, from_process, to_process, inlets, outlets)
+                .into_future()
+                .map_err(|err| Into::<OsProcessError>::into(err))
+        });
+
+    let stage_complete = handshake_done.and_then(move |handshake_done| {
+        let exited = handshake_done
+            .process_handle
+            .map_err(|err| OsProcessError::ProcessRunError(err));
+
+        let wired_up = wire_up::wire_up(
+            handshake_done.protocol_inlet,
+            handshake_done.protocol_outlet,
+            handshake_done.inlets_with_resolution,
+            handshake_done.outlets_with_resolution,
+        )
+        .map_err(|err| Into::<OsProcessError>::into(err));
+
+        exited
+            .select2(wired_up)
+            .map_err(|either| either.split().0)
+            .and_then(|either| -> SendBoxedFuture<(), OsProcessError> {
+                match either {
+                    Either::A((exit_status, wired_up)) => match check_exit_status(exit_status) {
+                        Ok(()) => Box::new(wired_up),
+                        Err(reason) => Box::new(future::err(reason)),
+                    },
+                    Either::B(((), exited)) => Box::new(exited.and_then(check_exit_status)),
+                }
+            })
+    });
+
+    Box::new(stage_complete)
+}
+
+fn check_exit_status(exit_status: ExitStatus) -> Result<(), OsProcessError> {
+    if exit_status.success() {
+        Ok(())
+    } else {
+        Err(OsProcessError::UnexpectedProcessExit(exit_status))
+    }
+}
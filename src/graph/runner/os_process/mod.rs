@@ -1,7 +1,11 @@
 use super::*;
 
 mod handshake;
+mod incarnation;
+mod relay;
+mod restart_intensity;
 mod spawn;
+mod supervisor;
 mod wire_up;
 
 mod os_process_error;
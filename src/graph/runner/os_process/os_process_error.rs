@@ -19,6 +19,24 @@ pub enum OsProcessError {
 
     #[fail(display = "OsProcessError::WireUpError")]
     WireUpError(#[cause] wire_up::WireUpError),
+
+    #[fail(display = "OsProcessError::RelayError")]
+    RelayError(#[cause] relay::RelayError),
+
+    #[fail(display = "OsProcessError::TimerError")]
+    TimerError(#[cause] tokio::timer::Error),
+}
+
+impl OsProcessError {
+    /// Whether a fresh process may recover from this failure.
+    /// A relay failure means a neighbour of the vertex is gone, so a restart is pointless.
+    pub fn is_restartable(&self) -> bool {
+        match *self {
+            OsProcessError::RelayError(_) => false,
+            OsProcessError::TimerError(_) => false,
+            _ => true,
+        }
+    }
 }
 
 impl From<spawn::SpawnError> for OsProcessError {
@@ -1,16 +1,21 @@
-use futures::future;
 use futures::prelude::*;
 use std::collections::HashMap;
 
+use crate::futures::fsm::FSM;
 use crate::futures::SendBoxedFuture;
-use crate::spec::LogSpec;
+use crate::spec::{LogSpec, RestartStrategySpec};
 
 use super::*;
 
+use relay::{InletRelay, OutletRelay};
+use restart_intensity::RestartIntensity;
+use supervisor::{Supervisor, SupervisorContext};
+
 pub struct OsProcessRunner {
     cmd: Vec<String>,
     env: HashMap<String, String>,
     log: LogSpec,
+    restart_strategy: RestartStrategySpec,
     inlets: Vec<graph_channels::ConsumerChannels>,
     outlets: Vec<graph_channels::ProducerChannels>,
 }
@@ -20,6 +25,7 @@ impl OsProcessRunner {
         cmd: Vec<String>,
         env: HashMap<String, String>,
         log: LogSpec,
+        restart_strategy: RestartStrategySpec,
         inlets: Vec<graph_channels::ConsumerChannels>,
         outlets: Vec<graph_channels::ProducerChannels>,
     ) -> Self {
@@ -29,6 +35,7 @@ impl OsProcessRunner {
             cmd,
             env,
             log,
+            restart_strategy,
             inlets,
             outlets,
         }
@@ -43,59 +50,16 @@ impl IntoFuture for OsProcessRunner {
     fn into_future(self) -> Self::Future {
         trace!("<OsProcessRunner as IntoFuture>::into_future(...)");
 
-        let spawned = future::result(spawn::spawn(self.cmd, self.env, self.log))
-            .map_err(|err| Into::<OsProcessError>::into(err));
-
-        let process_polled = spawned.and_then(|mut spawned| {
-            spawned
-                .process_handle
-                .poll()
-                .map_err(|io_err| OsProcessError::ProcessRunError(io_err))
-                .and_then(|poll| match poll {
-                    Async::Ready(exit_status) => {
-                        Err(OsProcessError::UnexpectedProcessExit(exit_status))
-                    }
-                    Async::NotReady => Ok(spawned),
-                })
-        });
-
-        let logging_spawned = process_polled.map(|spawned| {
-            let _log_capture_spawn = tokio::spawn(
-                spawned
-                    .log_capture
-                    .map(|()| info!("Logging complete"))
-                    .map_err(|reason| error!("Logging failure: {:?}", reason)),
-            );
-            (
-                spawned.process_handle,
-                spawned.from_process,
-                spawned.to_process,
-            )
-        });
-
-        let inlets = self.inlets;
-        let outlets = self.outlets;
-
-        let handshake_done =
-            logging_spawned.and_then(move |(process_handle, from_process, to_process)| {
-                handshake::handshake(process_handle, from_process, to_process, inlets, outlets)
-                    .into_future()
-                    .map_err(|err| Into::<OsProcessError>::into(err))
-            });
-
-        let stage_complete = handshake_done.and_then(move |handshake_done| {
-            let process_handle = handshake_done.process_handle;
-            wire_up::wire_up(
-                handshake_done.protocol_inlet,
-                handshake_done.protocol_outlet,
-                handshake_done.inlets_with_resolution,
-                handshake_done.outlets_with_resolution,
-            )
-            .map_err(|err| Into::<OsProcessError>::into(err))
-            .and_then(move |()| process_handle.map_err(|err| OsProcessError::ProcessRunError(err)))
-        });
+        let ctx = SupervisorContext {
+            cmd: self.cmd,
+            env: self.env,
+            log: self.log,
+            restart_intensity: RestartIntensity::from_spec(&self.restart_strategy),
+            inlets: self.inlets.into_iter().map(InletRelay::inlet).collect(),
+            outlets: self.outlets.into_iter().map(OutletRelay::outlet).collect(),
+        };
 
-        let inner = Box::new(stage_complete.map(|_| ()));
+        let inner = Box::new(Supervisor::Starting { ctx }.into_fsm_future());
 
         OsProcessRunnerFuture { inner }
     }
@@ -0,0 +1,289 @@
+use std::sync::{Arc, Mutex};
+
+use futures::prelude::*;
+use futures::stream;
+use futures::sync::mpsc;
+
+use crate::futures::SendBoxedFuture;
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::Schema;
+
+use super::*;
+
+use graph_channels::{Channels, ConsumerChannels, ProducerChannels};
+
+#[derive(Fail, Debug)]
+pub enum RelayError {
+    #[fail(display = "RelayError::RxFailure")]
+    RxFailure,
+
+    #[fail(display = "RelayError::TxFailure")]
+    TxFailure,
+
+    #[fail(display = "RelayError::Detached")]
+    Detached,
+}
+
+pub type RelayFuture = SendBoxedFuture<(), RelayError>;
+
+pub type InletRelay = Relay<InletState>;
+pub type OutletRelay = Relay<OutletState>;
+
+/// Tracks the protocol state of a single graph port,
+/// so that a freshly spawned process can be attached to it
+/// without the peer noticing the restart.
+pub trait PortState: Send + 'static {
+    type In: Send + 'static;
+    type Out: Send + 'static;
+
+    fn observe_in(&mut self, message: &Self::In);
+    fn observe_out(&mut self, message: &Self::Out) -> bool;
+    fn replay(&self) -> Option<Self::In>;
+}
+
+#[derive(Debug, Clone)]
+pub enum OutletState {
+    Idle,
+    Pulled(usize),
+    Cancelled,
+    Terminated,
+}
+
+#[derive(Debug, Clone)]
+pub enum InletState {
+    Idle,
+    Pulled,
+    Cancelled,
+    Completed,
+    Failed(PortFailure),
+}
+
+struct Shared<S: PortState> {
+    state: S,
+    rx: mpsc::Receiver<S::In>,
+}
+
+/// Owns the graph channels of a port for the whole lifetime of the vertex.
+/// Every incarnation of the process gets its own pair of channels via `attach`.
+pub struct Relay<S: PortState> {
+    schema: Schema,
+    shared: Arc<Mutex<Shared<S>>>,
+    tx: mpsc::Sender<S::Out>,
+}
+
+impl InletRelay {
+    pub fn inlet(chans: ConsumerChannels) -> Self {
+        Relay::new(InletState::Idle, chans)
+    }
+}
+
+impl OutletRelay {
+    pub fn outlet(chans: ProducerChannels) -> Self {
+        Relay::new(OutletState::Idle, chans)
+    }
+}
+
+impl<S: PortState> Relay<S> {
+    pub fn new(state: S, chans: Channels<S::In, S::Out>) -> Self {
+        let Channels { schema, rx, tx } = chans;
+        Self {
+            schema,
+            shared: Arc::new(Mutex::new(Shared { state, rx })),
+            tx,
+        }
+    }
+
+    /// Returns the channels for the process along with the inbound (peer -> process)
+    /// and the outbound (process -> peer) halves of the relay.
+    /// The outbound half completes once the process side of the channels is dropped
+    /// and everything it has sent is delivered to the peer.
+    pub fn attach(&self) -> (Channels<S::In, S::Out>, RelayFuture, RelayFuture) {
+        let (into_process_tx, into_process_rx) = mpsc::channel::<S::In>(0);
+        let (from_process_tx, from_process_rx) = mpsc::channel::<S::Out>(0);
+
+        let replay = self
+            .shared
+            .lock()
+            .expect("Relay state poisoned")
+            .state
+            .replay();
+
+        let inbound = stream::iter_ok(replay)
+            .chain(Leased {
+                shared: self.shared.clone(),
+            })
+            .forward(into_process_tx.sink_map_err(|_| RelayError::Detached))
+            .map(|_| ())
+            .or_else(|err| match err {
+                RelayError::Detached => Ok(()),
+                err => Err(err),
+            });
+
+        let shared = self.shared.clone();
+        let outbound = from_process_rx
+            .map_err(|()| RelayError::RxFailure)
+            .filter(move |message| {
+                shared
+                    .lock()
+                    .expect("Relay state poisoned")
+                    .state
+                    .observe_out(message)
+            })
+            .forward(self.tx.clone().sink_map_err(|_| RelayError::TxFailure))
+            .map(|_| ());
+
+        let chans = Channels::new(&self.schema, into_process_rx, from_process_tx);
+
+        (chans, Box::new(inbound), Box::new(outbound))
+    }
+}
+
+struct Leased<S: PortState> {
+    shared: Arc<Mutex<Shared<S>>>,
+}
+
+impl<S: PortState> Stream for Leased<S> {
+    type Item = S::In;
+    type Error = RelayError;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let mut shared = self.shared.lock().expect("Relay state poisoned");
+        let Shared {
+            ref mut state,
+            ref mut rx,
+        } = *shared;
+
+        match rx.poll().map_err(|()| RelayError::RxFailure)? {
+            Async::NotReady => Ok(Async::NotReady),
+            Async::Ready(None) => Ok(Async::Ready(None)),
+            Async::Ready(Some(message)) => {
+                state.observe_in(&message);
+                Ok(Async::Ready(Some(message)))
+            }
+        }
+    }
+}
+
+impl PortState for OutletState {
+    type In = ConsumerMessage;
+    type Out = ProducerMessage;
+
+    fn observe_in(&mut self, message: &ConsumerMessage) {
+        let next = match (&*self, message) {
+            (OutletState::Terminated, _) => OutletState::Terminated,
+            (_, ConsumerMessage::Cancel) => OutletState::Cancelled,
+            (OutletState::Cancelled, _) => OutletState::Cancelled,
+            (_, ConsumerMessage::Pull { max_items }) => OutletState::Pulled(*max_items),
+        };
+        *self = next;
+    }
+
+    fn observe_out(&mut self, message: &ProducerMessage) -> bool {
+        let (next, forward) = match (&*self, message) {
+            (OutletState::Terminated, _) => (OutletState::Terminated, false),
+            (OutletState::Pulled(_), ProducerMessage::Push { .. }) => (OutletState::Idle, true),
+            (as_is, ProducerMessage::Push { .. }) => (as_is.clone(), true),
+            (_, ProducerMessage::Complete) => (OutletState::Terminated, true),
+            (_, ProducerMessage::Fail { .. }) => (OutletState::Terminated, true),
+        };
+        *self = next;
+        forward
+    }
+
+    fn replay(&self) -> Option<ConsumerMessage> {
+        match *self {
+            OutletState::Idle => None,
+            OutletState::Pulled(max_items) => Some(ConsumerMessage::Pull { max_items }),
+            OutletState::Cancelled => Some(ConsumerMessage::Cancel),
+            OutletState::Terminated => Some(ConsumerMessage::Cancel),
+        }
+    }
+}
+
+impl PortState for InletState {
+    type In = ProducerMessage;
+    type Out = ConsumerMessage;
+
+    fn observe_in(&mut self, message: &ProducerMessage) {
+        let next = match (&*self, message) {
+            (InletState::Pulled, ProducerMessage::Push { .. }) => InletState::Idle,
+            (as_is, ProducerMessage::Push { .. }) => as_is.clone(),
+            (_, ProducerMessage::Complete) => InletState::Completed,
+            (_, ProducerMessage::Fail { failure }) => InletState::Failed(failure.clone()),
+        };
+        *self = next;
+    }
+
+    fn observe_out(&mut self, message: &ConsumerMessage) -> bool {
+        let (next, forward) = match (&*self, message) {
+            (InletState::Idle, ConsumerMessage::Pull { .. }) => (InletState::Pulled, true),
+            // The upstream still owes a push to the previous incarnation:
+            // it will be delivered to this one.
+            (InletState::Pulled, ConsumerMessage::Pull { .. }) => (InletState::Pulled, false),
+            (InletState::Idle, ConsumerMessage::Cancel) => (InletState::Cancelled, true),
+            (InletState::Pulled, ConsumerMessage::Cancel) => (InletState::Cancelled, true),
+            (as_is, _) => (as_is.clone(), false),
+        };
+        *self = next;
+        forward
+    }
+
+    fn replay(&self) -> Option<ProducerMessage> {
+        match *self {
+            InletState::Idle => None,
+            InletState::Pulled => None,
+            InletState::Cancelled => Some(ProducerMessage::Complete),
+            InletState::Completed => Some(ProducerMessage::Complete),
+            InletState::Failed(ref failure) => Some(ProducerMessage::Fail {
+                failure: failure.clone(),
+            }),
+        }
+    }
+}
+
+#[test]
+fn relay_test() {
+    // the downstream peer of an outlet relay
+    let (_pull_tx, pull_rx) = mpsc::channel::<ConsumerMessage>(0);
+    let (push_tx, push_rx) = mpsc::channel::<ProducerMessage>(0);
+    let relay = OutletRelay::outlet(Channels::new(&Schema::Null, pull_rx, push_tx));
+
+    // the process pushes its last items and exits; the inbound half is never polled
+    let (chans, _inbound, outbound) = relay.attach();
+    let Channels { tx: process_tx, .. } = chans;
+    let pushed = process_tx
+        .send(ProducerMessage::Push {
+            items: vec![vec![1]],
+        })
+        .and_then(|process_tx| process_tx.send(ProducerMessage::Complete))
+        .map(|_process_tx| ())
+        .map_err(|_| RelayError::TxFailure);
+    let received = push_rx
+        .take(2)
+        .collect()
+        .map_err(|()| RelayError::RxFailure);
+    let ((), (), received) = pushed.join3(outbound, received).wait().unwrap();
+    match &received[..] {
+        [ProducerMessage::Push { items }, ProducerMessage::Complete] => {
+            assert_eq!(items, &vec![vec![1]])
+        }
+        unexpected => panic!("unexpected messages: {:?}", unexpected),
+    }
+
+    // the next incarnation of a terminated outlet gets cancelled
+    match relay.shared.lock().unwrap().state.replay() {
+        Some(ConsumerMessage::Cancel) => (),
+        unexpected => panic!("unexpected replay: {:?}", unexpected),
+    }
+
+    // a push delivered to a process that crashes is not replayed: at-most-once
+    let mut inlet_state = InletState::Idle;
+    assert!(inlet_state.observe_out(&ConsumerMessage::Pull { max_items: 1 }));
+    assert!(!inlet_state.observe_out(&ConsumerMessage::Pull { max_items: 1 }));
+    inlet_state.observe_in(&ProducerMessage::Push {
+        items: vec![vec![1]],
+    });
+    assert!(inlet_state.replay().is_none());
+    assert!(inlet_state.observe_out(&ConsumerMessage::Pull { max_items: 1 }));
+}
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::spec::RestartStrategySpec;
+
+#[derive(Debug)]
+pub enum RestartIntensity {
+    NoRestart,
+    Restart {
+        max_restarts: usize,
+        within: Duration,
+        min_backoff: Duration,
+        max_backoff: Duration,
+        restarts: VecDeque<Instant>,
+    },
+}
+
+impl RestartIntensity {
+    pub fn from_spec(spec: &RestartStrategySpec) -> Self {
+        match *spec {
+            RestartStrategySpec::NoRestart => RestartIntensity::NoRestart,
+            RestartStrategySpec::Restart {
+                max_restarts,
+                within_ms,
+                min_backoff_ms,
+                max_backoff_ms,
+            } => RestartIntensity::Restart {
+                max_restarts,
+                within: Duration::from_millis(within_ms),
+                min_backoff: Duration::from_millis(min_backoff_ms),
+                max_backoff: Duration::from_millis(max_backoff_ms),
+                restarts: VecDeque::new(),
+            },
+        }
+    }
+
+    /// Registers a failure that happened at `now`.
+    /// Returns the delay before the next attempt, or `None` if the vertex should give up.
+    pub fn on_failure(&mut self, now: Instant) -> Option<Duration> {
+        match *self {
+            RestartIntensity::NoRestart => None,
+            RestartIntensity::Restart {
+                max_restarts,
+                within,
+                min_backoff,
+                max_backoff,
+                ref mut restarts,
+            } => {
+                while let Some(&oldest) = restarts.front() {
+                    if now.duration_since(oldest) > within {
+                        restarts.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+
+                if restarts.len() >= max_restarts {
+                    None
+                } else {
+                    let delay = backoff(min_backoff, max_backoff, restarts.len());
+                    restarts.push_back(now);
+                    Some(delay)
+                }
+            }
+        }
+    }
+}
+
+fn backoff(min_backoff: Duration, max_backoff: Duration, attempt: usize) -> Duration {
+    1u32.checked_shl(attempt as u32)
+        .and_then(|factor| min_backoff.checked_mul(factor))
+        .map(|delay| std::cmp::min(delay, max_backoff))
+        .unwrap_or(max_backoff)
+}
+
+#[test]
+fn restart_intensity_test() {
+    let spec = RestartStrategySpec::Restart {
+        max_restarts: 3,
+        within_ms: 1_000,
+        min_backoff_ms: 10,
+        max_backoff_ms: 25,
+    };
+    let mut intensity = RestartIntensity::from_spec(&spec);
+    let t0 = Instant::now();
+
+    assert_eq!(intensity.on_failure(t0), Some(Duration::from_millis(10)));
+    assert_eq!(intensity.on_failure(t0), Some(Duration::from_millis(20)));
+    assert_eq!(intensity.on_failure(t0), Some(Duration::from_millis(25)));
+    assert_eq!(intensity.on_failure(t0), None);
+
+    let t1 = t0 + Duration::from_millis(1_001);
+    assert_eq!(intensity.on_failure(t1), Some(Duration::from_millis(10)));
+
+    let mut no_restart = RestartIntensity::from_spec(&RestartStrategySpec::NoRestart);
+    assert_eq!(no_restart.on_failure(t0), None);
+}
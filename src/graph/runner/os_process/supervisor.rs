@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use futures::future;
+use futures::future::Either;
+use futures::prelude::*;
+use tokio::timer::Delay;
+
+use crate::futures::fsm::*;
+use crate::futures::SendBoxedFuture;
+use crate::spec::LogSpec;
+
+use super::*;
+
+use relay::{InletRelay, OutletRelay};
+use restart_intensity::RestartIntensity;
+
+pub struct SupervisorContext {
+    pub cmd: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub log: LogSpec,
+    pub restart_intensity: RestartIntensity,
+    pub inlets: Vec<InletRelay>,
+    pub outlets: Vec<OutletRelay>,
+}
+
+pub enum Supervisor {
+    Starting {
+        ctx: SupervisorContext,
+    },
+    Running {
+        ctx: SupervisorContext,
+        running: SendBoxedFuture<(), OsProcessError>,
+    },
+    BackingOff {
+        ctx: SupervisorContext,
+        delay: Delay,
+    },
+}
+
+impl FSM for Supervisor {
+    type Item = ();
+    type Error = OsProcessError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            Supervisor::Starting { ctx } => {
+                trace!(
+                    "<Supervisor::Starting as FSM>::turn(...) [cmd: {:?}]",
+                    ctx.cmd
+                );
+
+                let running = start_incarnation(&ctx);
+                Ok(TurnOk::PollMore(Supervisor::Running { ctx, running }))
+            }
+
+            Supervisor::Running {
+                mut ctx,
+                mut running,
+            } => match running.poll() {
+                Ok(Async::NotReady) => Ok(TurnOk::Suspend(Supervisor::Running { ctx, running })),
+                Ok(Async::Ready(())) => Ok(TurnOk::Ready(())),
+                Err(reason) => {
+                    if !reason.is_restartable() {
+                        Err(reason)
+                    } else if let Some(backoff) = ctx.restart_intensity.on_failure(Instant::now()) {
+                        warn!(
+                            "os_process {:?} failed: {:?}. Restarting in {:?}",
+                            ctx.cmd, reason, backoff
+                        );
+                        let delay = Delay::new(Instant::now() + backoff);
+                        Ok(TurnOk::PollMore(Supervisor::BackingOff { ctx, delay }))
+                    } else {
+                        error!("os_process {:?} failed: {:?}. Giving up", ctx.cmd, reason);
+                        Err(reason)
+                    }
+                }
+            },
+
+            Supervisor::BackingOff { ctx, mut delay } => delay
+                .poll()
+                .map_err(|err| OsProcessError::TimerError(err))
+                .map(|poll| match poll {
+                    Async::NotReady => TurnOk::Suspend(Supervisor::BackingOff { ctx, delay }),
+                    Async::Ready(()) => TurnOk::PollMore(Supervisor::Starting { ctx }),
+                }),
+        }
+    }
+}
+
+fn start_incarnation(ctx: &SupervisorContext) -> SendBoxedFuture<(), OsProcessError> {
+    let mut inbound_relays = Vec::new();
+    let mut outbound_relays = Vec::new();
+
+    let inlet_chans = ctx
+        .inlets
+        .iter()
+        .map(|relay| {
+            let (chans, inbound, outbound) = relay.attach();
+            inbound_relays.push(inbound);
+            outbound_relays.push(outbound);
+            chans
+        })
+        .collect();
+    let outlet_chans = ctx
+        .outlets
+        .iter()
+        .map(|relay| {
+            let (chans, inbound, outbound) = relay.attach();
+            inbound_relays.push(inbound);
+            outbound_relays.push(outbound);
+            chans
+        })
+        .collect();
+
+    let inbound = future::join_all(inbound_relays)
+        .map(|_| ())
+        .map_err(|err| OsProcessError::RelayError(err));
+    let outbound = future::join_all(outbound_relays)
+        .map(|_| ())
+        .map_err(|err| OsProcessError::RelayError(err));
+
+    let process = incarnation::incarnation(
+        ctx.cmd.clone(),
+        ctx.env.clone(),
+        ctx.log.clone(),
+        inlet_chans,
+        outlet_chans,
+    );
+
+    // Once the process is done, its ends of the channels are dropped:
+    // the outbound relays deliver whatever is still in flight and complete.
+    // The inbound ones wait for the peers and are dropped instead.
+    let process_flushed = process.join(outbound).map(|_| ());
+
+    Box::new(
+        process_flushed
+            .select2(inbound)
+            .map_err(|either| either.split().0)
+            .and_then(|either| -> SendBoxedFuture<(), OsProcessError> {
+                match either {
+                    Either::A(((), _inbound)) => Box::new(future::ok(())),
+                    Either::B(((), process_flushed)) => Box::new(process_flushed),
+                }
+            }),
+    )
+}
@@ -1,10 +1,13 @@
+use futures::future;
 use futures::prelude::*;
+use futures::sync::mpsc;
 
 use crate::futures::SendBoxedFuture;
 
 use crate::protocol::streams::{ChildStdinOutlet, ChildStdoutInlet};
 use crate::protocol::streams::{CommandToMessage, CommandToMessageError};
 use crate::protocol::streams::{MessageToCommand, MessageToCommandError};
+use crate::protocol::transcode::TranscodeError;
 
 use super::*;
 
@@ -21,6 +24,15 @@ pub enum WireUpError {
 
     #[fail(display = "WireUpError::ProtocolInletError")]
     ProtocolInletError(#[cause] failure::Error),
+
+    #[fail(display = "WireUpError::TranscodeError")]
+    TranscodeError(#[cause] TranscodeError),
+
+    #[fail(display = "WireUpError::HopRxError")]
+    HopRxError,
+
+    #[fail(display = "WireUpError::HopTxError")]
+    HopTxError,
 }
 
 pub fn wire_up(
@@ -29,21 +41,53 @@ pub fn wire_up(
     inlets: Vec<graph_channels::ConsumerChannelsWithResolution>,
     outlets: Vec<graph_channels::ProducerChannelsWithResolution>,
 ) -> SendBoxedFuture<(), WireUpError> {
-    let _inlet_schema_resolutions = inlets
-        .iter()
-        .map(|chans| chans.schema_resolution.clone())
-        .collect::<Vec<_>>();
-    let _outlet_schema_resolutions = outlets
-        .iter()
-        .map(|chans| chans.schema_resolution.clone())
-        .collect::<Vec<_>>();
-
-    let (inlet_rxs, inlet_txs): (Vec<_>, Vec<_>) =
-        inlets.into_iter().map(|chans| (chans.rx, chans.tx)).unzip();
+    let mut hops: Vec<SendBoxedFuture<(), WireUpError>> = Vec::new();
+
+    let (inlet_rxs, inlet_txs): (Vec<_>, Vec<_>) = inlets
+        .into_iter()
+        .map(|chans| {
+            let transcoder = chans.transcoder();
+            if transcoder.is_identity() {
+                (chans.rx, chans.tx)
+            } else {
+                let (hop_tx, hop_rx) = mpsc::channel(0);
+                let hop = chans
+                    .rx
+                    .map_err(|()| WireUpError::HopRxError)
+                    .and_then(move |message| {
+                        transcoder
+                            .transcode_message(message)
+                            .map_err(|err| WireUpError::TranscodeError(err))
+                    })
+                    .forward(hop_tx.sink_map_err(|_| WireUpError::HopTxError))
+                    .map(|_| ());
+                hops.push(Box::new(hop));
+                (hop_rx, chans.tx)
+            }
+        })
+        .unzip();
 
     let (outlet_rxs, outlet_txs): (Vec<_>, Vec<_>) = outlets
         .into_iter()
-        .map(|chans| (chans.rx, chans.tx))
+        .map(|chans| {
+            let transcoder = chans.transcoder();
+            if transcoder.is_identity() {
+                (chans.rx, chans.tx)
+            } else {
+                let (hop_tx, hop_rx) = mpsc::channel(0);
+                let hop = hop_rx
+                    .map_err(|()| WireUpError::HopRxError)
+                    .and_then(move |message| {
+                        transcoder
+                            .transcode_message(message)
+                            .map_err(|err| WireUpError::TranscodeError(err))
+                    })
+                    .forward(chans.tx.sink_map_err(|_| WireUpError::HopTxError))
+                    .map(|_| ());
+                hops.push(Box::new(hop));
+                (chans.rx, hop_tx)
+            }
+        })
         .unzip();
 
     let protocol_inlet = protocol_inlet.map_err(|err| WireUpError::ProtocolInletError(err));
@@ -54,10 +98,15 @@ pub fn wire_up(
     let message_to_command = MessageToCommand::new(outlet_rxs, inlet_rxs)
         .map_err(|err| WireUpError::MessageToCommandError(err));
 
-    let inlet_bound = command_to_message.send_all(protocol_inlet);
-    let outlet_bound = message_to_command.forward(protocol_outlet);
+    // Drop the CommandToMessage as soon as the child's stdout is over
+    // so that the outlet hops see the end of their streams.
+    let inlet_bound = command_to_message.send_all(protocol_inlet).map(|_| ());
+    let outlet_bound = message_to_command.forward(protocol_outlet).map(|_| ());
 
-    let fut = inlet_bound.join(outlet_bound).map(|_| ());
+    let fut = inlet_bound
+        .join(outlet_bound)
+        .join(future::join_all(hops))
+        .map(|_| ());
 
     Box::new(fut)
 }
@@ -1,3 +1,4 @@
+use crate::protocol::transcode::TranscodeError;
 use crate::protocol::SchemaResolutionError;
 use crate::std_stages::StdStageError;
 
@@ -20,6 +21,9 @@ pub enum StdStageRunnerError {
         actual_outlets: usize,
     },
 
+    #[fail(display = "StdStageRunnerError::TranscodeError")]
+    TranscodeError(#[cause] TranscodeError),
+
     #[fail(display = "StdStageRunnerError::MpscError")]
     MpscError,
 
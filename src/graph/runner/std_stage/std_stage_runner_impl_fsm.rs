@@ -106,6 +106,8 @@ fn wire_up(
         let inlets_wired_up =
             future::join_all(inlets_peer.into_iter().zip(inlets_stage.into_iter()).map(
                 |(inlet_peer, (tx, rx))| {
+                    let transcoder = inlet_peer.transcoder();
+
                     let into_stage = rx.map_err(|_| StdStageRunnerError::MpscError).forward(
                         inlet_peer
                             .tx
@@ -115,6 +117,11 @@ fn wire_up(
                     let from_stage = inlet_peer
                         .rx
                         .map_err(|_| StdStageRunnerError::MpscError)
+                        .and_then(move |message| {
+                            transcoder
+                                .transcode_message(message)
+                                .map_err(|err| StdStageRunnerError::TranscodeError(err))
+                        })
                         .forward(tx.sink_map_err(|_| StdStageRunnerError::MpscError));
 
                     into_stage.join(from_stage)
@@ -124,11 +131,20 @@ fn wire_up(
         let outlets_wired_up =
             future::join_all(outlets_peer.into_iter().zip(outlets_stage.into_iter()).map(
                 |(outlets_peer, (tx, rx))| {
-                    let into_stage = rx.map_err(|_| StdStageRunnerError::MpscError).forward(
-                        outlets_peer
-                            .tx
-                            .sink_map_err(|_| StdStageRunnerError::MpscError),
-                    );
+                    let transcoder = outlets_peer.transcoder();
+
+                    let into_stage = rx
+                        .map_err(|_| StdStageRunnerError::MpscError)
+                        .and_then(move |message| {
+                            transcoder
+                                .transcode_message(message)
+                                .map_err(|err| StdStageRunnerError::TranscodeError(err))
+                        })
+                        .forward(
+                            outlets_peer
+                                .tx
+                                .sink_map_err(|_| StdStageRunnerError::MpscError),
+                        );
 
                     let from_stage = outlets_peer
                         .rx
@@ -1,6 +1,6 @@
 use futures::prelude::*;
 
-use crate::spec::RunSpec;
+use crate::spec::{RestartStrategySpec, RunSpec};
 
 use super::*;
 
@@ -15,11 +15,21 @@ pub enum VertexRunner {
 impl VertexRunner {
     pub fn new(
         run_spec: &RunSpec,
+        restart_strategy: &RestartStrategySpec,
         inlets: Vec<ConsumerChannels>,
         outlets: Vec<ProducerChannels>,
     ) -> Self {
         trace!("VertexRunner::new(...)");
 
+        match (run_spec, restart_strategy) {
+            (RunSpec::OsProcess { .. }, _) => (),
+            (_, RestartStrategySpec::NoRestart) => (),
+            (_, restart_strategy) => warn!(
+                "restart_strategy {:?} is only supported for os_process vertices. Ignoring",
+                restart_strategy
+            ),
+        }
+
         match *run_spec {
             RunSpec::Graph(ref graph_spec) => {
                 VertexRunner::Graph(GraphRunner::new(*graph_spec.clone(), inlets, outlets))
@@ -33,6 +43,7 @@ impl VertexRunner {
                 cmd.clone(),
                 env.clone(),
                 log.clone(),
+                restart_strategy.clone(),
                 inlets,
                 outlets,
             )),
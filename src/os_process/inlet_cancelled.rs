@@ -0,0 +1,5 @@
+/// A sink given to the stage runner as an inlet may fail with `InletCancelled`
+/// to cancel that inlet without failing the whole stage.
+#[derive(Fail, Debug)]
+#[fail(display = "InletCancelled")]
+pub struct InletCancelled;
@@ -8,4 +8,7 @@ pub use stage_runner::StageRunner;
 mod ports;
 pub use ports::Ports;
 
+mod inlet_cancelled;
+pub use inlet_cancelled::InletCancelled;
+
 pub mod std;
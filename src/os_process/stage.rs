@@ -16,6 +16,14 @@ pub trait Stage: Sized {
         &*SCHEMAS_EMPTY_VEC
     }
 
+    /// Whether the stage's outlets end as soon as one of its inlets fails.
+    /// If so, an inlet failure is reported after the outlets are done,
+    /// so that the stage gets a chance to propagate it through them;
+    /// otherwise it is reported immediately.
+    fn propagates_inlet_failures(&self) -> bool {
+        false
+    }
+
     fn into_future(self, ports: Ports) -> FSMFuture<StageRunner<Self>> {
         StageRunner::new(self, ports.protocol_in, ports.protocol_out).into_fsm_future()
     }
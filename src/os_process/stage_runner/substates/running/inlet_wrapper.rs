@@ -6,6 +6,7 @@ use std::collections::VecDeque;
 
 use crate::futures::fsm::*;
 use crate::futures::SendBoxedSink;
+use crate::os_process::InletCancelled;
 use crate::protocol::command::Failure as PortFailure;
 use crate::protocol::streams::{ConsumerRx, ConsumerTx};
 use crate::protocol::{DataItem, Schema};
@@ -46,6 +47,9 @@ pub enum InletWrapper {
         tx: ConsumerTx,
         items: VecDeque<DataItem>,
     },
+    SinkClosing {
+        sink: Snk,
+    },
     SinkSend {
         sink: Snk,
         schema: Schema,
@@ -119,7 +123,13 @@ impl FSM for InletWrapper {
             {
                 Err(reason) => {
                     let message = ConsumerMessage::Cancel;
-                    let shutdown = move |_| Err(reason);
+                    let shutdown = move |_| {
+                        if is_cancellation(&reason) {
+                            Ok(TurnOk::Ready(()))
+                        } else {
+                            Err(reason)
+                        }
+                    };
 
                     let sending_cancel = InletWrapper::TxSend {
                         messages: vec![message].into(),
@@ -146,6 +156,15 @@ impl FSM for InletWrapper {
                 })),
             },
 
+            InletWrapper::SinkClosing { mut sink } => match sink.close() {
+                Err(ref reason) if reason.downcast_ref::<InletCancelled>().is_some() => {
+                    Ok(TurnOk::Ready(()))
+                }
+                Err(reason) => Err(InletWrapperError::SinkFailure(reason)),
+                Ok(Async::NotReady) => Ok(TurnOk::Suspend(InletWrapper::SinkClosing { sink })),
+                Ok(Async::Ready(())) => Ok(TurnOk::Ready(())),
+            },
+
             InletWrapper::SinkSend {
                 sink,
                 schema,
@@ -248,7 +267,9 @@ impl FSM for InletWrapper {
                         tx,
                     })),
                     Async::Ready(None) => Err(InletWrapperError::RxFailure),
-                    Async::Ready(Some(ProducerMessage::Complete)) => Ok(TurnOk::Ready(())),
+                    Async::Ready(Some(ProducerMessage::Complete)) => {
+                        Ok(TurnOk::PollMore(InletWrapper::SinkClosing { sink }))
+                    }
                     Async::Ready(Some(ProducerMessage::Fail { failure })) => {
                         Err(InletWrapperError::InletFailure(failure))
                     }
@@ -273,3 +294,79 @@ impl FSM for InletWrapper {
         }
     }
 }
+
+fn is_cancellation(reason: &InletWrapperError) -> bool {
+    match reason {
+        InletWrapperError::SinkFailure(sink_failure) => {
+            sink_failure.downcast_ref::<InletCancelled>().is_some()
+        }
+        _ => false,
+    }
+}
+
+#[test]
+fn inlet_wrapper_test() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    struct TestSink {
+        closed: Arc<AtomicBool>,
+        cancel_on_close: bool,
+    }
+
+    impl Sink for TestSink {
+        type SinkItem = DataItem;
+        type SinkError = failure::Error;
+
+        fn start_send(&mut self, _item: DataItem) -> StartSend<DataItem, failure::Error> {
+            Ok(AsyncSink::Ready)
+        }
+
+        fn poll_complete(&mut self) -> Poll<(), failure::Error> {
+            Ok(Async::Ready(()))
+        }
+
+        fn close(&mut self) -> Poll<(), failure::Error> {
+            if self.cancel_on_close {
+                Err(InletCancelled.into())
+            } else {
+                self.closed.store(true, Ordering::SeqCst);
+                Ok(Async::Ready(()))
+            }
+        }
+    }
+
+    let run = |cancel_on_close: bool, message: ProducerMessage| {
+        let closed = Arc::new(AtomicBool::new(false));
+        let sink = TestSink {
+            closed: closed.clone(),
+            cancel_on_close,
+        };
+        let ((_producer_rx, mut producer_tx), wrapper) =
+            InletWrapper::new(Box::new(sink), Schema::Null);
+        producer_tx.try_send(message).unwrap();
+        (
+            wrapper.into_fsm_future().wait(),
+            closed.load(Ordering::SeqCst),
+        )
+    };
+
+    // the inlet's completion closes the sink (`SinkClosing`)
+    match run(false, ProducerMessage::Complete) {
+        (Ok(()), true) => (),
+        unexpected => panic!("unexpected outcome: {:?}", unexpected),
+    }
+
+    // the sink may refuse to be closed with `InletCancelled`: not a failure
+    match run(true, ProducerMessage::Complete) {
+        (Ok(()), false) => (),
+        unexpected => panic!("unexpected outcome: {:?}", unexpected),
+    }
+
+    // the inlet's failure is not a completion
+    let failure = PortFailure::from(failure::err_msg("upstream failed"));
+    match run(false, ProducerMessage::Fail { failure }) {
+        (Err(InletWrapperError::InletFailure(_)), false) => (),
+        unexpected => panic!("unexpected outcome: {:?}", unexpected),
+    }
+}
@@ -2,6 +2,7 @@ use futures::future;
 use futures::prelude::*;
 
 use crate::futures::fsm::FSM;
+use crate::futures::SendBoxedFuture;
 use crate::os_process::Stage;
 use crate::protocol::streams::{OwnStdinInlet, OwnStdoutOutlet};
 
@@ -17,6 +18,7 @@ impl Running {
     ) -> Self {
         let outlet_schemas = stage.outlets().clone();
         let inlet_schemas = stage.inlets().clone();
+        let propagates_inlet_failures = stage.propagates_inlet_failures();
         let (outlets, inlets) = stage.into_streams();
 
         let (consumer_sides, outlets): (Vec<_>, Vec<_>) = outlet_schemas
@@ -52,15 +54,37 @@ impl Running {
         let outlets_done =
             future::join_all(outlets.into_iter().map(|outlet| outlet.into_fsm_future()))
                 .map_err(|owe| Into::<RunningFailure>::into(owe));
-        let inlets_done = future::join_all(inlets.into_iter().map(|inlet| inlet.into_fsm_future()))
-            .map_err(|iwe| Into::<RunningFailure>::into(iwe));
+        let inlets_done: SendBoxedFuture<(), RunningFailure> = if propagates_inlet_failures {
+            // An inlet failure is reported once everything else is done,
+            // so that the stage gets a chance to propagate it through its outlets.
+            Box::new(
+                future::join_all(inlets.into_iter().map(|inlet| {
+                    inlet
+                        .into_fsm_future()
+                        .then(|result| Ok::<_, RunningFailure>(result))
+                }))
+                .and_then(|inlet_results| {
+                    inlet_results
+                        .into_iter()
+                        .collect::<Result<Vec<()>, _>>()
+                        .map(|_| ())
+                        .map_err(|iwe| Into::<RunningFailure>::into(iwe))
+                }),
+            )
+        } else {
+            Box::new(
+                future::join_all(inlets.into_iter().map(|inlet| inlet.into_fsm_future()))
+                    .map(|_| ())
+                    .map_err(|iwe| Into::<RunningFailure>::into(iwe)),
+            )
+        };
 
         let done = inbound_commands
             .join(outbound_commands)
             .join(outlets_done)
             .map(|(keep, _)| keep)
             .join(inlets_done)
-            .map(|(keep, _)| keep);
+            .map(|(keep, ())| keep);
 
         Self {
             inner: Box::new(done),
@@ -1,21 +1,250 @@
-// use futures::prelude::*;
-
-pub struct FlowStage;
-
-// type Flow<In, InErr, Out, OutErr> = Sink<SinkItem = In, SinkError = InErr> + Stream<Item = Out, Error = OutErr>;
-
-// pub struct FlowStage<In, InErr, Out, OutErr, F>
-//     where F: Flow<In, InErr, Out, OutErr>
-// {
-//     flow: F,
-// }
-
-// impl<In, InErr, Out, OutErr, F> FlowStage<F>
-//     where F: Flow<In, InErr, Out, OutErr>
-// {
-//     fn from_sink(flow: F) -> Self {
-//         Self {
-//             flow,
-//         }
-//     }
-// }
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::futures::{SendBoxedSink, SendBoxedStream};
+use crate::os_process::{InletCancelled, Stage};
+use crate::protocol::{AvroSchema, DataItem, Schema};
+
+type Flow = SendBoxFnOnce<
+    'static,
+    (SendBoxedStream<DataItem, failure::Error>,),
+    SendBoxedStream<DataItem, failure::Error>,
+>;
+
+#[derive(Fail, Debug)]
+pub enum FlowStageError {
+    #[fail(display = "FlowStageError::InletFailed")]
+    InletFailed,
+}
+
+/// A stage with a single inlet and a single outlet.
+///
+/// - the inlet's completion ends the stream the flow consumes;
+/// - the inlet's failure fails that stream;
+/// - cancelling the outlet drops the flow, which in turn cancels the inlet.
+pub struct FlowStage {
+    flow: Flow,
+    inlet_schemas: Vec<Schema>,
+    outlet_schemas: Vec<Schema>,
+}
+
+impl FlowStage {
+    pub fn from_fn<F, I, O, E>(mut f: F, inlet_schema: &Schema, outlet_schema: &Schema) -> Self
+    where
+        F: FnMut(I) -> Result<O, E> + Send + 'static,
+        I: DeserializeOwned + Send + 'static,
+        O: Serialize + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        Self::from_flow(
+            move |items: SendBoxedStream<I, failure::Error>| {
+                items.and_then(move |item| f(item).map_err(|reason| reason.into()))
+            },
+            inlet_schema,
+            outlet_schema,
+        )
+    }
+
+    pub fn from_flow<F, S, I, O, E>(flow: F, inlet_schema: &Schema, outlet_schema: &Schema) -> Self
+    where
+        F: FnOnce(SendBoxedStream<I, failure::Error>) -> S + Send + 'static,
+        S: Stream<Item = O, Error = E> + Send + 'static,
+        I: DeserializeOwned + Send + 'static,
+        O: Serialize + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        let flow = move |data_items: SendBoxedStream<DataItem, failure::Error>| {
+            let items = data_items.and_then(|data_item| {
+                avro_rs::from_value::<I>(&data_item)
+                    .map_err(|reason| Into::<failure::Error>::into(reason))
+            });
+            let outputs = flow(Box::new(items))
+                .map_err(|reason| Into::<failure::Error>::into(reason))
+                .and_then(|item| {
+                    avro_rs::to_value(item).map_err(|reason| Into::<failure::Error>::into(reason))
+                });
+            Box::new(outputs) as SendBoxedStream<DataItem, failure::Error>
+        };
+
+        Self {
+            flow: SendBoxFnOnce::from(flow),
+            inlet_schemas: vec![inlet_schema.clone()],
+            outlet_schemas: vec![outlet_schema.clone()],
+        }
+    }
+
+    /// Same as `from_fn`, with the schemas derived from the item types.
+    pub fn from_typed_fn<F, I, O, E>(f: F) -> Self
+    where
+        F: FnMut(I) -> Result<O, E> + Send + 'static,
+        I: DeserializeOwned + AvroSchema + Send + 'static,
+        O: Serialize + AvroSchema + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        Self::from_fn(f, &I::avro_schema(), &O::avro_schema())
+    }
+
+    /// Same as `from_flow`, with the schemas derived from the item types.
+    pub fn from_typed_flow<F, S, I, O, E>(flow: F) -> Self
+    where
+        F: FnOnce(SendBoxedStream<I, failure::Error>) -> S + Send + 'static,
+        S: Stream<Item = O, Error = E> + Send + 'static,
+        I: DeserializeOwned + AvroSchema + Send + 'static,
+        O: Serialize + AvroSchema + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        Self::from_flow(flow, &I::avro_schema(), &O::avro_schema())
+    }
+}
+
+impl Stage for FlowStage {
+    fn inlets(&self) -> &Vec<Schema> {
+        &self.inlet_schemas
+    }
+
+    fn outlets(&self) -> &Vec<Schema> {
+        &self.outlet_schemas
+    }
+
+    /// The inlet's failure fails the stream the flow consumes.
+    fn propagates_inlet_failures(&self) -> bool {
+        true
+    }
+
+    fn into_streams(
+        self,
+    ) -> (
+        Vec<SendBoxedStream<DataItem, failure::Error>>,
+        Vec<SendBoxedSink<DataItem, failure::Error>>,
+    ) {
+        let (tx, rx) = mpsc::channel(0);
+        let completed = Arc::new(AtomicBool::new(false));
+
+        let inlet = FlowInlet {
+            tx,
+            completed: completed.clone(),
+        };
+        let input = FlowInput { rx, completed };
+
+        let outlet = self.flow.call(Box::new(input));
+
+        (vec![outlet], vec![Box::new(inlet)])
+    }
+}
+
+struct FlowInlet {
+    tx: mpsc::Sender<DataItem>,
+    completed: Arc<AtomicBool>,
+}
+
+impl Sink for FlowInlet {
+    type SinkItem = DataItem;
+    type SinkError = failure::Error;
+
+    fn start_send(&mut self, item: DataItem) -> StartSend<DataItem, failure::Error> {
+        self.tx.start_send(item).map_err(|_| InletCancelled.into())
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), failure::Error> {
+        self.tx.poll_complete().map_err(|_| InletCancelled.into())
+    }
+
+    fn close(&mut self) -> Poll<(), failure::Error> {
+        let () = futures::try_ready!(self.poll_complete());
+        self.completed.store(true, Ordering::SeqCst);
+        Ok(Async::Ready(()))
+    }
+}
+
+/// The items received by the inlet. Ends when the inlet is closed,
+/// fails when the inlet is dropped without having been closed.
+struct FlowInput {
+    rx: mpsc::Receiver<DataItem>,
+    completed: Arc<AtomicBool>,
+}
+
+impl Stream for FlowInput {
+    type Item = DataItem;
+    type Error = failure::Error;
+
+    fn poll(&mut self) -> Poll<Option<DataItem>, failure::Error> {
+        match self.rx.poll() {
+            Ok(Async::Ready(None)) | Err(()) => {
+                if self.completed.load(Ordering::SeqCst) {
+                    Ok(Async::Ready(None))
+                } else {
+                    Err(FlowStageError::InletFailed.into())
+                }
+            }
+            Ok(poll) => Ok(poll),
+        }
+    }
+}
+
+#[test]
+fn flow_stage_test() {
+    use futures::stream;
+
+    let run = |stage: FlowStage, items: Vec<i64>| {
+        let (mut outlets, mut inlets) = stage.into_streams();
+        let fed = inlets
+            .pop()
+            .unwrap()
+            .send_all(stream::iter_ok::<_, failure::Error>(
+                items.into_iter().map(DataItem::Long),
+            ))
+            .map(|_| ());
+        fed.join(outlets.pop().unwrap().collect())
+            .map(|((), outputs)| outputs)
+            .wait()
+    };
+
+    let doubled = FlowStage::from_fn(
+        |n: i64| Ok::<_, failure::Error>(n * 2),
+        &Schema::Long,
+        &Schema::Long,
+    );
+    assert_eq!(
+        run(doubled, vec![1, 2]).unwrap(),
+        vec![DataItem::Long(2), DataItem::Long(4)]
+    );
+
+    let failing = FlowStage::from_fn(
+        |n: i64| {
+            if n < 2 {
+                Ok(n)
+            } else {
+                Err(failure::err_msg("too large"))
+            }
+        },
+        &Schema::Long,
+        &Schema::Long,
+    );
+    assert!(run(failing, vec![1, 2]).is_err());
+
+    let evens = FlowStage::from_flow(
+        |items: SendBoxedStream<i64, failure::Error>| items.filter(|n| n % 2 == 0),
+        &Schema::Long,
+        &Schema::Long,
+    );
+    assert_eq!(
+        run(evens, vec![1, 2, 3, 4]).unwrap(),
+        vec![DataItem::Long(2), DataItem::Long(4)]
+    );
+
+    // the inlet dropped without having been closed fails the flow
+    let identity = FlowStage::from_flow(
+        |items: SendBoxedStream<i64, failure::Error>| items,
+        &Schema::Long,
+        &Schema::Long,
+    );
+    let (mut outlets, inlets) = identity.into_streams();
+    drop(inlets);
+    assert!(outlets.pop().unwrap().collect().wait().is_err());
+}
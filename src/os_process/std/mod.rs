@@ -1,7 +1,9 @@
 mod flow_stage;
 mod sink_stage;
 mod source_stage;
+mod stage_builder;
 
-pub use flow_stage::FlowStage;
+pub use flow_stage::{FlowStage, FlowStageError};
 pub use sink_stage::SinkStage;
 pub use source_stage::SourceStage;
+pub use stage_builder::{MultiPortStage, StageBuilder, StageBuilderError};
@@ -4,7 +4,7 @@ use serde::de::DeserializeOwned;
 
 use crate::futures::{SendBoxedSink, SendBoxedStream};
 use crate::os_process::Stage;
-use crate::protocol::{DataItem, Schema};
+use crate::protocol::{AvroSchema, DataItem, Schema};
 
 pub struct SinkStage<S, I, E>
 where
@@ -31,6 +31,18 @@ where
     }
 }
 
+impl<S, I, E> SinkStage<S, I, E>
+where
+    S: Sink<SinkItem = I, SinkError = E>,
+    I: DeserializeOwned + AvroSchema,
+    E: Into<failure::Error>,
+{
+    /// The inlet's schema is derived from the item type.
+    pub fn from_typed_sink(sink: S) -> Self {
+        Self::from_sink(sink, &I::avro_schema())
+    }
+}
+
 impl<S, I, E> Stage for SinkStage<S, I, E>
 where
     S: Sink<SinkItem = I, SinkError = E> + Send + 'static,
@@ -3,7 +3,7 @@ use serde::Serialize;
 
 use crate::futures::{SendBoxedSink, SendBoxedStream};
 use crate::os_process::Stage;
-use crate::protocol::{DataItem, Schema};
+use crate::protocol::{AvroSchema, DataItem, Schema};
 
 pub struct SourceStage<S, I, E>
 where
@@ -30,6 +30,18 @@ where
     }
 }
 
+impl<S, I, E> SourceStage<S, I, E>
+where
+    S: Stream<Item = I, Error = E> + Send + 'static,
+    I: Serialize + AvroSchema + Send + 'static,
+    E: Into<failure::Error>,
+{
+    /// The outlet's schema is derived from the item type.
+    pub fn from_typed_stream(source: S) -> Self {
+        Self::from_stream(source, &I::avro_schema())
+    }
+}
+
 impl<S, I, E> Stage for SourceStage<S, I, E>
 where
     S: Stream<Item = I, Error = E> + Send + 'static,
@@ -0,0 +1,228 @@
+use futures::prelude::*;
+use futures::stream;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::futures::{SendBoxedSink, SendBoxedStream};
+use crate::os_process::Stage;
+use crate::protocol::{AvroSchema, DataItem, Schema};
+
+#[derive(Fail, Debug)]
+pub enum StageBuilderError {
+    #[fail(display = "StageBuilderError::DuplicateInletName: {}", _0)]
+    DuplicateInletName(String),
+
+    #[fail(display = "StageBuilderError::DuplicateOutletName: {}", _0)]
+    DuplicateOutletName(String),
+}
+
+/// Assembles a `Stage` out of any number of typed streams (outlets) and sinks (inlets).
+/// The ports are numbered in the order they are added.
+pub struct StageBuilder {
+    inlet_names: Vec<String>,
+    inlet_schemas: Vec<Schema>,
+    inlets: Vec<SendBoxedSink<DataItem, failure::Error>>,
+
+    outlet_names: Vec<String>,
+    outlet_schemas: Vec<Schema>,
+    outlets: Vec<SendBoxedStream<DataItem, failure::Error>>,
+}
+
+impl StageBuilder {
+    pub fn new() -> Self {
+        Self {
+            inlet_names: Vec::new(),
+            inlet_schemas: Vec::new(),
+            inlets: Vec::new(),
+            outlet_names: Vec::new(),
+            outlet_schemas: Vec::new(),
+            outlets: Vec::new(),
+        }
+    }
+
+    pub fn inlet<S, I, E>(mut self, name: &str, schema: &Schema, sink: S) -> Self
+    where
+        S: Sink<SinkItem = I, SinkError = E> + Send + 'static,
+        I: DeserializeOwned + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        let e_mapped = sink.sink_map_err(|reason| Into::<failure::Error>::into(reason));
+        let v_mapped = e_mapped.with_flat_map(|data_item| {
+            stream::iter_result(vec![avro_rs::from_value::<I>(&data_item)])
+                .map_err(|reason| Into::<failure::Error>::into(reason))
+        });
+
+        self.inlet_names.push(name.to_owned());
+        self.inlet_schemas.push(schema.clone());
+        self.inlets.push(Box::new(v_mapped));
+        self
+    }
+
+    pub fn outlet<S, I, E>(mut self, name: &str, schema: &Schema, source: S) -> Self
+    where
+        S: Stream<Item = I, Error = E> + Send + 'static,
+        I: Serialize + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        let e_mapped = source.map_err(|reason| Into::<failure::Error>::into(reason));
+        let v_mapped = e_mapped.and_then(|item| {
+            avro_rs::to_value(item).map_err(|reason| Into::<failure::Error>::into(reason))
+        });
+
+        self.outlet_names.push(name.to_owned());
+        self.outlet_schemas.push(schema.clone());
+        self.outlets.push(Box::new(v_mapped));
+        self
+    }
+
+    /// Same as `inlet`, with the schema derived from the item type.
+    pub fn typed_inlet<S, I, E>(self, name: &str, sink: S) -> Self
+    where
+        S: Sink<SinkItem = I, SinkError = E> + Send + 'static,
+        I: DeserializeOwned + AvroSchema + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        self.inlet(name, &I::avro_schema(), sink)
+    }
+
+    /// Same as `outlet`, with the schema derived from the item type.
+    pub fn typed_outlet<S, I, E>(self, name: &str, source: S) -> Self
+    where
+        S: Stream<Item = I, Error = E> + Send + 'static,
+        I: Serialize + AvroSchema + Send + 'static,
+        E: Into<failure::Error>,
+    {
+        self.outlet(name, &I::avro_schema(), source)
+    }
+
+    pub fn build(self) -> Result<MultiPortStage, StageBuilderError> {
+        if let Some(name) = first_duplicate(&self.inlet_names) {
+            Err(StageBuilderError::DuplicateInletName(name.to_owned()))?
+        }
+        if let Some(name) = first_duplicate(&self.outlet_names) {
+            Err(StageBuilderError::DuplicateOutletName(name.to_owned()))?
+        }
+
+        Ok(MultiPortStage {
+            inlet_names: self.inlet_names,
+            inlet_schemas: self.inlet_schemas,
+            inlets: self.inlets,
+            outlet_names: self.outlet_names,
+            outlet_schemas: self.outlet_schemas,
+            outlets: self.outlets,
+        })
+    }
+}
+
+impl Default for StageBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct MultiPortStage {
+    inlet_names: Vec<String>,
+    inlet_schemas: Vec<Schema>,
+    inlets: Vec<SendBoxedSink<DataItem, failure::Error>>,
+
+    outlet_names: Vec<String>,
+    outlet_schemas: Vec<Schema>,
+    outlets: Vec<SendBoxedStream<DataItem, failure::Error>>,
+}
+
+impl MultiPortStage {
+    pub fn inlet_names(&self) -> &Vec<String> {
+        &self.inlet_names
+    }
+
+    pub fn outlet_names(&self) -> &Vec<String> {
+        &self.outlet_names
+    }
+}
+
+impl Stage for MultiPortStage {
+    fn inlets(&self) -> &Vec<Schema> {
+        &self.inlet_schemas
+    }
+
+    fn outlets(&self) -> &Vec<Schema> {
+        &self.outlet_schemas
+    }
+
+    fn into_streams(
+        self,
+    ) -> (
+        Vec<SendBoxedStream<DataItem, failure::Error>>,
+        Vec<SendBoxedSink<DataItem, failure::Error>>,
+    ) {
+        (self.outlets, self.inlets)
+    }
+}
+
+fn first_duplicate(names: &[String]) -> Option<&String> {
+    names
+        .iter()
+        .enumerate()
+        .find(|(idx, name)| names[..*idx].contains(name))
+        .map(|(_, name)| name)
+}
+
+#[test]
+fn stage_builder_test() {
+    use futures::sync::mpsc;
+
+    let (counts_tx, _counts_rx) = mpsc::unbounded::<i64>();
+    let (names_tx, _names_rx) = mpsc::unbounded::<String>();
+    let stage = StageBuilder::new()
+        .inlet("counts", &Schema::Long, counts_tx)
+        .inlet("names", &Schema::String, names_tx)
+        .outlet(
+            "tags",
+            &Schema::Array(Box::new(Schema::String)),
+            stream::iter_ok::<_, failure::Error>(vec![vec!["a".to_owned()]]),
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(stage.inlet_names(), &vec!["counts", "names"]);
+    assert_eq!(stage.inlets(), &vec![Schema::Long, Schema::String]);
+    assert_eq!(stage.outlet_names(), &vec!["tags"]);
+    assert_eq!(
+        stage.outlets(),
+        &vec![Schema::Array(Box::new(Schema::String))]
+    );
+    let (outlets, inlets) = stage.into_streams();
+    assert_eq!((outlets.len(), inlets.len()), (1, 2));
+
+    let (tx, _rx) = mpsc::unbounded::<i64>();
+    let (other_tx, _other_rx) = mpsc::unbounded::<i64>();
+    match StageBuilder::new()
+        .inlet("counts", &Schema::Long, tx)
+        .outlet(
+            "counts",
+            &Schema::Long,
+            stream::empty::<i64, failure::Error>(),
+        )
+        .inlet("counts", &Schema::Long, other_tx)
+        .build()
+    {
+        Err(StageBuilderError::DuplicateInletName(name)) => assert_eq!(name, "counts"),
+        _ => panic!("the duplicate inlet name is not reported"),
+    }
+    match StageBuilder::new()
+        .outlet(
+            "tags",
+            &Schema::Long,
+            stream::empty::<i64, failure::Error>(),
+        )
+        .outlet(
+            "tags",
+            &Schema::Long,
+            stream::empty::<i64, failure::Error>(),
+        )
+        .build()
+    {
+        Err(StageBuilderError::DuplicateOutletName(name)) => assert_eq!(name, "tags"),
+        _ => panic!("the duplicate outlet name is not reported"),
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Number, Value as JsonValue};
+
+use super::transcode::{default_value, schema_kind, TranscodeError};
+use super::{DataItem, Schema};
+
+#[derive(Fail, Debug)]
+pub enum AvroJsonError {
+    #[fail(display = "AvroJsonError::Mismatch [expected: {}]", expected)]
+    Mismatch { expected: &'static str },
+
+    #[fail(display = "AvroJsonError::UnknownUnionBranch: {}", _0)]
+    UnknownUnionBranch(String),
+
+    #[fail(display = "AvroJsonError::UnknownSymbol: {}", _0)]
+    UnknownSymbol(String),
+
+    #[fail(display = "AvroJsonError::MissingField: {}", _0)]
+    MissingField(String),
+
+    #[fail(display = "AvroJsonError::InvalidDefault")]
+    InvalidDefault(#[cause] TranscodeError),
+
+    #[fail(display = "AvroJsonError::NonFiniteNumber")]
+    NonFiniteNumber,
+}
+
+/// Reads a value of `schema` from its Avro JSON encoding:
+/// a non-null union value is an object with a single entry keyed by the branch's type name,
+/// `bytes` and `fixed` are strings of code-points 0-255,
+/// a record's missing fields are taken from their defaults.
+pub fn from_json(json: &JsonValue, schema: &Schema) -> Result<DataItem, AvroJsonError> {
+    let mismatch = || AvroJsonError::Mismatch {
+        expected: schema_kind(schema),
+    };
+
+    match (schema, json) {
+        (Schema::Null, JsonValue::Null) => Ok(DataItem::Null),
+        (Schema::Boolean, JsonValue::Bool(b)) => Ok(DataItem::Boolean(*b)),
+        (Schema::Int, JsonValue::Number(n)) => n
+            .as_i64()
+            .filter(|i| *i >= i64::from(i32::min_value()) && *i <= i64::from(i32::max_value()))
+            .map(|i| DataItem::Int(i as i32))
+            .ok_or_else(mismatch),
+        (Schema::Long, JsonValue::Number(n)) => n.as_i64().map(DataItem::Long).ok_or_else(mismatch),
+        (Schema::Float, JsonValue::Number(n)) => n
+            .as_f64()
+            .map(|f| DataItem::Float(f as f32))
+            .ok_or_else(mismatch),
+        (Schema::Double, JsonValue::Number(n)) => {
+            n.as_f64().map(DataItem::Double).ok_or_else(mismatch)
+        }
+        (Schema::Bytes, JsonValue::String(s)) => from_code_points(s)
+            .map(DataItem::Bytes)
+            .ok_or_else(mismatch),
+        (Schema::String, JsonValue::String(s)) => Ok(DataItem::String(s.to_owned())),
+        (Schema::Fixed { size, .. }, JsonValue::String(s)) => from_code_points(s)
+            .filter(|bytes| bytes.len() == *size)
+            .map(|bytes| DataItem::Fixed(*size, bytes))
+            .ok_or_else(mismatch),
+        (Schema::Enum { symbols, .. }, JsonValue::String(s)) => symbols
+            .iter()
+            .position(|symbol| symbol == s)
+            .map(|idx| DataItem::Enum(idx as i32, s.to_owned()))
+            .ok_or_else(|| AvroJsonError::UnknownSymbol(s.to_owned())),
+        (Schema::Array(items_schema), JsonValue::Array(items)) => items
+            .iter()
+            .map(|item| from_json(item, items_schema))
+            .collect::<Result<Vec<_>, _>>()
+            .map(DataItem::Array),
+        (Schema::Map(values_schema), JsonValue::Object(entries)) => entries
+            .iter()
+            .map(|(key, value)| from_json(value, values_schema).map(|v| (key.to_owned(), v)))
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map(DataItem::Map),
+
+        (Schema::Union(union_schema), JsonValue::Null) => union_schema
+            .variants()
+            .iter()
+            .find(|branch| **branch == Schema::Null)
+            .map(|_| DataItem::Union(Box::new(DataItem::Null)))
+            .ok_or_else(mismatch),
+        (Schema::Union(union_schema), JsonValue::Object(entries)) if entries.len() == 1 => {
+            let (type_name, value) = entries.iter().next().unwrap();
+            let branch = union_schema
+                .variants()
+                .iter()
+                .find(|branch| branch_name(branch) == *type_name)
+                .ok_or_else(|| AvroJsonError::UnknownUnionBranch(type_name.to_owned()))?;
+            from_json(value, branch).map(|value| DataItem::Union(Box::new(value)))
+        }
+
+        (Schema::Record { fields, .. }, JsonValue::Object(entries)) => fields
+            .iter()
+            .map(|field| {
+                let value = match (entries.get(&field.name), field.default.as_ref()) {
+                    (Some(value), _) => from_json(value, &field.schema),
+                    (None, Some(field_default)) => default_value(field_default, &field.schema)
+                        .map_err(|err| AvroJsonError::InvalidDefault(err)),
+                    (None, None) => Err(AvroJsonError::MissingField(field.name.to_owned())),
+                }?;
+                Ok((field.name.to_owned(), value))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(DataItem::Record),
+
+        (_, _) => Err(mismatch()),
+    }
+}
+
+/// Writes a value of `schema` in the Avro JSON encoding (see `from_json`).
+pub fn to_json(data_item: &DataItem, schema: &Schema) -> Result<JsonValue, AvroJsonError> {
+    let mismatch = || AvroJsonError::Mismatch {
+        expected: schema_kind(schema),
+    };
+
+    match (schema, data_item) {
+        (Schema::Union(union_schema), _) => {
+            let value = match data_item {
+                DataItem::Union(inner) => inner,
+                as_is => as_is,
+            };
+            let branch = union_schema
+                .variants()
+                .iter()
+                .find(|branch| value.validate(branch))
+                .ok_or_else(mismatch)?;
+            match branch {
+                Schema::Null => Ok(JsonValue::Null),
+                _ => {
+                    let mut entries = Map::new();
+                    entries.insert(branch_name(branch), to_json(value, branch)?);
+                    Ok(JsonValue::Object(entries))
+                }
+            }
+        }
+
+        (Schema::Null, DataItem::Null) => Ok(JsonValue::Null),
+        (Schema::Boolean, DataItem::Boolean(b)) => Ok(JsonValue::Bool(*b)),
+        (Schema::Int, DataItem::Int(i)) => Ok(JsonValue::from(*i)),
+        (Schema::Long, DataItem::Long(l)) => Ok(JsonValue::from(*l)),
+        (Schema::Float, DataItem::Float(f)) => Number::from_f64(f64::from(*f))
+            .map(JsonValue::Number)
+            .ok_or(AvroJsonError::NonFiniteNumber),
+        (Schema::Double, DataItem::Double(d)) => Number::from_f64(*d)
+            .map(JsonValue::Number)
+            .ok_or(AvroJsonError::NonFiniteNumber),
+        (Schema::Bytes, DataItem::Bytes(bytes))
+        | (Schema::Fixed { .. }, DataItem::Fixed(_, bytes)) => {
+            Ok(JsonValue::String(to_code_points(bytes)))
+        }
+        (Schema::String, DataItem::String(s)) | (Schema::Enum { .. }, DataItem::Enum(_, s)) => {
+            Ok(JsonValue::String(s.to_owned()))
+        }
+        (Schema::Array(items_schema), DataItem::Array(items)) => items
+            .iter()
+            .map(|item| to_json(item, items_schema))
+            .collect::<Result<Vec<_>, _>>()
+            .map(JsonValue::Array),
+        (Schema::Map(values_schema), DataItem::Map(entries)) => entries
+            .iter()
+            .map(|(key, value)| to_json(value, values_schema).map(|v| (key.to_owned(), v)))
+            .collect::<Result<Map<_, _>, _>>()
+            .map(JsonValue::Object),
+        (Schema::Record { fields, .. }, DataItem::Record(values)) => fields
+            .iter()
+            .map(|field| {
+                let value = values
+                    .iter()
+                    .find(|(name, _)| *name == field.name)
+                    .map(|(_, value)| value)
+                    .ok_or_else(|| AvroJsonError::MissingField(field.name.to_owned()))?;
+                to_json(value, &field.schema).map(|value| (field.name.to_owned(), value))
+            })
+            .collect::<Result<Map<_, _>, _>>()
+            .map(JsonValue::Object),
+
+        (_, _) => Err(mismatch()),
+    }
+}
+
+/// The key a union's branch goes by: the full name of a named type, the type itself otherwise.
+fn branch_name(branch: &Schema) -> String {
+    match branch {
+        Schema::Record { name, .. } | Schema::Enum { name, .. } | Schema::Fixed { name, .. } => {
+            name.fullname(None)
+        }
+        primitive => schema_kind(primitive).to_owned(),
+    }
+}
+
+/// `None` if there is a char above U+00FF, which cannot stand for a byte.
+fn from_code_points(s: &str) -> Option<Vec<u8>> {
+    s.chars()
+        .map(|c| {
+            Some(c as u32)
+                .filter(|code| *code <= 0xff)
+                .map(|code| code as u8)
+        })
+        .collect()
+}
+
+fn to_code_points(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| char::from(*b)).collect()
+}
+
+#[test]
+fn avro_json_test() {
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "event", "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "tag", "type": ["null", "string"]},
+            {"name": "payload", "type": "bytes"},
+            {"name": "retries", "type": "int", "default": 0}
+        ]}"#,
+    )
+    .unwrap();
+
+    let json = serde_json::json!({"id": 7, "tag": {"string": "a"}, "payload": "\u{00ff}"});
+    let data_item = from_json(&json, &schema).unwrap();
+    assert_eq!(
+        data_item,
+        DataItem::Record(vec![
+            ("id".to_owned(), DataItem::Long(7)),
+            (
+                "tag".to_owned(),
+                DataItem::Union(Box::new(DataItem::String("a".to_owned())))
+            ),
+            ("payload".to_owned(), DataItem::Bytes(vec![0xff])),
+            ("retries".to_owned(), DataItem::Int(0)),
+        ])
+    );
+    assert_eq!(
+        to_json(&data_item, &schema).unwrap(),
+        serde_json::json!({"id": 7, "tag": {"string": "a"}, "payload": "\u{00ff}", "retries": 0})
+    );
+    assert!(from_json(&serde_json::json!({"id": "7"}), &schema).is_err());
+    match from_json(
+        &serde_json::json!({"id": 7, "tag": null, "payload": "\u{0100}"}),
+        &schema,
+    ) {
+        Err(AvroJsonError::Mismatch { expected: "bytes" }) => (),
+        other => panic!("not a mismatch: {:?}", other),
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::json;
+pub use serde_json::Value as JsonValue;
+
+use super::Schema;
+
+/// Avro schema of a type, as serialized with serde.
+///
+/// Implemented for the primitives, `Option<T>` (as `["null", T]`), `Vec<T>` (as an array),
+/// `HashMap<String, T>` (as a map) and `Box<T>`.
+/// Records and enums get their implementation from the `avro_record!` and `avro_enum!` macros.
+pub trait AvroSchema {
+    /// The JSON representation of the schema.
+    /// Named types already present in `named` are referred to by their names.
+    fn avro_schema_json(named: &mut HashSet<String>) -> JsonValue;
+
+    fn avro_schema() -> Schema {
+        Schema::parse(&Self::avro_schema_json(&mut HashSet::new()))
+            .expect("AvroSchema::avro_schema_json produced an invalid schema")
+    }
+}
+
+macro_rules! impl_primitive {
+    ($ty:ty, $avro_type:expr) => {
+        impl AvroSchema for $ty {
+            fn avro_schema_json(_named: &mut HashSet<String>) -> JsonValue {
+                JsonValue::String($avro_type.to_owned())
+            }
+        }
+    };
+}
+
+impl_primitive!((), "null");
+impl_primitive!(bool, "boolean");
+impl_primitive!(i32, "int");
+impl_primitive!(i64, "long");
+impl_primitive!(f32, "float");
+impl_primitive!(f64, "double");
+impl_primitive!(String, "string");
+
+impl<T: AvroSchema> AvroSchema for Option<T> {
+    fn avro_schema_json(named: &mut HashSet<String>) -> JsonValue {
+        JsonValue::Array(vec![
+            JsonValue::String("null".to_owned()),
+            T::avro_schema_json(named),
+        ])
+    }
+}
+
+impl<T: AvroSchema> AvroSchema for Vec<T> {
+    fn avro_schema_json(named: &mut HashSet<String>) -> JsonValue {
+        json!({"type": "array", "items": T::avro_schema_json(named)})
+    }
+}
+
+impl<T: AvroSchema> AvroSchema for HashMap<String, T> {
+    fn avro_schema_json(named: &mut HashSet<String>) -> JsonValue {
+        json!({"type": "map", "values": T::avro_schema_json(named)})
+    }
+}
+
+impl<T: AvroSchema> AvroSchema for Box<T> {
+    fn avro_schema_json(named: &mut HashSet<String>) -> JsonValue {
+        T::avro_schema_json(named)
+    }
+}
+
+#[doc(hidden)]
+pub fn record_json(name: &str, fields: Vec<(&str, JsonValue)>) -> JsonValue {
+    let fields = fields
+        .into_iter()
+        .map(|(field_name, field_type)| json!({"name": field_name, "type": field_type}))
+        .collect::<Vec<_>>();
+    json!({"type": "record", "name": name, "fields": fields})
+}
+
+#[doc(hidden)]
+pub fn enum_json(name: &str, symbols: Vec<&str>) -> JsonValue {
+    json!({"type": "enum", "name": name, "symbols": symbols})
+}
+
+/// Defines a struct along with its `AvroSchema` implementation (an Avro record named after the struct).
+///
+/// ```ignore
+/// avro_record! {
+///     #[derive(Serialize, Deserialize)]
+///     pub struct Point {
+///         pub x: i32,
+///         pub y: i32,
+///     }
+/// }
+/// ```
+///
+/// Serde attributes renaming the fields are not taken into account.
+#[macro_export]
+macro_rules! avro_record {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $field_ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field : $field_ty
+            ),*
+        }
+
+        impl $crate::protocol::AvroSchema for $name {
+            fn avro_schema_json(
+                named: &mut ::std::collections::HashSet<String>,
+            ) -> $crate::protocol::avro_schema::JsonValue {
+                let name = stringify!($name);
+                if !named.insert(name.to_owned()) {
+                    return $crate::protocol::avro_schema::JsonValue::String(name.to_owned());
+                }
+                $crate::protocol::avro_schema::record_json(
+                    name,
+                    vec![$(
+                        (
+                            stringify!($field),
+                            <$field_ty as $crate::protocol::AvroSchema>::avro_schema_json(named),
+                        )
+                    ),*],
+                )
+            }
+        }
+    };
+}
+
+/// Defines a fieldless enum along with its `AvroSchema` implementation (an Avro enum named after the type).
+#[macro_export]
+macro_rules! avro_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant
+            ),*
+        }
+
+        impl $crate::protocol::AvroSchema for $name {
+            fn avro_schema_json(
+                named: &mut ::std::collections::HashSet<String>,
+            ) -> $crate::protocol::avro_schema::JsonValue {
+                let name = stringify!($name);
+                if !named.insert(name.to_owned()) {
+                    return $crate::protocol::avro_schema::JsonValue::String(name.to_owned());
+                }
+                $crate::protocol::avro_schema::enum_json(name, vec![$(stringify!($variant)),*])
+            }
+        }
+    };
+}
+
+#[test]
+fn avro_schema_test() {
+    avro_enum! {
+        #[allow(dead_code)]
+        enum Color {
+            Red,
+            Green,
+        }
+    }
+    avro_record! {
+        #[allow(dead_code)]
+        struct Point {
+            x: i32,
+            y: Option<i64>,
+        }
+    }
+    avro_record! {
+        #[allow(dead_code)]
+        struct Shape {
+            color: Color,
+            vertices: Vec<Point>,
+            origin: Point,
+            tags: HashMap<String, String>,
+        }
+    }
+
+    let expected = Schema::parse(&json!({
+        "type": "record",
+        "name": "Shape",
+        "fields": [
+            {"name": "color", "type": {"type": "enum", "name": "Color", "symbols": ["Red", "Green"]}},
+            {"name": "vertices", "type": {"type": "array", "items": {
+                "type": "record",
+                "name": "Point",
+                "fields": [
+                    {"name": "x", "type": "int"},
+                    {"name": "y", "type": ["null", "long"]}
+                ]
+            }}},
+            {"name": "origin", "type": "Point"},
+            {"name": "tags", "type": {"type": "map", "values": "string"}}
+        ]
+    }))
+    .unwrap();
+
+    assert_eq!(Shape::avro_schema(), expected);
+}
@@ -6,7 +6,7 @@ pub enum ConsumerMessage {
     Cancel,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ProducerMessage {
     Push { items: Vec<Vec<u8>> },
     Complete,
@@ -1,7 +1,11 @@
+pub mod avro_json;
+pub mod avro_schema;
 pub mod command;
 pub mod messages;
 pub mod streams;
+pub mod transcode;
 
+pub use avro_schema::AvroSchema;
 pub use command::Command;
 
 pub use avro_rs::types::Value as DataItem;
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use serde_json::Value as JsonValue;
+
+use crate::protocol::{DataItem, Schema};
+
+use super::TranscodeError;
+
+/// Converts the JSON default of a record field into a `DataItem` of the field's schema.
+/// Follows the Avro specification: a union's default corresponds to its first branch,
+/// `bytes` and `fixed` defaults are strings of code-points 0-255.
+pub fn default_value(json: &JsonValue, schema: &Schema) -> Result<DataItem, TranscodeError> {
+    let invalid = || TranscodeError::InvalidDefault(json.to_string());
+
+    match (schema, json) {
+        (Schema::Null, JsonValue::Null) => Ok(DataItem::Null),
+        (Schema::Boolean, JsonValue::Bool(b)) => Ok(DataItem::Boolean(*b)),
+        (Schema::Int, JsonValue::Number(n)) => n
+            .as_i64()
+            .map(|i| DataItem::Int(i as i32))
+            .ok_or_else(invalid),
+        (Schema::Long, JsonValue::Number(n)) => {
+            n.as_i64().map(|i| DataItem::Long(i)).ok_or_else(invalid)
+        }
+        (Schema::Float, JsonValue::Number(n)) => n
+            .as_f64()
+            .map(|f| DataItem::Float(f as f32))
+            .ok_or_else(invalid),
+        (Schema::Double, JsonValue::Number(n)) => {
+            n.as_f64().map(|f| DataItem::Double(f)).ok_or_else(invalid)
+        }
+        (Schema::Bytes, JsonValue::String(s)) => Ok(DataItem::Bytes(code_points(s))),
+        (Schema::String, JsonValue::String(s)) => Ok(DataItem::String(s.to_owned())),
+        (Schema::Fixed { size, .. }, JsonValue::String(s)) => {
+            let bytes = code_points(s);
+            if bytes.len() == *size {
+                Ok(DataItem::Fixed(*size, bytes))
+            } else {
+                Err(invalid())
+            }
+        }
+        (Schema::Enum { symbols, .. }, JsonValue::String(s)) => symbols
+            .iter()
+            .position(|symbol| symbol == s)
+            .map(|idx| DataItem::Enum(idx as i32, s.to_owned()))
+            .ok_or_else(invalid),
+        (Schema::Array(items_schema), JsonValue::Array(items)) => items
+            .iter()
+            .map(|item| default_value(item, items_schema))
+            .collect::<Result<Vec<_>, _>>()
+            .map(DataItem::Array),
+        (Schema::Map(values_schema), JsonValue::Object(entries)) => entries
+            .iter()
+            .map(|(key, value)| default_value(value, values_schema).map(|v| (key.to_owned(), v)))
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map(DataItem::Map),
+        (Schema::Union(union_schema), _) => union_schema
+            .variants()
+            .first()
+            .ok_or_else(invalid)
+            .and_then(|first_variant| default_value(json, first_variant))
+            .map(|value| DataItem::Union(Box::new(value))),
+        (Schema::Record { fields, .. }, JsonValue::Object(entries)) => fields
+            .iter()
+            .map(|field| {
+                let value = match (entries.get(&field.name), field.default.as_ref()) {
+                    (Some(value), _) => default_value(value, &field.schema),
+                    (None, Some(field_default)) => default_value(field_default, &field.schema),
+                    (None, None) => Err(TranscodeError::MissingField(field.name.to_owned())),
+                }?;
+                Ok((field.name.to_owned(), value))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(DataItem::Record),
+
+        (_, _) => Err(invalid()),
+    }
+}
+
+fn code_points(s: &str) -> Vec<u8> {
+    s.chars().map(|c| c as u32 as u8).collect()
+}
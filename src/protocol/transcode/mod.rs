@@ -0,0 +1,11 @@
+mod transcode_error;
+pub use transcode_error::TranscodeError;
+
+mod default_value;
+pub use default_value::default_value;
+
+mod resolve;
+pub use resolve::{resolve, schema_kind};
+
+mod transcoder;
+pub use transcoder::Transcoder;
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use avro_rs::schema::RecordField;
+
+use crate::protocol::{DataItem, Schema};
+
+use super::{default_value, TranscodeError};
+
+/// Converts a value written with `writer` schema into a value of `reader` schema
+/// following the Avro schema resolution rules:
+/// - numeric promotions (`int` → `long` → `float` → `double`), `string` ↔ `bytes`;
+/// - record fields are matched by name, reordered, and missing ones are taken from defaults;
+/// - enum symbols are matched by name;
+/// - union branches are chosen by the first matching reader branch.
+///
+/// Unlike the specification, record names are not required to match.
+pub fn resolve(
+    value: DataItem,
+    writer: &Schema,
+    reader: &Schema,
+) -> Result<DataItem, TranscodeError> {
+    match (writer, reader) {
+        (Schema::Union(writer_union), _) => {
+            let value = match value {
+                DataItem::Union(inner) => *inner,
+                as_is => as_is,
+            };
+            let writer_branch = writer_union
+                .variants()
+                .iter()
+                .find(|branch| value.validate(branch))
+                .ok_or(TranscodeError::UnionBranchNotFound)?;
+            resolve(value, writer_branch, reader)
+        }
+
+        (_, Schema::Union(reader_union)) => {
+            let branches = reader_union.variants();
+            let reader_branch = branches
+                .iter()
+                .find(|branch| same_name(writer, branch))
+                .or_else(|| branches.iter().find(|branch| matches(writer, branch)))
+                .ok_or_else(|| incompatible(writer, reader))?;
+            resolve(value, writer, reader_branch).map(|value| DataItem::Union(Box::new(value)))
+        }
+
+        (Schema::Null, Schema::Null)
+        | (Schema::Boolean, Schema::Boolean)
+        | (Schema::Int, Schema::Int)
+        | (Schema::Long, Schema::Long)
+        | (Schema::Float, Schema::Float)
+        | (Schema::Double, Schema::Double)
+        | (Schema::Bytes, Schema::Bytes)
+        | (Schema::String, Schema::String) => Ok(value),
+
+        (Schema::Int, Schema::Long) => match value {
+            DataItem::Int(i) => Ok(DataItem::Long(i as i64)),
+            _ => Err(unexpected(writer)),
+        },
+        (Schema::Int, Schema::Float) => match value {
+            DataItem::Int(i) => Ok(DataItem::Float(i as f32)),
+            _ => Err(unexpected(writer)),
+        },
+        (Schema::Int, Schema::Double) => match value {
+            DataItem::Int(i) => Ok(DataItem::Double(i as f64)),
+            _ => Err(unexpected(writer)),
+        },
+        (Schema::Long, Schema::Float) => match value {
+            DataItem::Long(l) => Ok(DataItem::Float(l as f32)),
+            _ => Err(unexpected(writer)),
+        },
+        (Schema::Long, Schema::Double) => match value {
+            DataItem::Long(l) => Ok(DataItem::Double(l as f64)),
+            _ => Err(unexpected(writer)),
+        },
+        (Schema::Float, Schema::Double) => match value {
+            DataItem::Float(f) => Ok(DataItem::Double(f as f64)),
+            _ => Err(unexpected(writer)),
+        },
+        (Schema::String, Schema::Bytes) => match value {
+            DataItem::String(s) => Ok(DataItem::Bytes(s.into_bytes())),
+            _ => Err(unexpected(writer)),
+        },
+        (Schema::Bytes, Schema::String) => match value {
+            DataItem::Bytes(b) => String::from_utf8(b)
+                .map(DataItem::String)
+                .map_err(|err| TranscodeError::InvalidUtf8(err)),
+            _ => Err(unexpected(writer)),
+        },
+
+        (Schema::Array(writer_items), Schema::Array(reader_items)) => match value {
+            DataItem::Array(items) => items
+                .into_iter()
+                .map(|item| resolve(item, writer_items, reader_items))
+                .collect::<Result<Vec<_>, _>>()
+                .map(DataItem::Array),
+            _ => Err(unexpected(writer)),
+        },
+
+        (Schema::Map(writer_values), Schema::Map(reader_values)) => match value {
+            DataItem::Map(entries) => entries
+                .into_iter()
+                .map(|(key, value)| {
+                    resolve(value, writer_values, reader_values).map(|value| (key, value))
+                })
+                .collect::<Result<HashMap<_, _>, _>>()
+                .map(DataItem::Map),
+            _ => Err(unexpected(writer)),
+        },
+
+        (Schema::Enum { .. }, Schema::Enum { symbols, .. }) => match value {
+            DataItem::Enum(_, symbol) => symbols
+                .iter()
+                .position(|s| *s == symbol)
+                .map(|idx| DataItem::Enum(idx as i32, symbol.to_owned()))
+                .ok_or(TranscodeError::UnknownSymbol(symbol)),
+            _ => Err(unexpected(writer)),
+        },
+
+        (
+            Schema::Fixed {
+                size: writer_size, ..
+            },
+            Schema::Fixed {
+                size: reader_size, ..
+            },
+        ) if writer_size == reader_size => Ok(value),
+
+        (
+            Schema::Record {
+                fields: writer_fields,
+                ..
+            },
+            Schema::Record {
+                fields: reader_fields,
+                ..
+            },
+        ) => resolve_record(value, writer_fields, reader_fields),
+
+        (_, _) => Err(incompatible(writer, reader)),
+    }
+}
+
+fn resolve_record(
+    value: DataItem,
+    writer_fields: &[RecordField],
+    reader_fields: &[RecordField],
+) -> Result<DataItem, TranscodeError> {
+    let mut written = match value {
+        DataItem::Record(entries) => entries.into_iter().collect::<HashMap<_, _>>(),
+        _ => return Err(TranscodeError::UnexpectedValue { expected: "record" }),
+    };
+
+    reader_fields
+        .iter()
+        .map(|reader_field| {
+            let writer_field = writer_fields
+                .iter()
+                .find(|writer_field| writer_field.name == reader_field.name);
+            let value = match (written.remove(&reader_field.name), writer_field) {
+                (Some(value), Some(writer_field)) => {
+                    resolve(value, &writer_field.schema, &reader_field.schema)
+                }
+                (_, _) => match reader_field.default {
+                    Some(ref field_default) => default_value(field_default, &reader_field.schema),
+                    None => Err(TranscodeError::MissingField(reader_field.name.to_owned())),
+                },
+            }?;
+            Ok((reader_field.name.to_owned(), value))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(DataItem::Record)
+}
+
+/// Whether a value of `writer` schema can be resolved into `reader` schema.
+fn matches(writer: &Schema, reader: &Schema) -> bool {
+    match (writer, reader) {
+        (Schema::Int, Schema::Long)
+        | (Schema::Int, Schema::Float)
+        | (Schema::Int, Schema::Double)
+        | (Schema::Long, Schema::Float)
+        | (Schema::Long, Schema::Double)
+        | (Schema::Float, Schema::Double)
+        | (Schema::String, Schema::Bytes)
+        | (Schema::Bytes, Schema::String) => true,
+        (_, _) => schema_kind(writer) == schema_kind(reader),
+    }
+}
+
+fn same_name(writer: &Schema, reader: &Schema) -> bool {
+    match (writer, reader) {
+        (Schema::Record { name: w, .. }, Schema::Record { name: r, .. })
+        | (Schema::Enum { name: w, .. }, Schema::Enum { name: r, .. })
+        | (Schema::Fixed { name: w, .. }, Schema::Fixed { name: r, .. }) => w.name == r.name,
+        (_, _) => false,
+    }
+}
+
+pub fn schema_kind(schema: &Schema) -> &'static str {
+    match schema {
+        Schema::Null => "null",
+        Schema::Boolean => "boolean",
+        Schema::Int => "int",
+        Schema::Long => "long",
+        Schema::Float => "float",
+        Schema::Double => "double",
+        Schema::Bytes => "bytes",
+        Schema::String => "string",
+        Schema::Array(_) => "array",
+        Schema::Map(_) => "map",
+        Schema::Union(_) => "union",
+        Schema::Record { .. } => "record",
+        Schema::Enum { .. } => "enum",
+        Schema::Fixed { .. } => "fixed",
+    }
+}
+
+fn incompatible(writer: &Schema, reader: &Schema) -> TranscodeError {
+    TranscodeError::Incompatible {
+        writer: schema_kind(writer),
+        reader: schema_kind(reader),
+    }
+}
+
+fn unexpected(writer: &Schema) -> TranscodeError {
+    TranscodeError::UnexpectedValue {
+        expected: schema_kind(writer),
+    }
+}
+
+#[test]
+fn resolve_record_test() {
+    let writer = Schema::parse_str(
+        r#"{"type": "record", "name": "w", "fields": [
+            {"name": "b", "type": "int"},
+            {"name": "a", "type": "string"},
+            {"name": "dropped", "type": "boolean"}
+        ]}"#,
+    )
+    .unwrap();
+    let reader = Schema::parse_str(
+        r#"{"type": "record", "name": "r", "fields": [
+            {"name": "a", "type": "bytes"},
+            {"name": "b", "type": ["null", "long"]},
+            {"name": "c", "type": "double", "default": 1.5}
+        ]}"#,
+    )
+    .unwrap();
+
+    let written = DataItem::Record(vec![
+        ("b".to_owned(), DataItem::Int(42)),
+        ("a".to_owned(), DataItem::String("hi".to_owned())),
+        ("dropped".to_owned(), DataItem::Boolean(true)),
+    ]);
+    let resolved = resolve(written, &writer, &reader).unwrap();
+
+    assert_eq!(
+        resolved,
+        DataItem::Record(vec![
+            ("a".to_owned(), DataItem::Bytes(b"hi".to_vec())),
+            (
+                "b".to_owned(),
+                DataItem::Union(Box::new(DataItem::Long(42)))
+            ),
+            ("c".to_owned(), DataItem::Double(1.5)),
+        ])
+    );
+    assert!(resolved.validate(&reader));
+}
@@ -0,0 +1,35 @@
+#[derive(Fail, Debug)]
+pub enum TranscodeError {
+    #[fail(display = "TranscodeError::DecodeError")]
+    DecodeError(#[cause] failure::Error),
+
+    #[fail(display = "TranscodeError::EncodeError")]
+    EncodeError(#[cause] failure::Error),
+
+    #[fail(
+        display = "TranscodeError::Incompatible [writer: {}; reader: {}]",
+        writer, reader
+    )]
+    Incompatible {
+        writer: &'static str,
+        reader: &'static str,
+    },
+
+    #[fail(display = "TranscodeError::UnexpectedValue [expected: {}]", expected)]
+    UnexpectedValue { expected: &'static str },
+
+    #[fail(display = "TranscodeError::UnionBranchNotFound")]
+    UnionBranchNotFound,
+
+    #[fail(display = "TranscodeError::MissingField: {}", _0)]
+    MissingField(String),
+
+    #[fail(display = "TranscodeError::UnknownSymbol: {}", _0)]
+    UnknownSymbol(String),
+
+    #[fail(display = "TranscodeError::InvalidDefault: {}", _0)]
+    InvalidDefault(String),
+
+    #[fail(display = "TranscodeError::InvalidUtf8")]
+    InvalidUtf8(#[cause] std::string::FromUtf8Error),
+}
@@ -0,0 +1,55 @@
+use bytes::IntoBuf;
+
+use crate::protocol::messages::ProducerMessage;
+use crate::protocol::Schema;
+
+use super::{resolve, TranscodeError};
+
+/// Re-encodes datums written with the writer's schema so that they can be read with the reader's schema.
+#[derive(Debug, Clone)]
+pub struct Transcoder {
+    writer_schema: Schema,
+    reader_schema: Schema,
+    is_identity: bool,
+}
+
+impl Transcoder {
+    pub fn new(writer_schema: &Schema, reader_schema: &Schema) -> Self {
+        let is_identity = writer_schema.canonical_form() == reader_schema.canonical_form();
+        Self {
+            writer_schema: writer_schema.clone(),
+            reader_schema: reader_schema.clone(),
+            is_identity,
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.is_identity
+    }
+
+    pub fn transcode(&self, datum: Vec<u8>) -> Result<Vec<u8>, TranscodeError> {
+        if self.is_identity {
+            return Ok(datum);
+        }
+
+        let written = avro_rs::from_avro_datum(&self.writer_schema, &mut datum.into_buf(), None)
+            .map_err(|err| TranscodeError::DecodeError(err))?;
+        let resolved = resolve(written, &self.writer_schema, &self.reader_schema)?;
+        avro_rs::to_avro_datum(&self.reader_schema, resolved)
+            .map_err(|err| TranscodeError::EncodeError(err))
+    }
+
+    pub fn transcode_message(
+        &self,
+        message: ProducerMessage,
+    ) -> Result<ProducerMessage, TranscodeError> {
+        match message {
+            ProducerMessage::Push { items } => items
+                .into_iter()
+                .map(|item| self.transcode(item))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|items| ProducerMessage::Push { items }),
+            passed_through => Ok(passed_through),
+        }
+    }
+}
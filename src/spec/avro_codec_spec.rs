@@ -0,0 +1,25 @@
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "avro_codec")]
+pub enum AvroCodecSpec {
+    #[serde(rename = "null")]
+    Null,
+
+    #[serde(rename = "deflate")]
+    Deflate,
+}
+
+impl AvroCodecSpec {
+    /// The name the codec goes by in a container file's header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AvroCodecSpec::Null => "null",
+            AvroCodecSpec::Deflate => "deflate",
+        }
+    }
+}
+
+impl Default for AvroCodecSpec {
+    fn default() -> Self {
+        AvroCodecSpec::Null
+    }
+}
@@ -0,0 +1,36 @@
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "balance_mode")]
+pub enum BalanceModeSpec {
+    /// The outlets take the pushes in turn; an outlet that has not pulled yet holds the others up.
+    #[serde(rename = "round_robin")]
+    RoundRobin,
+
+    /// A push goes to whichever outlet has pulled.
+    #[serde(rename = "demand")]
+    Demand,
+}
+
+/// What happens to the rest of the stage when one of its outlets stops.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "balance_outlet_policy")]
+pub enum BalanceOutletPolicySpec {
+    /// Cancel the upstream and stop the remaining outlets.
+    #[serde(rename = "shutdown")]
+    Shutdown,
+
+    /// Keep serving the remaining outlets; the upstream is cancelled once none are left.
+    #[serde(rename = "continue")]
+    Continue,
+}
+
+impl Default for BalanceModeSpec {
+    fn default() -> Self {
+        BalanceModeSpec::Demand
+    }
+}
+
+impl Default for BalanceOutletPolicySpec {
+    fn default() -> Self {
+        BalanceOutletPolicySpec::Shutdown
+    }
+}
@@ -0,0 +1,24 @@
+/// What the `buffer` std stage does with the items pushed into it when it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "buffer_overflow")]
+pub enum BufferOverflowSpec {
+    /// Do not pull upstream until there is room.
+    #[serde(rename = "backpressure")]
+    Backpressure,
+
+    #[serde(rename = "drop_oldest")]
+    DropOldest,
+
+    #[serde(rename = "drop_newest")]
+    DropNewest,
+
+    /// Fail the downstream and cancel the upstream.
+    #[serde(rename = "fail")]
+    Fail,
+}
+
+impl Default for BufferOverflowSpec {
+    fn default() -> Self {
+        BufferOverflowSpec::Backpressure
+    }
+}
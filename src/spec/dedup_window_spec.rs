@@ -0,0 +1,25 @@
+/// Which keys the `dedup` std stage remembers; the least recently seen keys are forgotten first.
+///
+/// The keys are remembered by their 64-bit hashes (which tell apart the keys' types and lengths), so
+/// an item whose distinct key happens to collide with a remembered one's hash is dropped, though
+/// such collisions are rare.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "dedup_window")]
+pub enum DedupWindowSpec {
+    /// The last `count` keys.
+    #[serde(rename = "last")]
+    Last { count: usize },
+
+    /// The keys first seen within the last `ttl_ms` milliseconds, no more than `max_keys` of them.
+    /// Seeing a key again doesn't postpone its expiry.
+    #[serde(rename = "ttl")]
+    Ttl {
+        ttl_ms: u64,
+        #[serde(default = "default_dedup_max_keys")]
+        max_keys: usize,
+    },
+}
+
+fn default_dedup_max_keys() -> usize {
+    100_000
+}
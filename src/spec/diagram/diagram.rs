@@ -0,0 +1,295 @@
+use crate::protocol::Schema;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeShape {
+    OsProcess,
+    StdStage,
+    Inlet,
+    Outlet,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    pub id: String,
+    pub label: String,
+    pub shape: NodeShape,
+}
+
+#[derive(Debug)]
+pub struct Cluster {
+    pub id: String,
+    pub label: String,
+    pub nodes: Vec<Node>,
+    pub clusters: Vec<Cluster>,
+}
+
+#[derive(Debug)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub label: String,
+}
+
+/// Intermediate representation shared by the renderers.
+/// The top-level graph's vertices live in `root`; it is not drawn as a cluster itself.
+#[derive(Debug)]
+pub struct Diagram {
+    pub root: Cluster,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Inlet,
+    Outlet,
+}
+
+impl Diagram {
+    pub fn from_graph_spec(graph_spec: &GraphSpec) -> Self {
+        let inlet_names = (0..graph_spec.inlets.len())
+            .map(|idx| format!("inlet #{}", idx))
+            .collect::<Vec<_>>();
+        let outlet_names = (0..graph_spec.outlets.len())
+            .map(|idx| format!("outlet #{}", idx))
+            .collect::<Vec<_>>();
+
+        let mut edges = Vec::new();
+        let root = build_cluster(
+            graph_spec,
+            "v",
+            "".to_owned(),
+            &inlet_names,
+            &outlet_names,
+            &mut edges,
+        );
+
+        Self { root, edges }
+    }
+}
+
+fn build_cluster(
+    graph_spec: &GraphSpec,
+    id: &str,
+    label: String,
+    inlet_names: &[String],
+    outlet_names: &[String],
+    edges: &mut Vec<Edge>,
+) -> Cluster {
+    let mut nodes = Vec::new();
+    let mut clusters = Vec::new();
+
+    for (idx, (port_spec, name)) in graph_spec.inlets.iter().zip(inlet_names).enumerate() {
+        let boundary_id = boundary_id(id, Direction::Inlet, idx);
+        nodes.push(Node {
+            id: boundary_id.to_owned(),
+            label: name.to_owned(),
+            shape: NodeShape::Inlet,
+        });
+        if let Some(to) = endpoint(graph_spec, id, port_spec, Direction::Inlet) {
+            edges.push(Edge {
+                from: boundary_id,
+                to,
+                label: format!("{} -> {}", name, port_spec.port),
+            });
+        }
+    }
+
+    let mut vertex_names = graph_spec.vertices.keys().collect::<Vec<_>>();
+    vertex_names.sort();
+
+    for vertex_name in vertex_names {
+        let vertex_spec = &graph_spec.vertices[vertex_name];
+        let vertex_id = vertex_id(id, vertex_name);
+
+        match vertex_spec.run {
+            RunSpec::OsProcess { ref cmd, .. } => nodes.push(Node {
+                id: vertex_id,
+                label: format!("{}\n{}", vertex_name, cmd.join(" ")),
+                shape: NodeShape::OsProcess,
+            }),
+            RunSpec::StdStage(ref std_stage_spec) => nodes.push(Node {
+                id: vertex_id,
+                label: format!("{}\nstd: {}", vertex_name, std_stage_name(std_stage_spec)),
+                shape: NodeShape::StdStage,
+            }),
+            RunSpec::Graph(ref subgraph_spec) => clusters.push(build_cluster(
+                subgraph_spec,
+                &vertex_id,
+                vertex_name.to_owned(),
+                &vertex_spec.inlets,
+                &vertex_spec.outlets,
+                edges,
+            )),
+        }
+    }
+
+    for (idx, (port_spec, name)) in graph_spec.outlets.iter().zip(outlet_names).enumerate() {
+        let boundary_id = boundary_id(id, Direction::Outlet, idx);
+        nodes.push(Node {
+            id: boundary_id.to_owned(),
+            label: name.to_owned(),
+            shape: NodeShape::Outlet,
+        });
+        if let Some(from) = endpoint(graph_spec, id, port_spec, Direction::Outlet) {
+            edges.push(Edge {
+                from,
+                to: boundary_id,
+                label: format!("{} -> {}", port_spec.port, name),
+            });
+        }
+    }
+
+    for edge_spec in graph_spec.edges.iter() {
+        let from = endpoint(graph_spec, id, &edge_spec.producer, Direction::Outlet);
+        let to = endpoint(graph_spec, id, &edge_spec.consumer, Direction::Inlet);
+        if let (Some(from), Some(to)) = (from, to) {
+            edges.push(Edge {
+                from,
+                to,
+                label: format!(
+                    "{} -> {}\n{}",
+                    edge_spec.producer.port,
+                    edge_spec.consumer.port,
+                    schema_summary(&edge_spec.schema)
+                ),
+            });
+        }
+    }
+
+    Cluster {
+        id: id.to_owned(),
+        label,
+        nodes,
+        clusters,
+    }
+}
+
+/// The id of the node an edge attached to the given port should be drawn to.
+/// Ports of nested graphs are drawn as boundary nodes inside of their clusters.
+fn endpoint(
+    graph_spec: &GraphSpec,
+    id: &str,
+    port_spec: &PortSpec,
+    direction: Direction,
+) -> Option<String> {
+    let vertex_spec = graph_spec.vertices.get(&port_spec.vertex)?;
+    let vertex_id = vertex_id(id, &port_spec.vertex);
+
+    match vertex_spec.run {
+        RunSpec::Graph(_) => {
+            let ports = match direction {
+                Direction::Inlet => &vertex_spec.inlets,
+                Direction::Outlet => &vertex_spec.outlets,
+            };
+            ports
+                .iter()
+                .position(|port| *port == port_spec.port)
+                .map(|idx| boundary_id(&vertex_id, direction, idx))
+        }
+        _ => Some(vertex_id),
+    }
+}
+
+/// Keeps the ASCII alphanumerics of the name and escapes any other character as `_` followed by
+/// six hex digits of its code point, so that distinct names never share an id.
+/// An escaped name never contains `__`, which separates the nesting levels.
+fn vertex_id(parent_id: &str, vertex_name: &str) -> String {
+    let escaped = vertex_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_string()
+            } else {
+                format!("_{:06x}", c as u32)
+            }
+        })
+        .collect::<String>();
+    format!("{}__{}", parent_id, escaped)
+}
+
+/// The `_` following the separator is not followed by hex digits, unlike in an escaped vertex name.
+fn boundary_id(cluster_id: &str, direction: Direction, idx: usize) -> String {
+    match direction {
+        Direction::Inlet => format!("{}___inlet_{}", cluster_id, idx),
+        Direction::Outlet => format!("{}___outlet_{}", cluster_id, idx),
+    }
+}
+
+fn std_stage_name(std_stage_spec: &StdStageSpec) -> String {
+    if let StdStageSpec::Custom { ref name, .. } = std_stage_spec {
+        return format!("custom {}", name);
+    }
+
+    match serde_json::to_value(std_stage_spec) {
+        Ok(serde_json::Value::Object(ref map)) if map.len() == 1 => {
+            map.keys().next().expect("map.len() == 1").to_owned()
+        }
+        Ok(serde_json::Value::String(name)) => name,
+        _ => "?".to_owned(),
+    }
+}
+
+fn schema_summary(json: &serde_json::Value) -> String {
+    Schema::parse(json)
+        .map(|schema| summarize(&schema))
+        .unwrap_or_else(|_| "<invalid schema>".to_owned())
+}
+
+fn summarize(schema: &Schema) -> String {
+    match schema {
+        Schema::Null => "null".to_owned(),
+        Schema::Boolean => "boolean".to_owned(),
+        Schema::Int => "int".to_owned(),
+        Schema::Long => "long".to_owned(),
+        Schema::Float => "float".to_owned(),
+        Schema::Double => "double".to_owned(),
+        Schema::Bytes => "bytes".to_owned(),
+        Schema::String => "string".to_owned(),
+        Schema::Array(items) => format!("array<{}>", summarize(items)),
+        Schema::Map(values) => format!("map<{}>", summarize(values)),
+        Schema::Union(union_schema) => union_schema
+            .variants()
+            .iter()
+            .map(summarize)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Schema::Record { name, .. } => format!("record {}", name.name),
+        Schema::Enum { name, .. } => format!("enum {}", name.name),
+        Schema::Fixed { name, size } => format!("fixed {}[{}]", name.name, size),
+    }
+}
+
+/// A graph of two vertices whose names only differ in a punctuation character, one of them nested.
+#[cfg(test)]
+pub fn example_graph_spec() -> GraphSpec {
+    serde_yaml::from_str(
+        r#"
+vertices:
+  a-b:
+    run: {os_process: {cmd: ["cat"]}}
+    inlets: [in]
+    outlets: [out]
+  a_b:
+    run:
+      graph:
+        vertices:
+          tee:
+            run: {std: {tee: {schema: "string", outlets_count: 1}}}
+            inlets: [in]
+            outlets: [out]
+        inlets: [{vertex: tee, port: in}]
+        outlets: [{vertex: tee, port: out}]
+    inlets: [in]
+    outlets: [out]
+edges:
+  - producer: {vertex: a-b, port: out}
+    consumer: {vertex: a_b, port: in}
+    schema: "string"
+inlets: [{vertex: a-b, port: in}]
+outlets: [{vertex: a_b, port: out}]
+"#,
+    )
+    .unwrap()
+}
@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use super::diagram::*;
+
+pub fn render(diagram: &Diagram) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "digraph {{").unwrap();
+    writeln!(out, "  rankdir=LR;").unwrap();
+    render_cluster_body(&mut out, &diagram.root, 1);
+    for edge in diagram.edges.iter() {
+        writeln!(
+            out,
+            "  {} -> {} [label=\"{}\"];",
+            edge.from,
+            edge.to,
+            escape(&edge.label)
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+fn render_cluster_body(out: &mut String, cluster: &Cluster, depth: usize) {
+    let indent = "  ".repeat(depth);
+
+    for node in cluster.nodes.iter() {
+        let attrs = match node.shape {
+            NodeShape::OsProcess => "shape=box",
+            NodeShape::StdStage => "shape=hexagon",
+            NodeShape::Inlet => "shape=invhouse, style=dashed",
+            NodeShape::Outlet => "shape=house, style=dashed",
+        };
+        writeln!(
+            out,
+            "{}{} [label=\"{}\", {}];",
+            indent,
+            node.id,
+            escape(&node.label),
+            attrs
+        )
+        .unwrap();
+    }
+
+    for subcluster in cluster.clusters.iter() {
+        writeln!(out, "{}subgraph cluster_{} {{", indent, subcluster.id).unwrap();
+        writeln!(out, "{}  label=\"{}\";", indent, escape(&subcluster.label)).unwrap();
+        render_cluster_body(out, subcluster, depth + 1);
+        writeln!(out, "{}}}", indent).unwrap();
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[test]
+fn dot_test() {
+    let diagram = Diagram::from_graph_spec(&example_graph_spec());
+
+    assert_eq!(
+        render(&diagram),
+        r#"digraph {
+  rankdir=LR;
+  v___inlet_0 [label="inlet #0", shape=invhouse, style=dashed];
+  v__a_00002db [label="a-b\ncat", shape=box];
+  v___outlet_0 [label="outlet #0", shape=house, style=dashed];
+  subgraph cluster_v__a_00005fb {
+    label="a_b";
+    v__a_00005fb___inlet_0 [label="in", shape=invhouse, style=dashed];
+    v__a_00005fb__tee [label="tee\nstd: tee", shape=hexagon];
+    v__a_00005fb___outlet_0 [label="out", shape=house, style=dashed];
+  }
+  v___inlet_0 -> v__a_00002db [label="inlet #0 -> in"];
+  v__a_00005fb___inlet_0 -> v__a_00005fb__tee [label="in -> in"];
+  v__a_00005fb__tee -> v__a_00005fb___outlet_0 [label="out -> out"];
+  v__a_00005fb___outlet_0 -> v___outlet_0 [label="out -> outlet #0"];
+  v__a_00002db -> v__a_00005fb___inlet_0 [label="out -> in\nstring"];
+}
+"#
+    );
+}
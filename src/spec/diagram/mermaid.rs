@@ -0,0 +1,89 @@
+use std::fmt::Write;
+
+use super::diagram::*;
+
+pub fn render(diagram: &Diagram) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "flowchart LR").unwrap();
+    render_cluster_body(&mut out, &diagram.root, 1);
+    for edge in diagram.edges.iter() {
+        writeln!(
+            out,
+            "  {} -->|\"{}\"| {}",
+            edge.from,
+            escape(&edge.label),
+            edge.to
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+fn render_cluster_body(out: &mut String, cluster: &Cluster, depth: usize) {
+    let indent = "  ".repeat(depth);
+
+    for node in cluster.nodes.iter() {
+        let (open, close) = match node.shape {
+            NodeShape::OsProcess => ("[", "]"),
+            NodeShape::StdStage => ("{{", "}}"),
+            NodeShape::Inlet => ("([", "])"),
+            NodeShape::Outlet => ("([", "])"),
+        };
+        writeln!(
+            out,
+            "{}{}{}\"{}\"{}",
+            indent,
+            node.id,
+            open,
+            escape(&node.label),
+            close
+        )
+        .unwrap();
+    }
+
+    for subcluster in cluster.clusters.iter() {
+        writeln!(
+            out,
+            "{}subgraph {}[\"{}\"]",
+            indent,
+            subcluster.id,
+            escape(&subcluster.label)
+        )
+        .unwrap();
+        render_cluster_body(out, subcluster, depth + 1);
+        writeln!(out, "{}end", indent).unwrap();
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', "<br/>")
+}
+
+#[test]
+fn mermaid_test() {
+    let diagram = Diagram::from_graph_spec(&example_graph_spec());
+
+    assert_eq!(
+        render(&diagram),
+        r#"flowchart LR
+  v___inlet_0(["inlet #0"])
+  v__a_00002db["a-b<br/>cat"]
+  v___outlet_0(["outlet #0"])
+  subgraph v__a_00005fb["a_b"]
+    v__a_00005fb___inlet_0(["in"])
+    v__a_00005fb__tee{{"tee<br/>std: tee"}}
+    v__a_00005fb___outlet_0(["out"])
+  end
+  v___inlet_0 -->|"inlet #0 -#gt; in"| v__a_00002db
+  v__a_00005fb___inlet_0 -->|"in -#gt; in"| v__a_00005fb__tee
+  v__a_00005fb__tee -->|"out -#gt; out"| v__a_00005fb___outlet_0
+  v__a_00005fb___outlet_0 -->|"out -#gt; outlet #0"| v___outlet_0
+  v__a_00002db -->|"out -#gt; in<br/>string"| v__a_00005fb___inlet_0
+"#
+    );
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+mod diagram;
+use diagram::Diagram;
+
+mod dot;
+mod mermaid;
+
+#[derive(Fail, Debug)]
+pub enum DiagramError {
+    #[fail(display = "DiagramError::UnknownFormat: {}", _0)]
+    UnknownFormat(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagramFormat {
+    Dot,
+    Mermaid,
+}
+
+impl DiagramFormat {
+    pub fn from_name(name: &str) -> Result<Self, DiagramError> {
+        match name {
+            "dot" | "graphviz" => Ok(DiagramFormat::Dot),
+            "mermaid" => Ok(DiagramFormat::Mermaid),
+            unknown => Err(DiagramError::UnknownFormat(unknown.to_owned())),
+        }
+    }
+}
+
+impl GraphSpec {
+    pub fn to_diagram(&self, format: DiagramFormat) -> String {
+        match format {
+            DiagramFormat::Dot => self.to_dot(),
+            DiagramFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    /// Renders the graph as a Graphviz digraph: nested graphs become clusters.
+    pub fn to_dot(&self) -> String {
+        dot::render(&Diagram::from_graph_spec(self))
+    }
+
+    /// Renders the graph as a Mermaid flowchart: nested graphs become subgraphs.
+    pub fn to_mermaid(&self) -> String {
+        mermaid::render(&Diagram::from_graph_spec(self))
+    }
+}
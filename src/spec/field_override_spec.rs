@@ -0,0 +1,19 @@
+use std::collections::HashMap;
+
+/// How the `generate` std stage makes up the values of a field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "field_override")]
+pub enum FieldOverrideSpec {
+    /// A number within `[min, max]` for the `int` and `long` fields, within `[min, max)` for the `float` and `double` ones.
+    #[serde(rename = "range")]
+    Range { min: f64, max: f64 },
+
+    /// An enum's symbol chosen with the probability proportional to its weight; the symbols left out are never chosen.
+    #[serde(rename = "enum_weights")]
+    EnumWeights { weights: HashMap<String, f64> },
+
+    /// A string following the `pattern`: `#` stands for a digit, `?` for a lowercase letter,
+    /// `*` for an alphanumeric character, `\` escapes the character that follows.
+    #[serde(rename = "pattern")]
+    Pattern { pattern: String },
+}
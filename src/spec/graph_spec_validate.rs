@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use crate::protocol::{Schema, SchemaResolution};
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Direction {
+    Inlet,
+    Outlet,
+}
+
+impl GraphSpec {
+    /// Checks the spec (including the nested graphs) without running anything.
+    /// Returns every problem found rather than the first one.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        validate_graph(self, "", &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl VertexSpec {
+    fn ports(&self, direction: Direction) -> &Vec<String> {
+        match direction {
+            Direction::Inlet => &self.inlets,
+            Direction::Outlet => &self.outlets,
+        }
+    }
+}
+
+fn validate_graph(graph_spec: &GraphSpec, prefix: &str, errors: &mut Vec<ValidationError>) {
+    let mut vertex_names = graph_spec.vertices.keys().collect::<Vec<_>>();
+    vertex_names.sort();
+
+    let mut bindings = HashMap::new();
+
+    for vertex_name in vertex_names.iter() {
+        let vertex_spec = &graph_spec.vertices[*vertex_name];
+        let vertex = format!("{}{}", prefix, vertex_name);
+
+        for direction in [Direction::Inlet, Direction::Outlet].iter() {
+            let ports = vertex_spec.ports(*direction);
+            for (idx, port) in ports.iter().enumerate() {
+                if ports[..idx].contains(port) {
+                    let vertex = vertex.to_owned();
+                    let port = port.to_owned();
+                    errors.push(match direction {
+                        Direction::Inlet => ValidationError::DuplicateInlet { vertex, port },
+                        Direction::Outlet => ValidationError::DuplicateOutlet { vertex, port },
+                    });
+                } else {
+                    bindings.insert((vertex_name.as_str(), port.as_str(), *direction), 0);
+                }
+            }
+        }
+
+        validate_run(vertex_spec, &vertex, errors);
+    }
+
+    let port_usages = graph_spec
+        .edges
+        .iter()
+        .flat_map(|edge_spec| {
+            vec![
+                (&edge_spec.producer, Direction::Outlet),
+                (&edge_spec.consumer, Direction::Inlet),
+            ]
+        })
+        .chain(
+            graph_spec
+                .inlets
+                .iter()
+                .map(|port_spec| (port_spec, Direction::Inlet)),
+        )
+        .chain(
+            graph_spec
+                .outlets
+                .iter()
+                .map(|port_spec| (port_spec, Direction::Outlet)),
+        );
+
+    for (port_spec, direction) in port_usages {
+        if check_port(graph_spec, port_spec, direction, prefix, errors).is_some() {
+            let key = (
+                port_spec.vertex.as_str(),
+                port_spec.port.as_str(),
+                direction,
+            );
+            if let Some(times) = bindings.get_mut(&key) {
+                *times += 1;
+            }
+        }
+    }
+
+    let mut bindings = bindings.into_iter().collect::<Vec<_>>();
+    bindings.sort();
+    for ((vertex_name, port, direction), times) in bindings {
+        let vertex = format!("{}{}", prefix, vertex_name);
+        let port = port.to_owned();
+        match (times, direction) {
+            (1, _) => (),
+            (0, Direction::Inlet) => errors.push(ValidationError::UnboundInlet { vertex, port }),
+            (0, Direction::Outlet) => errors.push(ValidationError::UnboundOutlet { vertex, port }),
+            (times, Direction::Inlet) => errors.push(ValidationError::InletBoundTwice {
+                vertex,
+                port,
+                times,
+            }),
+            (times, Direction::Outlet) => errors.push(ValidationError::OutletBoundTwice {
+                vertex,
+                port,
+                times,
+            }),
+        }
+    }
+
+    for edge_spec in graph_spec.edges.iter() {
+        validate_edge_schema(graph_spec, edge_spec, prefix, errors);
+    }
+}
+
+fn validate_run(vertex_spec: &VertexSpec, vertex: &str, errors: &mut Vec<ValidationError>) {
+    let (expected_inlets, expected_outlets) = match vertex_spec.run {
+        RunSpec::OsProcess { .. } => return,
+
+        RunSpec::Graph(ref subgraph_spec) => {
+            validate_graph(subgraph_spec, &format!("{}/", vertex), errors);
+            (subgraph_spec.inlets.len(), subgraph_spec.outlets.len())
+        }
+
+        RunSpec::StdStage(ref std_stage_spec) => {
+            match crate::std_stages::from_spec(std_stage_spec.clone()) {
+                Ok(stage) => (stage.inlet_schemas().len(), stage.outlet_schemas().len()),
+                Err(reason) => {
+                    errors.push(ValidationError::StdStageError {
+                        vertex: vertex.to_owned(),
+                        reason,
+                    });
+                    return;
+                }
+            }
+        }
+    };
+
+    let actual_inlets = vertex_spec.inlets.len();
+    let actual_outlets = vertex_spec.outlets.len();
+
+    if expected_inlets != actual_inlets || expected_outlets != actual_outlets {
+        errors.push(ValidationError::PortCountMismatch {
+            vertex: vertex.to_owned(),
+            expected_inlets,
+            actual_inlets,
+            expected_outlets,
+            actual_outlets,
+        })
+    }
+}
+
+fn validate_edge_schema(
+    graph_spec: &GraphSpec,
+    edge_spec: &EdgeSpec,
+    prefix: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let edge = format!(
+        "{} -> {}",
+        port_name(&edge_spec.producer, prefix),
+        port_name(&edge_spec.consumer, prefix)
+    );
+
+    let edge_schema = match Schema::parse(&edge_spec.schema) {
+        Ok(edge_schema) => edge_schema,
+        Err(reason) => {
+            errors.push(ValidationError::EdgeSchemaParseError { edge, reason });
+            return;
+        }
+    };
+
+    if let Some(writer_schema) = port_schema(graph_spec, &edge_spec.producer, Direction::Outlet) {
+        if let Err(reason) = SchemaResolution::new(&writer_schema, &edge_schema) {
+            errors.push(ValidationError::IncompatibleSchemas {
+                edge: edge.to_owned(),
+                port: port_name(&edge_spec.producer, prefix),
+                reason,
+            });
+        }
+    }
+
+    if let Some(reader_schema) = port_schema(graph_spec, &edge_spec.consumer, Direction::Inlet) {
+        if let Err(reason) = SchemaResolution::new(&edge_schema, &reader_schema) {
+            errors.push(ValidationError::IncompatibleSchemas {
+                edge: edge.to_owned(),
+                port: port_name(&edge_spec.consumer, prefix),
+                reason,
+            });
+        }
+    }
+}
+
+fn check_port(
+    graph_spec: &GraphSpec,
+    port_spec: &PortSpec,
+    direction: Direction,
+    prefix: &str,
+    errors: &mut Vec<ValidationError>,
+) -> Option<usize> {
+    let vertex = format!("{}{}", prefix, port_spec.vertex);
+
+    match graph_spec.vertices.get(&port_spec.vertex) {
+        None => {
+            errors.push(ValidationError::UnknownVertex(vertex));
+            None
+        }
+        Some(vertex_spec) => {
+            let idx_opt = vertex_spec
+                .ports(direction)
+                .iter()
+                .position(|port| *port == port_spec.port);
+            if idx_opt.is_none() {
+                let port = port_spec.port.to_owned();
+                errors.push(match direction {
+                    Direction::Inlet => ValidationError::UnknownInlet { vertex, port },
+                    Direction::Outlet => ValidationError::UnknownOutlet { vertex, port },
+                });
+            }
+            idx_opt
+        }
+    }
+}
+
+/// The schema a vertex declares for its port, when it is known without running the vertex.
+fn port_schema(
+    graph_spec: &GraphSpec,
+    port_spec: &PortSpec,
+    direction: Direction,
+) -> Option<Schema> {
+    let vertex_spec = graph_spec.vertices.get(&port_spec.vertex)?;
+    let idx = vertex_spec
+        .ports(direction)
+        .iter()
+        .position(|port| *port == port_spec.port)?;
+
+    match vertex_spec.run {
+        RunSpec::OsProcess { .. } => None,
+
+        RunSpec::StdStage(ref std_stage_spec) => {
+            let stage = crate::std_stages::from_spec(std_stage_spec.clone()).ok()?;
+            let schemas = match direction {
+                Direction::Inlet => stage.inlet_schemas(),
+                Direction::Outlet => stage.outlet_schemas(),
+            };
+            schemas.get(idx).map(|schema| (*schema).clone())
+        }
+
+        RunSpec::Graph(ref subgraph_spec) => {
+            let inner_port_spec = match direction {
+                Direction::Inlet => subgraph_spec.inlets.get(idx),
+                Direction::Outlet => subgraph_spec.outlets.get(idx),
+            }?;
+            port_schema(subgraph_spec, inner_port_spec, direction)
+        }
+    }
+}
+
+fn port_name(port_spec: &PortSpec, prefix: &str) -> String {
+    format!("{}{}.{}", prefix, port_spec.vertex, port_spec.port)
+}
+
+#[test]
+fn validate_test() {
+    let graph_spec: GraphSpec = serde_yaml::from_str(
+        r#"
+vertices:
+  source:
+    run: {os_process: {cmd: ["source"]}}
+    outlets: [out, out]
+  tee:
+    run: {std: {tee: {schema: "string", outlets_count: 2}}}
+    inlets: [in]
+    outlets: [a]
+edges:
+  - producer: {vertex: source, port: out}
+    consumer: {vertex: tee, port: in}
+    schema: "int"
+  - producer: {vertex: tee, port: a}
+    consumer: {vertex: sink, port: in}
+    schema: "string"
+"#,
+    )
+    .unwrap();
+
+    let errors = graph_spec.validate().unwrap_err();
+    let errors = errors.iter().map(|err| err.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        errors,
+        vec![
+            "ValidationError::DuplicateOutlet [vertex: source; port: out]",
+            "ValidationError::PortCountMismatch [vertex: tee; expected-inlets: 1; actual-inlets: 1; expected-outlets: 2; actual-outlets: 1]",
+            "ValidationError::UnknownVertex: sink",
+            "ValidationError::IncompatibleSchemas [edge: source.out -> tee.in; port: tee.in]",
+        ]
+    );
+}
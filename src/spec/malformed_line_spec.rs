@@ -0,0 +1,21 @@
+/// What the `jsonl_source` std stage does with a line that does not convert into a datum.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "malformed_line")]
+pub enum MalformedLineSpec {
+    /// Fail the downstream.
+    #[serde(rename = "fail")]
+    Fail,
+
+    #[serde(rename = "skip")]
+    Skip,
+
+    /// Skip the line, logging a warning.
+    #[serde(rename = "log")]
+    Log,
+}
+
+impl Default for MalformedLineSpec {
+    fn default() -> Self {
+        MalformedLineSpec::Fail
+    }
+}
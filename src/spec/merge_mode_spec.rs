@@ -0,0 +1,22 @@
+/// In which order the `merge` std stage serves the items received from its inlets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "merge_mode")]
+pub enum MergeModeSpec {
+    /// In the order of arrival.
+    #[serde(rename = "arrival")]
+    Arrival,
+
+    /// The items of the inlets with greater `priorities` (one per inlet) first.
+    #[serde(rename = "priority")]
+    Priority { priorities: Vec<u32> },
+
+    /// Each of the inlets that have items gets its share proportional to its weight (one per inlet).
+    #[serde(rename = "weighted")]
+    Weighted { weights: Vec<u32> },
+}
+
+impl Default for MergeModeSpec {
+    fn default() -> Self {
+        MergeModeSpec::Arrival
+    }
+}
@@ -7,6 +7,33 @@ pub use port_spec::PortSpec;
 mod run_spec;
 pub use run_spec::RunSpec;
 
+mod avro_codec_spec;
+pub use avro_codec_spec::AvroCodecSpec;
+
+mod balance_spec;
+pub use balance_spec::{BalanceModeSpec, BalanceOutletPolicySpec};
+
+mod buffer_overflow_spec;
+pub use buffer_overflow_spec::BufferOverflowSpec;
+
+mod dedup_window_spec;
+pub use dedup_window_spec::DedupWindowSpec;
+
+mod field_override_spec;
+pub use field_override_spec::FieldOverrideSpec;
+
+mod malformed_line_spec;
+pub use malformed_line_spec::MalformedLineSpec;
+
+mod merge_mode_spec;
+pub use merge_mode_spec::MergeModeSpec;
+
+mod projection_spec;
+pub use projection_spec::ProjectionSpec;
+
+mod route_rule_spec;
+pub use route_rule_spec::RouteRuleSpec;
+
 mod std_stage_spec;
 pub use std_stage_spec::StdStageSpec;
 
@@ -21,3 +48,11 @@ pub use edge_spec::EdgeSpec;
 
 mod graph_spec;
 pub use graph_spec::GraphSpec;
+
+mod graph_spec_validate;
+
+mod diagram;
+pub use diagram::{DiagramError, DiagramFormat};
+
+mod validation_error;
+pub use validation_error::ValidationError;
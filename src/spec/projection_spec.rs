@@ -0,0 +1,16 @@
+/// A field of the records outleted by the `project` std stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "projection")]
+pub struct ProjectionSpec {
+    /// The name of the outlet's field.
+    pub name: String,
+
+    /// The path to the inlet's field to take the value from; the same as `name` if not set.
+    #[serde(default)]
+    pub from: Option<String>,
+
+    /// The value to use if the inlet's field is absent (e.g. there is a `null` record along the `from` path).
+    /// Such fields without a default become nullable.
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+}
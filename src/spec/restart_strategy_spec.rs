@@ -1,8 +1,28 @@
+/// What to do when the process of an `os_process` vertex fails.
+///
+/// A restarted process is attached to the same graph channels, so its peers do not notice the restart.
+/// The delivery across restarts is at-most-once though: the items pushed to the failed process
+/// but not yet processed by it are lost, and so are the ones it has not pushed on yet.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "restart_strategy")]
 pub enum RestartStrategySpec {
     #[serde(rename = "no_restart")]
     NoRestart,
+
+    #[serde(rename = "restart")]
+    Restart {
+        #[serde(default = "default_max_restarts")]
+        max_restarts: usize,
+
+        #[serde(default = "default_within_ms")]
+        within_ms: u64,
+
+        #[serde(default = "default_min_backoff_ms")]
+        min_backoff_ms: u64,
+
+        #[serde(default = "default_max_backoff_ms")]
+        max_backoff_ms: u64,
+    },
 }
 
 impl Default for RestartStrategySpec {
@@ -10,3 +30,19 @@ impl Default for RestartStrategySpec {
         RestartStrategySpec::NoRestart
     }
 }
+
+fn default_max_restarts() -> usize {
+    3
+}
+
+fn default_within_ms() -> u64 {
+    60_000
+}
+
+fn default_min_backoff_ms() -> u64 {
+    100
+}
+
+fn default_max_backoff_ms() -> u64 {
+    10_000
+}
@@ -0,0 +1,19 @@
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "route_rule_spec")]
+pub enum RouteRuleSpec {
+    /// Sends the item to `outlet` if the value at `field` equals `value`.
+    #[serde(rename = "field_equals")]
+    FieldEquals {
+        field: String,
+        value: serde_json::Value,
+        outlet: usize,
+    },
+
+    /// Sends the item to one of `outlets` (all of the outlets if empty) chosen by the hash of the value at `field`.
+    #[serde(rename = "hash_mod")]
+    HashMod {
+        field: String,
+        #[serde(default)]
+        outlets: Vec<usize>,
+    },
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use super::*;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "std_stage_spec")]
 pub enum StdStageSpec {
@@ -15,9 +19,228 @@ pub enum StdStageSpec {
         #[serde(default = "default_eagerly_fail")]
         eagerly_fail: bool,
 
+        #[serde(default)]
+        mode: MergeModeSpec,
+
+        schema: serde_json::Value,
+        inlets_count: usize,
+    },
+
+    /// Merges the inlets, each of them sorted by the value at `key`, into a stream sorted the same way.
+    #[serde(rename = "merge_sorted")]
+    MergeSorted {
+        #[serde(default = "default_eagerly_complete")]
+        eagerly_complete: bool,
+
+        #[serde(default = "default_eagerly_fail")]
+        eagerly_fail: bool,
+
+        schema: serde_json::Value,
+        inlets_count: usize,
+        key: String,
+    },
+
+    /// Drains the inlets one after another: all of the first one, then all of the second one, etc.
+    #[serde(rename = "concat")]
+    Concat {
+        #[serde(default = "default_eagerly_fail")]
+        eagerly_fail: bool,
+
         schema: serde_json::Value,
         inlets_count: usize,
     },
+
+    #[serde(rename = "route")]
+    Route {
+        schema: serde_json::Value,
+        outlets_count: usize,
+        #[serde(default)]
+        rules: Vec<RouteRuleSpec>,
+        #[serde(default)]
+        default_outlet: Option<usize>,
+    },
+
+    #[serde(rename = "balance")]
+    Balance {
+        schema: serde_json::Value,
+        outlets_count: usize,
+        #[serde(default)]
+        mode: BalanceModeSpec,
+        /// Applies when an outlet sends `Cancel`.
+        #[serde(default)]
+        on_outlet_cancel: BalanceOutletPolicySpec,
+        /// Applies when an outlet goes away without cancelling.
+        #[serde(default)]
+        on_outlet_failure: BalanceOutletPolicySpec,
+    },
+
+    /// The outlet's schema is a record named `record_name` with a field per inlet;
+    /// the fields are named after `fields` (`_0`, `_1`, ... if omitted).
+    #[serde(rename = "zip")]
+    Zip {
+        #[serde(default = "default_zip_eagerly_complete")]
+        eagerly_complete: bool,
+
+        #[serde(default = "default_zip_eagerly_fail")]
+        eagerly_fail: bool,
+
+        #[serde(default = "default_zip_record_name")]
+        record_name: String,
+
+        #[serde(default)]
+        fields: Vec<String>,
+
+        schemas: Vec<serde_json::Value>,
+    },
+
+    /// The outlet's schema is an array of `schema`.
+    #[serde(rename = "batch")]
+    Batch {
+        schema: serde_json::Value,
+        max_size: usize,
+        #[serde(default)]
+        max_latency_ms: Option<u64>,
+    },
+
+    #[serde(rename = "throttle")]
+    Throttle {
+        schema: serde_json::Value,
+        items_per_second: f64,
+        #[serde(default = "default_throttle_burst")]
+        burst: usize,
+    },
+
+    #[serde(rename = "buffer")]
+    Buffer {
+        schema: serde_json::Value,
+        capacity: usize,
+        #[serde(default)]
+        overflow: BufferOverflowSpec,
+    },
+
+    /// Emits the datums of an Avro object container file, resolved into `schema`.
+    #[serde(rename = "avro_file_source")]
+    AvroFileSource {
+        schema: serde_json::Value,
+        path: String,
+    },
+
+    /// Writes the datums into an Avro object container file;
+    /// a block is written once it grows past `sync_interval` bytes.
+    #[serde(rename = "avro_file_sink")]
+    AvroFileSink {
+        schema: serde_json::Value,
+        path: String,
+        #[serde(default)]
+        codec: AvroCodecSpec,
+        #[serde(default = "default_avro_file_sync_interval")]
+        sync_interval: usize,
+    },
+
+    /// Passes the first `count` items through, then cancels the upstream and completes the downstream.
+    #[serde(rename = "take", alias = "limit")]
+    Take {
+        schema: serde_json::Value,
+        count: usize,
+    },
+
+    /// Drops the first `count` items.
+    #[serde(rename = "skip")]
+    Skip {
+        schema: serde_json::Value,
+        count: usize,
+    },
+
+    /// Passes each item through with the probability `fraction`;
+    /// the same `seed` picks the same items out of the same stream.
+    #[serde(rename = "sample")]
+    Sample {
+        schema: serde_json::Value,
+        fraction: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+
+    /// Drops the items whose values at the `keys` field paths have already been seen within the `window`.
+    #[serde(rename = "dedup")]
+    Dedup {
+        schema: serde_json::Value,
+        keys: Vec<String>,
+        window: DedupWindowSpec,
+    },
+
+    /// Outlets records named `record_name` made of the `fields` taken from the inlet's `schema` records.
+    /// If `keep_others` is set, the inlet's top-level fields that none of the `fields` takes as a whole follow them,
+    /// except those to `drop`.
+    #[serde(rename = "project")]
+    Project {
+        schema: serde_json::Value,
+        #[serde(default = "default_project_record_name")]
+        record_name: String,
+        #[serde(default)]
+        fields: Vec<ProjectionSpec>,
+        #[serde(default)]
+        keep_others: bool,
+        #[serde(default)]
+        drop: Vec<String>,
+    },
+
+    /// Emits random datums of `schema` until `count` of them are emitted or `duration_ms` elapses (whichever is set),
+    /// at `items_per_second` (as fast as they are pulled if not set).
+    /// `overrides` tell how to make up the values of the fields at the given paths.
+    #[serde(rename = "generate")]
+    Generate {
+        schema: serde_json::Value,
+        #[serde(default)]
+        seed: u64,
+        #[serde(default)]
+        count: Option<u64>,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        items_per_second: Option<f64>,
+        #[serde(default)]
+        overrides: HashMap<String, FieldOverrideSpec>,
+    },
+
+    /// Consumes all of the items, logging the throughput every `report_interval_ms` and a summary in the end;
+    /// fails if the total is below `expected_min` or above `expected_max` (whichever is set).
+    #[serde(rename = "count")]
+    Count {
+        schema: serde_json::Value,
+        #[serde(default = "default_count_report_interval_ms")]
+        report_interval_ms: u64,
+        #[serde(default)]
+        expected_min: Option<u64>,
+        #[serde(default)]
+        expected_max: Option<u64>,
+    },
+
+    /// Reads a JSON Lines file (or the stdin if there is no `path`),
+    /// each line being the Avro JSON encoding of a datum of `schema`; blank lines are ignored.
+    #[serde(rename = "jsonl_source")]
+    JsonlSource {
+        schema: serde_json::Value,
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        on_malformed: MalformedLineSpec,
+    },
+
+    /// Writes the datums as JSON Lines into a file (or the stdout if there is no `path`).
+    #[serde(rename = "jsonl_sink")]
+    JsonlSink {
+        schema: serde_json::Value,
+        #[serde(default)]
+        path: Option<String>,
+    },
+
+    #[serde(rename = "custom")]
+    Custom {
+        name: String,
+        #[serde(default)]
+        config: serde_json::Value,
+    },
 }
 
 fn default_eagerly_complete() -> bool {
@@ -27,3 +250,31 @@ fn default_eagerly_complete() -> bool {
 fn default_eagerly_fail() -> bool {
     false
 }
+
+fn default_zip_eagerly_complete() -> bool {
+    true
+}
+
+fn default_zip_eagerly_fail() -> bool {
+    true
+}
+
+fn default_zip_record_name() -> String {
+    "zipped".to_owned()
+}
+
+fn default_throttle_burst() -> usize {
+    1
+}
+
+fn default_avro_file_sync_interval() -> usize {
+    16_000
+}
+
+fn default_count_report_interval_ms() -> u64 {
+    10_000
+}
+
+fn default_project_record_name() -> String {
+    "projected".to_owned()
+}
@@ -0,0 +1,101 @@
+use crate::protocol::SchemaResolutionError;
+use crate::std_stages::StdStageError;
+
+#[derive(Fail, Debug)]
+pub enum ValidationError {
+    #[fail(display = "ValidationError::UnknownVertex: {}", _0)]
+    UnknownVertex(String),
+
+    #[fail(
+        display = "ValidationError::DuplicateInlet [vertex: {}; port: {}]",
+        vertex, port
+    )]
+    DuplicateInlet { vertex: String, port: String },
+
+    #[fail(
+        display = "ValidationError::DuplicateOutlet [vertex: {}; port: {}]",
+        vertex, port
+    )]
+    DuplicateOutlet { vertex: String, port: String },
+
+    #[fail(
+        display = "ValidationError::UnknownInlet [vertex: {}; port: {}]",
+        vertex, port
+    )]
+    UnknownInlet { vertex: String, port: String },
+
+    #[fail(
+        display = "ValidationError::UnknownOutlet [vertex: {}; port: {}]",
+        vertex, port
+    )]
+    UnknownOutlet { vertex: String, port: String },
+
+    #[fail(
+        display = "ValidationError::InletBoundTwice [vertex: {}; port: {}; times: {}]",
+        vertex, port, times
+    )]
+    InletBoundTwice {
+        vertex: String,
+        port: String,
+        times: usize,
+    },
+
+    #[fail(
+        display = "ValidationError::OutletBoundTwice [vertex: {}; port: {}; times: {}]",
+        vertex, port, times
+    )]
+    OutletBoundTwice {
+        vertex: String,
+        port: String,
+        times: usize,
+    },
+
+    #[fail(
+        display = "ValidationError::UnboundInlet [vertex: {}; port: {}]",
+        vertex, port
+    )]
+    UnboundInlet { vertex: String, port: String },
+
+    #[fail(
+        display = "ValidationError::UnboundOutlet [vertex: {}; port: {}]",
+        vertex, port
+    )]
+    UnboundOutlet { vertex: String, port: String },
+
+    #[fail(display = "ValidationError::EdgeSchemaParseError [edge: {}]", edge)]
+    EdgeSchemaParseError {
+        edge: String,
+        #[cause]
+        reason: failure::Error,
+    },
+
+    #[fail(display = "ValidationError::StdStageError [vertex: {}]", vertex)]
+    StdStageError {
+        vertex: String,
+        #[cause]
+        reason: StdStageError,
+    },
+
+    #[fail(
+        display = "ValidationError::PortCountMismatch [vertex: {}; expected-inlets: {}; actual-inlets: {}; expected-outlets: {}; actual-outlets: {}]",
+        vertex, expected_inlets, actual_inlets, expected_outlets, actual_outlets
+    )]
+    PortCountMismatch {
+        vertex: String,
+        expected_inlets: usize,
+        actual_inlets: usize,
+        expected_outlets: usize,
+        actual_outlets: usize,
+    },
+
+    #[fail(
+        display = "ValidationError::IncompatibleSchemas [edge: {}; port: {}]",
+        edge, port
+    )]
+    IncompatibleSchemas {
+        edge: String,
+        port: String,
+        #[cause]
+        reason: SchemaResolutionError,
+    },
+}
@@ -0,0 +1,28 @@
+/// A `long` in the Avro binary encoding: zig-zag, then variable-length.
+pub fn encode_long(n: i64) -> Vec<u8> {
+    let mut zigzag = ((n << 1) ^ (n >> 63)) as u64;
+    let mut bytes = Vec::new();
+    loop {
+        if zigzag & !0x7f == 0 {
+            bytes.push(zigzag as u8);
+            return bytes;
+        }
+        bytes.push((zigzag & 0x7f | 0x80) as u8);
+        zigzag >>= 7;
+    }
+}
+
+/// `bytes` (as well as `string`) in the Avro binary encoding: the length followed by the bytes.
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = encode_long(bytes.len() as i64);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+#[test]
+fn encode_long_test() {
+    assert_eq!(encode_long(0), vec![0]);
+    assert_eq!(encode_long(-1), vec![1]);
+    assert_eq!(encode_long(64), vec![0x80, 0x01]);
+    assert_eq!(encode_bytes(b"ab"), vec![4, b'a', b'b']);
+}
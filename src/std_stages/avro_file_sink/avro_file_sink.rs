@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use crate::protocol::Schema;
+use crate::spec::AvroCodecSpec;
+
+/// The file is created upon the first block (or the upstream's completion) and is written within the stage's task.
+#[derive(Debug)]
+pub struct AvroFileSink {
+    pub schema: Schema,
+    pub path: PathBuf,
+    pub codec: AvroCodecSpec,
+    pub sync_interval: usize,
+}
+
+impl AvroFileSink {
+    pub fn new<P: Into<PathBuf>>(
+        schema: Schema,
+        path: P,
+        codec: AvroCodecSpec,
+        sync_interval: usize,
+    ) -> Self {
+        Self {
+            schema,
+            path: path.into(),
+            codec,
+            sync_interval,
+        }
+    }
+}
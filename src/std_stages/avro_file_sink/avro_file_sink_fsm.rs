@@ -0,0 +1,187 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::SendBoxedFuture;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+
+use super::super::blocking::poll_blocking;
+use super::*;
+
+const PULL_SIZE: usize = 256;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<AvroFileSinkFSM>>;
+
+#[derive(Fail, Debug)]
+pub enum AvroFileSinkError {
+    #[fail(display = "AvroFileSinkError::RxError")]
+    RxError,
+
+    #[fail(display = "AvroFileSinkError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "AvroFileSinkError::WriteError")]
+    WriteError(#[cause] failure::Error),
+
+    #[fail(display = "AvroFileSinkError::UpstreamFailed: {}", _0)]
+    UpstreamFailed(String),
+}
+
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum AvroFileSinkFSM {
+    ReceiveEvent {
+        container_writer: ContainerWriter,
+        inlet_rx: ConsumerRx,
+        inlet_tx: ConsumerTx,
+    },
+    /// The file is written off the runtime's thread (see `poll_blocking`).
+    Appending {
+        container_writer: ContainerWriter,
+        items: Option<Vec<Vec<u8>>>,
+        inlet_rx: ConsumerRx,
+        inlet_tx: ConsumerTx,
+    },
+    /// What has been received is written out even if the upstream has failed.
+    Finishing {
+        container_writer: ContainerWriter,
+        upstream_failure: Option<String>,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+}
+
+impl AvroFileSinkFSM {
+    pub fn new(container_writer: ContainerWriter, inlet: (ConsumerTx, ConsumerRx)) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        pulling_upstream(container_writer, inlet_rx, inlet_tx)
+    }
+}
+
+impl FSM for AvroFileSinkFSM {
+    type Item = ();
+    type Error = AvroFileSinkError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            AvroFileSinkFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| AvroFileSinkError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(AvroFileSinkFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            AvroFileSinkFSM::ReceiveEvent {
+                container_writer,
+                mut inlet_rx,
+                inlet_tx,
+            } => match inlet_rx.poll() {
+                Err(()) => Err(AvroFileSinkError::RxError),
+
+                Ok(Async::NotReady) => Ok(TurnOk::Suspend(AvroFileSinkFSM::ReceiveEvent {
+                    container_writer,
+                    inlet_rx,
+                    inlet_tx,
+                })),
+
+                Ok(Async::Ready(None)) => Ok(TurnOk::Ready(())),
+
+                Ok(Async::Ready(Some(ProducerMessage::Push { items }))) => {
+                    Ok(TurnOk::PollMore(AvroFileSinkFSM::Appending {
+                        container_writer,
+                        items: Some(items),
+                        inlet_rx,
+                        inlet_tx,
+                    }))
+                }
+
+                Ok(Async::Ready(Some(ProducerMessage::Complete))) => {
+                    Ok(TurnOk::PollMore(AvroFileSinkFSM::Finishing {
+                        container_writer,
+                        upstream_failure: None,
+                    }))
+                }
+
+                Ok(Async::Ready(Some(ProducerMessage::Fail { failure }))) => {
+                    Ok(TurnOk::PollMore(AvroFileSinkFSM::Finishing {
+                        container_writer,
+                        upstream_failure: Some(failure.message),
+                    }))
+                }
+            },
+
+            AvroFileSinkFSM::Appending {
+                mut container_writer,
+                mut items,
+                inlet_rx,
+                inlet_tx,
+            } => match poll_blocking(|| {
+                container_writer.append(items.take().expect("appended only once"))
+            }) {
+                Async::NotReady => Ok(TurnOk::Suspend(AvroFileSinkFSM::Appending {
+                    container_writer,
+                    items,
+                    inlet_rx,
+                    inlet_tx,
+                })),
+                Async::Ready(Ok(())) => Ok(TurnOk::PollMore(pulling_upstream(
+                    container_writer,
+                    inlet_rx,
+                    inlet_tx,
+                ))),
+                Async::Ready(Err(reason)) => {
+                    let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+                    let and_then = move |_inlet_tx| Err(AvroFileSinkError::WriteError(reason));
+                    Ok(TurnOk::PollMore(AvroFileSinkFSM::SendingToUpstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(and_then),
+                    }))
+                }
+            },
+
+            AvroFileSinkFSM::Finishing {
+                mut container_writer,
+                upstream_failure,
+            } => match poll_blocking(|| container_writer.finish()) {
+                Async::NotReady => Ok(TurnOk::Suspend(AvroFileSinkFSM::Finishing {
+                    container_writer,
+                    upstream_failure,
+                })),
+                Async::Ready(Err(reason)) => Err(AvroFileSinkError::WriteError(reason)),
+                Async::Ready(Ok(())) => match upstream_failure {
+                    None => Ok(TurnOk::Ready(())),
+                    Some(message) => Err(AvroFileSinkError::UpstreamFailed(message)),
+                },
+            },
+        }
+    }
+}
+
+fn pulling_upstream(
+    container_writer: ContainerWriter,
+    inlet_rx: ConsumerRx,
+    inlet_tx: ConsumerTx,
+) -> AvroFileSinkFSM {
+    let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull {
+        max_items: PULL_SIZE,
+    }));
+    let into_receiving_events = move |inlet_tx| {
+        Ok(TurnOk::PollMore(AvroFileSinkFSM::ReceiveEvent {
+            container_writer,
+            inlet_rx,
+            inlet_tx,
+        }))
+    };
+    AvroFileSinkFSM::SendingToUpstream {
+        sent,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }
+}
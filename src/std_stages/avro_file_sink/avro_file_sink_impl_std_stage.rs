@@ -0,0 +1,38 @@
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for AvroFileSink {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.schema]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        mut inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.len() == 1);
+        assert!(outlets.is_empty());
+        let inlet = inlets.pop().unwrap();
+
+        let container_writer =
+            ContainerWriter::new(self.path, self.schema, self.codec, self.sync_interval);
+
+        Box::new(
+            AvroFileSinkFSM::new(container_writer, inlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use avro_rs::Codec;
+
+use crate::protocol::Schema;
+use crate::spec::AvroCodecSpec;
+
+use super::super::avro_encoding::{encode_bytes, encode_long};
+
+const MAGIC: &[u8] = b"Obj\x01";
+
+/// Writes an object container file out of datums that are already encoded,
+/// so that they are never decoded on their way to the file.
+pub struct ContainerWriter {
+    path: PathBuf,
+    schema: Schema,
+    codec: AvroCodecSpec,
+    sync_interval: usize,
+    sync_marker: [u8; 16],
+    file: Option<BufWriter<File>>,
+    block: Vec<u8>,
+    block_count: usize,
+}
+
+impl ContainerWriter {
+    pub fn new(path: PathBuf, schema: Schema, codec: AvroCodecSpec, sync_interval: usize) -> Self {
+        Self {
+            path,
+            schema,
+            codec,
+            sync_interval,
+            sync_marker: sync_marker(),
+            file: None,
+            block: Vec::new(),
+            block_count: 0,
+        }
+    }
+
+    pub fn append(&mut self, items: Vec<Vec<u8>>) -> Result<(), failure::Error> {
+        for item in items {
+            self.block.extend(item);
+            self.block_count += 1;
+
+            if self.block.len() >= self.sync_interval {
+                self.write_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the pending block out and flushes the file.
+    pub fn finish(&mut self) -> Result<(), failure::Error> {
+        if self.block_count > 0 {
+            self.write_block()?;
+        }
+        self.file()?.flush()?;
+        Ok(())
+    }
+
+    fn write_block(&mut self) -> Result<(), failure::Error> {
+        let mut block = std::mem::replace(&mut self.block, Vec::new());
+        let block_count = std::mem::replace(&mut self.block_count, 0);
+
+        codec(self.codec).compress(&mut block)?;
+
+        let sync_marker = self.sync_marker;
+        let file = self.file()?;
+        file.write_all(&encode_long(block_count as i64))?;
+        file.write_all(&encode_bytes(&block))?;
+        file.write_all(&sync_marker)?;
+        Ok(())
+    }
+
+    /// The file, created along with its header upon the first call.
+    fn file(&mut self) -> Result<&mut BufWriter<File>, failure::Error> {
+        if self.file.is_none() {
+            let schema_json = serde_json::to_string(&self.schema)?;
+            let mut file = BufWriter::new(File::create(&self.path)?);
+
+            file.write_all(MAGIC)?;
+            file.write_all(&encode_long(2))?;
+            file.write_all(&encode_bytes(b"avro.schema"))?;
+            file.write_all(&encode_bytes(schema_json.as_bytes()))?;
+            file.write_all(&encode_bytes(b"avro.codec"))?;
+            file.write_all(&encode_bytes(self.codec.name().as_bytes()))?;
+            file.write_all(&encode_long(0))?;
+            file.write_all(&self.sync_marker)?;
+
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+}
+
+fn codec(codec_spec: AvroCodecSpec) -> Codec {
+    match codec_spec {
+        AvroCodecSpec::Null => Codec::Null,
+        AvroCodecSpec::Deflate => Codec::Deflate,
+    }
+}
+
+/// Random enough to tell the blocks apart from the data.
+fn sync_marker() -> [u8; 16] {
+    let random = || RandomState::new().build_hasher().finish().to_le_bytes();
+    let mut sync_marker = [0; 16];
+    sync_marker[..8].copy_from_slice(&random());
+    sync_marker[8..].copy_from_slice(&random());
+    sync_marker
+}
+
+#[test]
+fn container_round_trip_test() {
+    use super::super::avro_file_source::ContainerReader;
+
+    let path = std::env::temp_dir().join(format!(
+        "raffineria-container-round-trip-{}.avro",
+        std::process::id()
+    ));
+    let items = (0..10).map(encode_long).collect::<Vec<_>>();
+
+    // a block per three items or so
+    let mut writer = ContainerWriter::new(path.clone(), Schema::Long, AvroCodecSpec::Deflate, 3);
+    writer.append(items[..4].to_vec()).unwrap();
+    writer.append(items[4..].to_vec()).unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = ContainerReader::new(path.clone(), Schema::Long);
+    let mut read = Vec::new();
+    loop {
+        let chunk = reader.read(4).unwrap();
+        if chunk.is_empty() {
+            break;
+        }
+        read.extend(chunk);
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read, items);
+}
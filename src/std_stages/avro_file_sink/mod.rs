@@ -0,0 +1,12 @@
+use super::*;
+
+mod avro_file_sink;
+pub use avro_file_sink::AvroFileSink;
+
+mod container_writer;
+use container_writer::ContainerWriter;
+
+mod avro_file_sink_impl_std_stage;
+
+mod avro_file_sink_fsm;
+use avro_file_sink_fsm::AvroFileSinkFSM;
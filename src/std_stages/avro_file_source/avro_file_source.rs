@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use crate::protocol::Schema;
+
+/// The file is opened upon the first pull and is read within the stage's task.
+#[derive(Debug)]
+pub struct AvroFileSource {
+    pub schema: Schema,
+    pub path: PathBuf,
+}
+
+impl AvroFileSource {
+    pub fn new<P: Into<PathBuf>>(schema: Schema, path: P) -> Self {
+        Self {
+            schema,
+            path: path.into(),
+        }
+    }
+}
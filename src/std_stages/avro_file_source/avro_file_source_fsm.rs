@@ -0,0 +1,151 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::SendBoxedFuture;
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::super::blocking::poll_blocking;
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<AvroFileSourceFSM>>;
+
+#[derive(Fail, Debug)]
+pub enum AvroFileSourceError {
+    #[fail(display = "AvroFileSourceError::RxError")]
+    RxError,
+
+    #[fail(display = "AvroFileSourceError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+
+pub enum AvroFileSourceFSM {
+    ReceiveEvent {
+        container_reader: ContainerReader,
+        outlet_rx: ProducerRx,
+        outlet_tx: ProducerTx,
+    },
+    /// The file is read off the runtime's thread (see `poll_blocking`).
+    Reading {
+        container_reader: ContainerReader,
+        max_items: usize,
+        outlet_rx: ProducerRx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl AvroFileSourceFSM {
+    pub fn new(container_reader: ContainerReader, outlet: (ProducerTx, ProducerRx)) -> Self {
+        let (outlet_tx, outlet_rx) = outlet;
+        AvroFileSourceFSM::ReceiveEvent {
+            container_reader,
+            outlet_rx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for AvroFileSourceFSM {
+    type Item = ();
+    type Error = AvroFileSourceError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            AvroFileSourceFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| AvroFileSourceError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => {
+                        Ok(TurnOk::Suspend(AvroFileSourceFSM::SendingToDownstream {
+                            sent,
+                            and_then,
+                        }))
+                    }
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            AvroFileSourceFSM::ReceiveEvent {
+                container_reader,
+                mut outlet_rx,
+                outlet_tx,
+            } => match outlet_rx.poll() {
+                Err(()) => Err(AvroFileSourceError::RxError),
+
+                Ok(Async::NotReady) => Ok(TurnOk::Suspend(AvroFileSourceFSM::ReceiveEvent {
+                    container_reader,
+                    outlet_rx,
+                    outlet_tx,
+                })),
+
+                Ok(Async::Ready(None)) | Ok(Async::Ready(Some(ConsumerMessage::Cancel))) => {
+                    Ok(TurnOk::Ready(()))
+                }
+
+                Ok(Async::Ready(Some(ConsumerMessage::Pull { max_items }))) => {
+                    Ok(TurnOk::PollMore(AvroFileSourceFSM::Reading {
+                        container_reader,
+                        max_items,
+                        outlet_rx,
+                        outlet_tx,
+                    }))
+                }
+            },
+
+            AvroFileSourceFSM::Reading {
+                mut container_reader,
+                max_items,
+                outlet_rx,
+                outlet_tx,
+            } => match poll_blocking(|| container_reader.read(max_items)) {
+                Async::NotReady => Ok(TurnOk::Suspend(AvroFileSourceFSM::Reading {
+                    container_reader,
+                    max_items,
+                    outlet_rx,
+                    outlet_tx,
+                })),
+                Async::Ready(read) => {
+                    let (message, and_then) = match read {
+                        Ok(ref items) if items.is_empty() && max_items > 0 => {
+                            (ProducerMessage::Complete, None)
+                        }
+                        Ok(items) => (
+                            ProducerMessage::Push { items },
+                            Some((container_reader, outlet_rx)),
+                        ),
+                        Err(reason) => (
+                            ProducerMessage::Fail {
+                                failure: PortFailure::from(reason),
+                            },
+                            None,
+                        ),
+                    };
+
+                    let sent = Box::new(outlet_tx.send(message));
+                    let and_then = move |outlet_tx| match and_then {
+                        Some((container_reader, outlet_rx)) => {
+                            Ok(TurnOk::PollMore(AvroFileSourceFSM::ReceiveEvent {
+                                container_reader,
+                                outlet_rx,
+                                outlet_tx,
+                            }))
+                        }
+                        None => Ok(TurnOk::Ready(())),
+                    };
+                    Ok(TurnOk::PollMore(AvroFileSourceFSM::SendingToDownstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(and_then),
+                    }))
+                }
+            },
+        }
+    }
+}
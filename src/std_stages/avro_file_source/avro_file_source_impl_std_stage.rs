@@ -0,0 +1,37 @@
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for AvroFileSource {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.schema]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        mut outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.is_empty());
+        assert!(outlets.len() == 1);
+        let outlet = outlets.pop().unwrap();
+
+        let container_reader = ContainerReader::new(self.path, self.schema);
+
+        Box::new(
+            AvroFileSourceFSM::new(container_reader, outlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
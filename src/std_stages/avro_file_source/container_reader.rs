@@ -0,0 +1,45 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use avro_rs::Reader;
+
+use crate::protocol::transcode::resolve;
+use crate::protocol::Schema;
+
+/// Reads the datums of a container file, converting them from the file's schema into `schema`.
+pub struct ContainerReader {
+    path: PathBuf,
+    schema: Schema,
+    opened: Option<(Reader<'static, BufReader<File>>, Schema)>,
+}
+
+impl ContainerReader {
+    pub fn new(path: PathBuf, schema: Schema) -> Self {
+        Self {
+            path,
+            schema,
+            opened: None,
+        }
+    }
+
+    /// Reads up to `max_items` datums; none are read once the file is over.
+    pub fn read(&mut self, max_items: usize) -> Result<Vec<Vec<u8>>, failure::Error> {
+        if self.opened.is_none() {
+            let file = File::open(&self.path)?;
+            let reader = Reader::new(BufReader::new(file))?;
+            let writer_schema = reader.writer_schema().clone();
+            self.opened = Some((reader, writer_schema));
+        }
+        let (reader, writer_schema) = self.opened.as_mut().unwrap();
+        let schema = &self.schema;
+
+        reader
+            .take(max_items)
+            .map(|written| {
+                let resolved = resolve(written?, writer_schema, schema)?;
+                avro_rs::to_avro_datum(schema, resolved)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod avro_file_source;
+pub use avro_file_source::AvroFileSource;
+
+mod container_reader;
+pub(super) use container_reader::ContainerReader;
+
+mod avro_file_source_impl_std_stage;
+
+mod avro_file_source_fsm;
+use avro_file_source_fsm::AvroFileSourceFSM;
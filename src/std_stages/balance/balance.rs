@@ -0,0 +1,29 @@
+use crate::protocol::Schema;
+use crate::spec::{BalanceModeSpec, BalanceOutletPolicySpec};
+
+#[derive(Debug)]
+pub struct Balance {
+    pub schema: Schema,
+    pub outlets_count: usize,
+    pub mode: BalanceModeSpec,
+    pub on_outlet_cancel: BalanceOutletPolicySpec,
+    pub on_outlet_failure: BalanceOutletPolicySpec,
+}
+
+impl Balance {
+    pub fn new(
+        schema: Schema,
+        outlets_count: usize,
+        mode: BalanceModeSpec,
+        on_outlet_cancel: BalanceOutletPolicySpec,
+        on_outlet_failure: BalanceOutletPolicySpec,
+    ) -> Self {
+        Self {
+            schema,
+            outlets_count,
+            mode,
+            on_outlet_cancel,
+            on_outlet_failure,
+        }
+    }
+}
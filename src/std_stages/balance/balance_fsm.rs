@@ -0,0 +1,357 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::future;
+use futures::prelude::*;
+use futures::stream;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::spec::BalanceOutletPolicySpec;
+
+use super::balancer::{DownstreamState, UpstreamState};
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<BalanceFSM>>;
+
+pub enum Event {
+    ConsumerMessage(usize, ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+    OutletClosed(usize),
+}
+
+#[derive(Fail, Debug)]
+pub enum BalanceError {
+    #[fail(display = "BalanceError::RxError")]
+    RxError,
+
+    #[fail(display = "BalanceError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "BalanceError::OutletClosed: {}", _0)]
+    OutletClosed(usize),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, BalanceError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type DownstreamSendResult = Result<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum BalanceFSM {
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(DownstreamSendResult,)>,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    ReceiveEvent {
+        balancer: Balancer,
+        rx_events: RxEventStream,
+        inlet_tx: ConsumerTx,
+        outlet_txs: Vec<Option<ProducerTx>>,
+    },
+    Stopping {
+        sents: SendBoxedFuture<(), BalanceError>,
+    },
+}
+
+impl FSM for BalanceFSM {
+    type Item = ();
+    type Error = BalanceError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            BalanceFSM::ReceiveEvent {
+                balancer,
+                mut rx_events,
+                inlet_tx,
+                outlet_txs,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(BalanceFSM::ReceiveEvent {
+                    balancer,
+                    rx_events,
+                    inlet_tx,
+                    outlet_txs,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => {
+                    handle_rx_event(event, balancer, rx_events, inlet_tx, outlet_txs)
+                }
+            }),
+
+            BalanceFSM::SendingToDownstream { mut sent, and_then } => match sent.poll() {
+                Ok(Async::NotReady) => Ok(TurnOk::Suspend(BalanceFSM::SendingToDownstream {
+                    sent,
+                    and_then,
+                })),
+                Ok(Async::Ready(outlet_tx)) => and_then.call(Ok(outlet_tx)),
+                Err(err) => and_then.call(Err(err)),
+            },
+
+            BalanceFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| BalanceError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(BalanceFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            BalanceFSM::Stopping { mut sents } => sents.poll().map(|poll| match poll {
+                Async::NotReady => TurnOk::Suspend(BalanceFSM::Stopping { sents }),
+                Async::Ready(()) => TurnOk::Ready(()),
+            }),
+        }
+    }
+}
+
+impl BalanceFSM {
+    pub fn new(
+        balancer: Balancer,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_txs, outlet_rxs): (Vec<_>, Vec<_>) = outlets
+            .into_iter()
+            .map(|(outlet_tx, outlet_rx)| (Some(outlet_tx), outlet_rx))
+            .unzip();
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rxs);
+
+        BalanceFSM::ReceiveEvent {
+            balancer,
+            rx_events,
+            inlet_tx,
+            outlet_txs,
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    mut balancer: Balancer,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_txs: Vec<Option<ProducerTx>>,
+) -> TurnResult<BalanceFSM> {
+    match event {
+        Event::ProducerMessage(ProducerMessage::Push { items }) => {
+            balancer.upstream_state = UpstreamState::Idle;
+            balancer.buffer.extend(items);
+            proceed(balancer, rx_events, inlet_tx, outlet_txs)
+        }
+
+        Event::ProducerMessage(ProducerMessage::Complete) => {
+            balancer.upstream_state = UpstreamState::Complete;
+            proceed(balancer, rx_events, inlet_tx, outlet_txs)
+        }
+
+        Event::ProducerMessage(ProducerMessage::Fail { failure }) => Ok(TurnOk::PollMore(
+            stopping(None, outlet_txs, ProducerMessage::Fail { failure }),
+        )),
+
+        Event::ConsumerMessage(outlet_idx, ConsumerMessage::Pull { max_items }) => {
+            assert!(outlet_idx < balancer.downstream_states.len());
+            if balancer.downstream_states[outlet_idx] != DownstreamState::Gone {
+                balancer.downstream_states[outlet_idx] = DownstreamState::Ready(max_items);
+            }
+            proceed(balancer, rx_events, inlet_tx, outlet_txs)
+        }
+
+        Event::ConsumerMessage(outlet_idx, ConsumerMessage::Cancel) => {
+            outlet_stopped(outlet_idx, true, balancer, rx_events, inlet_tx, outlet_txs)
+        }
+
+        Event::OutletClosed(outlet_idx) => {
+            if balancer.downstream_states[outlet_idx] == DownstreamState::Gone {
+                proceed(balancer, rx_events, inlet_tx, outlet_txs)
+            } else {
+                outlet_stopped(outlet_idx, false, balancer, rx_events, inlet_tx, outlet_txs)
+            }
+        }
+    }
+}
+
+/// Pushes the buffered items, completes the downstreams once the upstream has completed
+/// and the buffer is drained, or pulls the upstream.
+fn proceed(
+    mut balancer: Balancer,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    mut outlet_txs: Vec<Option<ProducerTx>>,
+) -> TurnResult<BalanceFSM> {
+    if let Some((outlet_idx, items)) = balancer.next_push() {
+        let outlet_tx = outlet_txs[outlet_idx]
+            .take()
+            .expect("a live outlet without a tx");
+        let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+
+        let and_then = move |sent: DownstreamSendResult| match sent {
+            Ok(outlet_tx) => {
+                outlet_txs[outlet_idx] = Some(outlet_tx);
+                proceed(balancer, rx_events, inlet_tx, outlet_txs)
+            }
+            Err(err) => {
+                if let ProducerMessage::Push { items } = err.into_inner() {
+                    for item in items.into_iter().rev() {
+                        balancer.buffer.push_front(item);
+                    }
+                }
+                outlet_stopped(outlet_idx, false, balancer, rx_events, inlet_tx, outlet_txs)
+            }
+        };
+
+        Ok(TurnOk::PollMore(BalanceFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(and_then),
+        }))
+    } else if balancer.buffer.is_empty() && balancer.upstream_state == UpstreamState::Complete {
+        Ok(TurnOk::PollMore(stopping(
+            None,
+            outlet_txs,
+            ProducerMessage::Complete,
+        )))
+    } else if let Some(max_items) = balancer.pull_size() {
+        balancer.upstream_state = UpstreamState::Pulled;
+        let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items }));
+        let into_receiving_events = move |inlet_tx| {
+            Ok(TurnOk::PollMore(BalanceFSM::ReceiveEvent {
+                balancer,
+                rx_events,
+                inlet_tx,
+                outlet_txs,
+            }))
+        };
+        Ok(TurnOk::PollMore(BalanceFSM::SendingToUpstream {
+            sent,
+            and_then: SendBoxFnOnce::from(into_receiving_events),
+        }))
+    } else {
+        Ok(TurnOk::PollMore(BalanceFSM::ReceiveEvent {
+            balancer,
+            rx_events,
+            inlet_tx,
+            outlet_txs,
+        }))
+    }
+}
+
+/// An outlet has either cancelled or gone away; the items it had been pushed are lost,
+/// the items that failed to reach it remain in the buffer.
+fn outlet_stopped(
+    outlet_idx: usize,
+    cancelled: bool,
+    mut balancer: Balancer,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    mut outlet_txs: Vec<Option<ProducerTx>>,
+) -> TurnResult<BalanceFSM> {
+    let policy = if cancelled {
+        balancer.on_outlet_cancel
+    } else {
+        balancer.on_outlet_failure
+    };
+    balancer.downstream_states[outlet_idx] = DownstreamState::Gone;
+    // the cancelled outlet is to be completed, the one gone is just dropped
+    let stopped_outlet_tx = outlet_txs[outlet_idx].take().filter(|_| cancelled);
+
+    match policy {
+        BalanceOutletPolicySpec::Continue if balancer.live_outlets_count() > 0 => {
+            match stopped_outlet_tx {
+                Some(outlet_tx) => {
+                    let sent = Box::new(outlet_tx.send(ProducerMessage::Complete));
+                    let into_proceeding =
+                        move |_sent| proceed(balancer, rx_events, inlet_tx, outlet_txs);
+                    Ok(TurnOk::PollMore(BalanceFSM::SendingToDownstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(into_proceeding),
+                    }))
+                }
+                None => proceed(balancer, rx_events, inlet_tx, outlet_txs),
+            }
+        }
+
+        _ => {
+            outlet_txs[outlet_idx] = stopped_outlet_tx;
+            let message = if cancelled {
+                ProducerMessage::Complete
+            } else {
+                let reason: failure::Error = BalanceError::OutletClosed(outlet_idx).into();
+                ProducerMessage::Fail {
+                    failure: PortFailure::from(reason),
+                }
+            };
+            let inlet_tx =
+                Some(inlet_tx).filter(|_| balancer.upstream_state != UpstreamState::Complete);
+            Ok(TurnOk::PollMore(stopping(inlet_tx, outlet_txs, message)))
+        }
+    }
+}
+
+/// Cancels the upstream (if given) and sends the message to the remaining outlets.
+/// Both might be gone already, hence the send errors are ignored.
+fn stopping(
+    inlet_tx: Option<ConsumerTx>,
+    outlet_txs: Vec<Option<ProducerTx>>,
+    message: ProducerMessage,
+) -> BalanceFSM {
+    let upstream_sent: SendBoxedFuture<(), BalanceError> = match inlet_tx {
+        Some(inlet_tx) => Box::new(
+            inlet_tx
+                .send(ConsumerMessage::Cancel)
+                .then(|_| Ok::<_, BalanceError>(())),
+        ),
+        None => Box::new(future::ok(())),
+    };
+    let downstreams_sent =
+        future::join_all(outlet_txs.into_iter().flatten().map(move |outlet_tx| {
+            outlet_tx
+                .send(message.clone())
+                .then(|_| Ok::<_, BalanceError>(()))
+        }));
+
+    BalanceFSM::Stopping {
+        sents: Box::new(
+            upstream_sent
+                .and_then(move |()| downstreams_sent)
+                .map(|_| ()),
+        ),
+    }
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rxs: Vec<ProducerRx>,
+) -> SendBoxedStream<Event, BalanceError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| BalanceError::RxError);
+    let outlet_rx_events =
+        outlet_rxs
+            .into_iter()
+            .enumerate()
+            .map(move |(outlet_idx, outlet_rx)| {
+                outlet_rx
+                    .map(move |consumer_message| {
+                        Event::ConsumerMessage(outlet_idx, consumer_message)
+                    })
+                    .map_err(|()| BalanceError::RxError)
+                    .chain(stream::once(Ok(Event::OutletClosed(outlet_idx))))
+            });
+
+    outlet_rx_events.fold::<SendBoxedStream<Event, BalanceError>, _>(
+        Box::new(inlet_rx_events),
+        |acc, outlet_rx| Box::new(acc.select(outlet_rx)),
+    )
+}
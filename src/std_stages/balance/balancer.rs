@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+use crate::spec::{BalanceModeSpec, BalanceOutletPolicySpec};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamState {
+    Idle,
+    Pulled,
+    Complete,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownstreamState {
+    Busy,
+    Ready(usize),
+    Gone,
+}
+
+/// Decides which outlet gets the buffered items and how many items to pull from upstream.
+#[derive(Debug)]
+pub struct Balancer {
+    pub mode: BalanceModeSpec,
+    pub on_outlet_cancel: BalanceOutletPolicySpec,
+    pub on_outlet_failure: BalanceOutletPolicySpec,
+    pub upstream_state: UpstreamState,
+    pub downstream_states: Vec<DownstreamState>,
+    pub buffer: VecDeque<Vec<u8>>,
+    next_outlet: usize,
+}
+
+impl Balancer {
+    pub fn new(
+        mode: BalanceModeSpec,
+        on_outlet_cancel: BalanceOutletPolicySpec,
+        on_outlet_failure: BalanceOutletPolicySpec,
+        outlets_count: usize,
+    ) -> Self {
+        Self {
+            mode,
+            on_outlet_cancel,
+            on_outlet_failure,
+            upstream_state: UpstreamState::Idle,
+            downstream_states: (0..outlets_count).map(|_| DownstreamState::Busy).collect(),
+            buffer: VecDeque::new(),
+            next_outlet: 0,
+        }
+    }
+
+    pub fn live_outlets_count(&self) -> usize {
+        self.downstream_states
+            .iter()
+            .filter(|state| **state != DownstreamState::Gone)
+            .count()
+    }
+
+    /// Takes the items for the next push out of the buffer, marking the chosen outlet busy.
+    pub fn next_push(&mut self) -> Option<(usize, Vec<Vec<u8>>)> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        self.candidate().map(|(outlet_idx, max_items)| {
+            let items_to_send = std::cmp::min(max_items, self.buffer.len());
+            let items = self.buffer.drain(0..items_to_send).collect();
+            self.downstream_states[outlet_idx] = DownstreamState::Busy;
+            self.next_outlet = (outlet_idx + 1) % self.downstream_states.len();
+            (outlet_idx, items)
+        })
+    }
+
+    /// How many items to pull, if upstream should be pulled at all.
+    pub fn pull_size(&self) -> Option<usize> {
+        if self.upstream_state != UpstreamState::Idle || !self.buffer.is_empty() {
+            return None;
+        }
+
+        match self.mode {
+            BalanceModeSpec::RoundRobin => self.candidate().map(|(_, max_items)| max_items),
+            BalanceModeSpec::Demand => {
+                let credit = self
+                    .downstream_states
+                    .iter()
+                    .map(|state| match state {
+                        DownstreamState::Ready(max_items) => *max_items,
+                        _ => 0,
+                    })
+                    .sum();
+                Some(credit).filter(|credit| *credit > 0)
+            }
+        }
+    }
+
+    fn candidate(&self) -> Option<(usize, usize)> {
+        let outlets_count = self.downstream_states.len();
+        let mut live_outlets = (0..outlets_count)
+            .map(|offset| (self.next_outlet + offset) % outlets_count)
+            .filter(|outlet_idx| self.downstream_states[*outlet_idx] != DownstreamState::Gone);
+        let ready = |outlet_idx: usize| match self.downstream_states[outlet_idx] {
+            DownstreamState::Ready(max_items) if max_items > 0 => Some((outlet_idx, max_items)),
+            _ => None,
+        };
+
+        match self.mode {
+            BalanceModeSpec::RoundRobin => live_outlets.next().and_then(ready),
+            BalanceModeSpec::Demand => live_outlets.filter_map(ready).next(),
+        }
+    }
+}
+
+#[test]
+fn balancer_test() {
+    let new_balancer = |mode| {
+        let mut balancer = Balancer::new(
+            mode,
+            BalanceOutletPolicySpec::Shutdown,
+            BalanceOutletPolicySpec::Shutdown,
+            3,
+        );
+        balancer.downstream_states[1] = DownstreamState::Ready(2);
+        balancer.downstream_states[2] = DownstreamState::Ready(5);
+        balancer
+    };
+
+    let mut round_robin = new_balancer(BalanceModeSpec::RoundRobin);
+    assert_eq!(round_robin.pull_size(), None);
+    round_robin.downstream_states[0] = DownstreamState::Gone;
+    assert_eq!(round_robin.pull_size(), Some(2));
+    round_robin.buffer.extend(vec![vec![1], vec![2], vec![3]]);
+    assert_eq!(round_robin.next_push(), Some((1, vec![vec![1], vec![2]])));
+    assert_eq!(round_robin.next_push(), Some((2, vec![vec![3]])));
+    assert_eq!(round_robin.next_push(), None);
+
+    let mut demand = new_balancer(BalanceModeSpec::Demand);
+    assert_eq!(demand.pull_size(), Some(7));
+    demand.buffer.extend(vec![vec![1], vec![2], vec![3]]);
+    assert_eq!(demand.next_push(), Some((1, vec![vec![1], vec![2]])));
+    assert_eq!(demand.next_push(), Some((2, vec![vec![3]])));
+}
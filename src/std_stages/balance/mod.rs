@@ -0,0 +1,12 @@
+use super::*;
+
+mod balance;
+pub use balance::Balance;
+
+mod balancer;
+use balancer::Balancer;
+
+mod balance_impl_std_stage;
+
+mod balance_fsm;
+use balance_fsm::BalanceFSM;
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use crate::protocol::Schema;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct Batch {
+    pub inlet_schema: Schema,
+    pub outlet_schema: Schema,
+    pub max_size: usize,
+    pub max_latency: Option<Duration>,
+}
+
+impl Batch {
+    pub fn new(
+        schema: Schema,
+        max_size: usize,
+        max_latency: Option<Duration>,
+    ) -> Result<Self, StdStageError> {
+        if max_size == 0 {
+            return Err(StdStageError::InvalidParameter("max_size"));
+        }
+
+        Ok(Self {
+            outlet_schema: Schema::Array(Box::new(schema.clone())),
+            inlet_schema: schema,
+            max_size,
+            max_latency,
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use std::time::Instant;
+
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+use tokio::timer::Delay;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::batcher::UpstreamState;
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<BatchFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+    Deadline,
+}
+
+#[derive(Fail, Debug)]
+pub enum BatchError {
+    #[fail(display = "BatchError::RxError")]
+    RxError,
+
+    #[fail(display = "BatchError::TimerError")]
+    TimerError(#[cause] tokio::timer::Error),
+
+    #[fail(display = "BatchError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "BatchError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, BatchError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum BatchFSM {
+    ReceiveEvent {
+        batcher: Batcher,
+        rx_events: RxEventStream,
+        delay: Option<Delay>,
+        inlet_tx: ConsumerTx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl BatchFSM {
+    pub fn new(
+        batcher: Batcher,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_tx, outlet_rx) = outlet;
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rx);
+
+        BatchFSM::ReceiveEvent {
+            batcher,
+            rx_events,
+            delay: None,
+            inlet_tx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for BatchFSM {
+    type Item = ();
+    type Error = BatchError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            BatchFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| BatchError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(BatchFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            BatchFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| BatchError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(BatchFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            BatchFSM::ReceiveEvent {
+                batcher,
+                mut rx_events,
+                mut delay,
+                inlet_tx,
+                outlet_tx,
+            } => {
+                let deadline_reached = match delay.as_mut() {
+                    Some(delay) => delay
+                        .poll()
+                        .map_err(|err| BatchError::TimerError(err))?
+                        .is_ready(),
+                    None => false,
+                };
+                if deadline_reached {
+                    return handle_rx_event(
+                        Event::Deadline,
+                        batcher,
+                        rx_events,
+                        None,
+                        inlet_tx,
+                        outlet_tx,
+                    );
+                }
+
+                rx_events.poll().and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(BatchFSM::ReceiveEvent {
+                        batcher,
+                        rx_events,
+                        delay,
+                        inlet_tx,
+                        outlet_tx,
+                    })),
+
+                    Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                    Async::Ready(Some(event)) => {
+                        handle_rx_event(event, batcher, rx_events, delay, inlet_tx, outlet_tx)
+                    }
+                })
+            }
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    mut batcher: Batcher,
+    rx_events: RxEventStream,
+    delay: Option<Delay>,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<BatchFSM> {
+    match event {
+        Event::Deadline => proceed(batcher, rx_events, delay, inlet_tx, outlet_tx),
+
+        Event::ProducerMessage(ProducerMessage::Push { items }) => {
+            batcher.pushed(items, Instant::now());
+            proceed(batcher, rx_events, delay, inlet_tx, outlet_tx)
+        }
+
+        Event::ProducerMessage(ProducerMessage::Complete) => {
+            batcher.upstream_state = UpstreamState::Complete;
+            proceed(batcher, rx_events, delay, inlet_tx, outlet_tx)
+        }
+
+        Event::ProducerMessage(ProducerMessage::Fail { failure }) => {
+            let sent = Box::new(outlet_tx.send(ProducerMessage::Fail { failure }));
+            let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+            Ok(TurnOk::PollMore(BatchFSM::SendingToDownstream {
+                sent,
+                and_then: SendBoxFnOnce::from(shutdown),
+            }))
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => {
+            batcher.pulled(max_items);
+            proceed(batcher, rx_events, delay, inlet_tx, outlet_tx)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => {
+            let send_downstream_complete = move || {
+                let sent = Box::new(outlet_tx.send(ProducerMessage::Complete));
+                let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+                Ok(TurnOk::PollMore(BatchFSM::SendingToDownstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(shutdown),
+                }))
+            };
+
+            if batcher.upstream_state == UpstreamState::Complete {
+                send_downstream_complete()
+            } else {
+                let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+                Ok(TurnOk::PollMore(BatchFSM::SendingToUpstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(move |_inlet_tx| send_downstream_complete()),
+                }))
+            }
+        }
+    }
+}
+
+/// Flushes the due batches, completes the downstream once drained, or pulls the upstream.
+fn proceed(
+    mut batcher: Batcher,
+    rx_events: RxEventStream,
+    delay: Option<Delay>,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<BatchFSM> {
+    let now = Instant::now();
+
+    if let Some(items) = batcher.next_push(now) {
+        let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+        let into_proceeding =
+            move |outlet_tx| proceed(batcher, rx_events, delay, inlet_tx, outlet_tx);
+        Ok(TurnOk::PollMore(BatchFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(into_proceeding),
+        }))
+    } else if batcher.is_drained() {
+        let sent = Box::new(outlet_tx.send(ProducerMessage::Complete));
+        let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+        Ok(TurnOk::PollMore(BatchFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(shutdown),
+        }))
+    } else {
+        let delay = rearm(delay, batcher.deadline(), now);
+
+        match batcher.pull_size() {
+            Some(max_items) => {
+                batcher.upstream_state = UpstreamState::Pulled;
+                let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items }));
+                let into_receiving_events = move |inlet_tx| {
+                    Ok(TurnOk::PollMore(BatchFSM::ReceiveEvent {
+                        batcher,
+                        rx_events,
+                        delay,
+                        inlet_tx,
+                        outlet_tx,
+                    }))
+                };
+                Ok(TurnOk::PollMore(BatchFSM::SendingToUpstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(into_receiving_events),
+                }))
+            }
+            None => Ok(TurnOk::PollMore(BatchFSM::ReceiveEvent {
+                batcher,
+                rx_events,
+                delay,
+                inlet_tx,
+                outlet_tx,
+            })),
+        }
+    }
+}
+
+/// A deadline already passed needs no timer: the due batch goes out with the next downstream pull.
+fn rearm(delay: Option<Delay>, deadline: Option<Instant>, now: Instant) -> Option<Delay> {
+    match (delay, deadline) {
+        (_, Some(deadline)) if deadline <= now => None,
+        (Some(delay), Some(deadline)) if delay.deadline() == deadline => Some(delay),
+        (_, Some(deadline)) => Some(Delay::new(deadline)),
+        (_, None) => None,
+    }
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, BatchError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| BatchError::RxError);
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| BatchError::RxError);
+
+    Box::new(inlet_rx_events.select(outlet_rx_events))
+}
@@ -0,0 +1,38 @@
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for Batch {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.inlet_schema]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.outlet_schema]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        mut inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        mut outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.len() == 1);
+        assert!(outlets.len() == 1);
+        let inlet = inlets.pop().unwrap();
+        let outlet = outlets.pop().unwrap();
+
+        let batcher = Batcher::new(self.max_size, self.max_latency);
+
+        Box::new(
+            BatchFSM::new(batcher, inlet, outlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
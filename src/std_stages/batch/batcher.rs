@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::super::avro_encoding::encode_long;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamState {
+    Idle,
+    Pulled,
+    Complete,
+}
+
+/// Groups the items into batches, each of them encoded as an Avro array datum.
+///
+/// A batch is flushed once it has `max_size` items, once its oldest item has waited for `max_latency`,
+/// or once the upstream has completed; either way only as far as the downstream has pulled.
+#[derive(Debug)]
+pub struct Batcher {
+    pub upstream_state: UpstreamState,
+    max_size: usize,
+    max_latency: Option<Duration>,
+    buffer: VecDeque<(Instant, Vec<u8>)>,
+    downstream_ready: Option<usize>,
+}
+
+impl Batcher {
+    pub fn new(max_size: usize, max_latency: Option<Duration>) -> Self {
+        Self {
+            upstream_state: UpstreamState::Idle,
+            max_size,
+            max_latency,
+            buffer: VecDeque::new(),
+            downstream_ready: None,
+        }
+    }
+
+    pub fn pushed(&mut self, items: Vec<Vec<u8>>, now: Instant) {
+        self.upstream_state = UpstreamState::Idle;
+        self.buffer
+            .extend(items.into_iter().map(|item| (now, item)));
+    }
+
+    pub fn pulled(&mut self, max_items: usize) {
+        self.downstream_ready = Some(max_items);
+    }
+
+    /// The upstream has completed and every item has been flushed.
+    pub fn is_drained(&self) -> bool {
+        self.upstream_state == UpstreamState::Complete && self.buffer.is_empty()
+    }
+
+    /// When the oldest item is due.
+    pub fn deadline(&self) -> Option<Instant> {
+        match (self.buffer.front(), self.max_latency) {
+            (Some((received_at, _)), Some(max_latency)) => Some(*received_at + max_latency),
+            (_, _) => None,
+        }
+    }
+
+    /// The batches for the next push, if the downstream has pulled and there are any due.
+    pub fn next_push(&mut self, now: Instant) -> Option<Vec<Vec<u8>>> {
+        let max_items = self.downstream_ready.unwrap_or(0);
+        let mut batches = Vec::new();
+
+        while batches.len() < max_items && self.is_due(now) {
+            let batch_size = std::cmp::min(self.max_size, self.buffer.len());
+            let items = self.buffer.drain(0..batch_size).map(|(_, item)| item);
+            batches.push(encode_array(batch_size, items));
+        }
+
+        if batches.is_empty() {
+            None
+        } else {
+            self.downstream_ready = None;
+            Some(batches)
+        }
+    }
+
+    /// How many items to pull, if upstream should be pulled at all: at most a batch is buffered.
+    pub fn pull_size(&self) -> Option<usize> {
+        if self.upstream_state == UpstreamState::Idle && self.buffer.len() < self.max_size {
+            Some(self.max_size - self.buffer.len())
+        } else {
+            None
+        }
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        !self.buffer.is_empty()
+            && (self.buffer.len() >= self.max_size
+                || self.upstream_state == UpstreamState::Complete
+                || self
+                    .deadline()
+                    .map(|deadline| deadline <= now)
+                    .unwrap_or(false))
+    }
+}
+
+/// An array is encoded as a block of `count` items followed by an empty block.
+fn encode_array<I: Iterator<Item = Vec<u8>>>(count: usize, items: I) -> Vec<u8> {
+    let mut datum = encode_long(count as i64);
+    for item in items {
+        datum.extend(item);
+    }
+    datum.push(0);
+    datum
+}
+
+#[test]
+fn batcher_test() {
+    let t0 = Instant::now();
+    let mut batcher = Batcher::new(2, Some(Duration::from_millis(100)));
+
+    assert_eq!(batcher.pull_size(), Some(2));
+    batcher.pushed(vec![vec![1], vec![2], vec![3]], t0);
+    assert_eq!(batcher.pull_size(), None);
+    assert_eq!(batcher.next_push(t0), None);
+
+    batcher.pulled(5);
+    assert_eq!(batcher.next_push(t0), Some(vec![vec![4, 1, 2, 0]]));
+
+    batcher.pulled(5);
+    assert_eq!(batcher.next_push(t0), None);
+    assert_eq!(batcher.deadline(), Some(t0 + Duration::from_millis(100)));
+    assert_eq!(
+        batcher.next_push(t0 + Duration::from_millis(100)),
+        Some(vec![vec![2, 3, 0]])
+    );
+}
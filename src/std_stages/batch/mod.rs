@@ -0,0 +1,12 @@
+use super::*;
+
+mod batch;
+pub use batch::Batch;
+
+mod batcher;
+use batcher::Batcher;
+
+mod batch_impl_std_stage;
+
+mod batch_fsm;
+use batch_fsm::BatchFSM;
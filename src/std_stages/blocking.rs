@@ -0,0 +1,17 @@
+use futures::Async;
+
+/// Runs `f`, which blocks on file or stdio I/O, in a way that does not stall the other tasks
+/// of the runtime's thread pool: the pool hands its other tasks to another thread meanwhile.
+///
+/// `NotReady` means the pool is out of threads to block: the current task is notified
+/// once it may retry. Outside of a thread pool `f` just runs in place.
+pub fn poll_blocking<F, T>(f: F) -> Async<T>
+where
+    F: FnOnce() -> T,
+{
+    let mut f = Some(f);
+    match tokio_threadpool::blocking(|| (f.take().expect("called once"))()) {
+        Ok(polled) => polled,
+        Err(_not_in_thread_pool) => Async::Ready((f.take().expect("not called yet"))()),
+    }
+}
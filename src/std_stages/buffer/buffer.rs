@@ -0,0 +1,29 @@
+use crate::protocol::Schema;
+use crate::spec::BufferOverflowSpec;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct Buffer {
+    pub schema: Schema,
+    pub capacity: usize,
+    pub overflow: BufferOverflowSpec,
+}
+
+impl Buffer {
+    pub fn new(
+        schema: Schema,
+        capacity: usize,
+        overflow: BufferOverflowSpec,
+    ) -> Result<Self, StdStageError> {
+        if capacity == 0 {
+            return Err(StdStageError::InvalidParameter("capacity"));
+        }
+
+        Ok(Self {
+            schema,
+            capacity,
+            overflow,
+        })
+    }
+}
@@ -0,0 +1,270 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::overflow_buffer::{Overflow, UpstreamState};
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<BufferFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum BufferError {
+    #[fail(display = "BufferError::RxError")]
+    RxError,
+
+    #[fail(display = "BufferError::Overflow")]
+    Overflow,
+
+    #[fail(display = "BufferError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "BufferError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, BufferError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum BufferFSM {
+    ReceiveEvent {
+        buffer: OverflowBuffer,
+        rx_events: RxEventStream,
+        inlet_tx: ConsumerTx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl BufferFSM {
+    pub fn new(
+        buffer: OverflowBuffer,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_tx, outlet_rx) = outlet;
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rx);
+
+        BufferFSM::ReceiveEvent {
+            buffer,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for BufferFSM {
+    type Item = ();
+    type Error = BufferError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            BufferFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| BufferError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(BufferFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            BufferFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| BufferError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(BufferFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            BufferFSM::ReceiveEvent {
+                buffer,
+                mut rx_events,
+                inlet_tx,
+                outlet_tx,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(BufferFSM::ReceiveEvent {
+                    buffer,
+                    rx_events,
+                    inlet_tx,
+                    outlet_tx,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => {
+                    handle_rx_event(event, buffer, rx_events, inlet_tx, outlet_tx)
+                }
+            }),
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    mut buffer: OverflowBuffer,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<BufferFSM> {
+    match event {
+        Event::ProducerMessage(ProducerMessage::Push { items }) => match buffer.pushed(items) {
+            Ok(dropped) => {
+                if dropped > 0 {
+                    warn!(
+                        "Buffer overflow: dropped {} item(s), {} in total",
+                        dropped, buffer.dropped_total
+                    );
+                }
+                proceed(buffer, rx_events, inlet_tx, outlet_tx)
+            }
+            Err(Overflow) => {
+                let failure =
+                    PortFailure::from(Into::<failure::Error>::into(BufferError::Overflow));
+                shutdown(
+                    buffer,
+                    Some(inlet_tx),
+                    ProducerMessage::Fail { failure },
+                    outlet_tx,
+                )
+            }
+        },
+
+        Event::ProducerMessage(ProducerMessage::Complete) => {
+            buffer.upstream_state = UpstreamState::Complete;
+            proceed(buffer, rx_events, inlet_tx, outlet_tx)
+        }
+
+        Event::ProducerMessage(ProducerMessage::Fail { failure }) => {
+            shutdown(buffer, None, ProducerMessage::Fail { failure }, outlet_tx)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => {
+            buffer.pulled(max_items);
+            proceed(buffer, rx_events, inlet_tx, outlet_tx)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => {
+            let inlet_tx =
+                Some(inlet_tx).filter(|_| buffer.upstream_state != UpstreamState::Complete);
+            shutdown(buffer, inlet_tx, ProducerMessage::Complete, outlet_tx)
+        }
+    }
+}
+
+/// Pushes the buffered items, completes the downstream once drained, or pulls the upstream.
+fn proceed(
+    mut buffer: OverflowBuffer,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<BufferFSM> {
+    if let Some(items) = buffer.next_push() {
+        let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+        let into_proceeding = move |outlet_tx| proceed(buffer, rx_events, inlet_tx, outlet_tx);
+        Ok(TurnOk::PollMore(BufferFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(into_proceeding),
+        }))
+    } else if buffer.is_drained() {
+        shutdown(buffer, None, ProducerMessage::Complete, outlet_tx)
+    } else if let Some(max_items) = buffer.pull_size() {
+        buffer.upstream_state = UpstreamState::Pulled;
+        let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items }));
+        let into_receiving_events = move |inlet_tx| {
+            Ok(TurnOk::PollMore(BufferFSM::ReceiveEvent {
+                buffer,
+                rx_events,
+                inlet_tx,
+                outlet_tx,
+            }))
+        };
+        Ok(TurnOk::PollMore(BufferFSM::SendingToUpstream {
+            sent,
+            and_then: SendBoxFnOnce::from(into_receiving_events),
+        }))
+    } else {
+        Ok(TurnOk::PollMore(BufferFSM::ReceiveEvent {
+            buffer,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }))
+    }
+}
+
+/// Cancels the upstream (if given), then sends the last message downstream.
+fn shutdown(
+    buffer: OverflowBuffer,
+    inlet_tx: Option<ConsumerTx>,
+    downstream_bye_message: ProducerMessage,
+    outlet_tx: ProducerTx,
+) -> TurnResult<BufferFSM> {
+    if buffer.dropped_total > 0 {
+        info!(
+            "Buffer shutting down, {} item(s) dropped in total",
+            buffer.dropped_total
+        );
+    }
+
+    let send_downstream_termination = move || {
+        let sent = Box::new(outlet_tx.send(downstream_bye_message));
+        let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+        Ok(TurnOk::PollMore(BufferFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(shutdown),
+        }))
+    };
+
+    match inlet_tx {
+        Some(inlet_tx) => {
+            let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+            Ok(TurnOk::PollMore(BufferFSM::SendingToUpstream {
+                sent,
+                and_then: SendBoxFnOnce::from(move |_inlet_tx| send_downstream_termination()),
+            }))
+        }
+        None => send_downstream_termination(),
+    }
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, BufferError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| BufferError::RxError);
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| BufferError::RxError);
+
+    Box::new(inlet_rx_events.select(outlet_rx_events))
+}
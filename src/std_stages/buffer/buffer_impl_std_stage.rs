@@ -0,0 +1,38 @@
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for Buffer {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.schema]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.schema]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        mut inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        mut outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.len() == 1);
+        assert!(outlets.len() == 1);
+        let inlet = inlets.pop().unwrap();
+        let outlet = outlets.pop().unwrap();
+
+        let buffer = OverflowBuffer::new(self.capacity, self.overflow);
+
+        Box::new(
+            BufferFSM::new(buffer, inlet, outlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod buffer;
+pub use buffer::Buffer;
+
+mod overflow_buffer;
+use overflow_buffer::OverflowBuffer;
+
+mod buffer_impl_std_stage;
+
+mod buffer_fsm;
+use buffer_fsm::BufferFSM;
@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+
+use crate::spec::BufferOverflowSpec;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamState {
+    Idle,
+    Pulled,
+    Complete,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Overflow;
+
+#[derive(Debug)]
+pub struct OverflowBuffer {
+    pub upstream_state: UpstreamState,
+    pub dropped_total: usize,
+    capacity: usize,
+    overflow: BufferOverflowSpec,
+    items: VecDeque<Vec<u8>>,
+    downstream_ready: Option<usize>,
+}
+
+impl OverflowBuffer {
+    pub fn new(capacity: usize, overflow: BufferOverflowSpec) -> Self {
+        Self {
+            upstream_state: UpstreamState::Idle,
+            dropped_total: 0,
+            capacity,
+            overflow,
+            items: VecDeque::new(),
+            downstream_ready: None,
+        }
+    }
+
+    /// Stores the pushed items, returns how many items have been dropped to fit them in.
+    pub fn pushed(&mut self, items: Vec<Vec<u8>>) -> Result<usize, Overflow> {
+        self.upstream_state = UpstreamState::Idle;
+
+        let excess = (self.items.len() + items.len()).saturating_sub(self.capacity);
+        let dropped = match self.overflow {
+            _ if excess == 0 => {
+                self.items.extend(items);
+                0
+            }
+            BufferOverflowSpec::Fail => return Err(Overflow),
+            // the upstream pushes no more than it was pulled for, hence the excess is not expected
+            BufferOverflowSpec::Backpressure => {
+                self.items.extend(items);
+                0
+            }
+            BufferOverflowSpec::DropOldest => {
+                self.items.extend(items);
+                self.items.drain(0..excess);
+                excess
+            }
+            BufferOverflowSpec::DropNewest => {
+                let room = items.len() - excess;
+                self.items.extend(items.into_iter().take(room));
+                excess
+            }
+        };
+
+        self.dropped_total += dropped;
+        Ok(dropped)
+    }
+
+    pub fn pulled(&mut self, max_items: usize) {
+        self.downstream_ready = Some(max_items);
+    }
+
+    pub fn is_drained(&self) -> bool {
+        self.upstream_state == UpstreamState::Complete && self.items.is_empty()
+    }
+
+    pub fn next_push(&mut self) -> Option<Vec<Vec<u8>>> {
+        match self.downstream_ready {
+            Some(max_items) if max_items > 0 && !self.items.is_empty() => {
+                self.downstream_ready = None;
+                let items_to_send = std::cmp::min(max_items, self.items.len());
+                Some(self.items.drain(0..items_to_send).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// The upstream is pulled whenever idle: for as much as there is room for,
+    /// or (unless backpressuring) for the whole capacity when there is none.
+    pub fn pull_size(&self) -> Option<usize> {
+        if self.upstream_state != UpstreamState::Idle {
+            return None;
+        }
+
+        let room = self.capacity.saturating_sub(self.items.len());
+        match self.overflow {
+            BufferOverflowSpec::Backpressure if room == 0 => None,
+            _ if room == 0 => Some(self.capacity),
+            _ => Some(room),
+        }
+    }
+}
+
+#[test]
+fn overflow_buffer_test() {
+    let items = |range: std::ops::Range<u8>| range.map(|i| vec![i]).collect::<Vec<_>>();
+
+    let mut drop_oldest = OverflowBuffer::new(3, BufferOverflowSpec::DropOldest);
+    assert_eq!(drop_oldest.pushed(items(0..5)), Ok(2));
+    drop_oldest.pulled(10);
+    assert_eq!(drop_oldest.next_push(), Some(items(2..5)));
+
+    let mut drop_newest = OverflowBuffer::new(3, BufferOverflowSpec::DropNewest);
+    assert_eq!(drop_newest.pushed(items(0..5)), Ok(2));
+    assert_eq!(drop_newest.pull_size(), Some(3));
+    drop_newest.pulled(10);
+    assert_eq!(drop_newest.next_push(), Some(items(0..3)));
+
+    let mut backpressure = OverflowBuffer::new(3, BufferOverflowSpec::Backpressure);
+    assert_eq!(backpressure.pushed(items(0..3)), Ok(0));
+    assert_eq!(backpressure.pull_size(), None);
+
+    let mut fail = OverflowBuffer::new(3, BufferOverflowSpec::Fail);
+    assert_eq!(fail.pushed(items(0..4)), Err(Overflow));
+}
@@ -0,0 +1,19 @@
+use crate::protocol::Schema;
+
+/// Drains the inlets one after another, pulling only from the current one.
+#[derive(Debug)]
+pub struct Concat {
+    pub schema: Schema,
+    pub inlets_count: usize,
+    pub eagerly_fail: bool,
+}
+
+impl Concat {
+    pub fn new(schema: Schema, inlets_count: usize, eagerly_fail: bool) -> Self {
+        Self {
+            schema,
+            inlets_count,
+            eagerly_fail,
+        }
+    }
+}
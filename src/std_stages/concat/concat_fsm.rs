@@ -0,0 +1,337 @@
+use futures::future;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use boxfnonce::SendBoxFnOnce;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<ConcatFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(usize, ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum ConcatError {
+    #[fail(display = "ConcatError::RxError")]
+    RxError,
+
+    #[fail(display = "ConcatError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "ConcatError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+
+    #[fail(display = "ConcatError::PulledTwice")]
+    PulledTwice,
+}
+
+pub type RxEventStream = SendBoxedStream<Event, ConcatError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSendSingle = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+pub type UpstreamsSend = SendBoxedFuture<Vec<ConsumerTx>, mpsc::SendError<ConsumerMessage>>;
+
+pub enum ConcatFSM {
+    ReceiveEvent {
+        eagerly_fail: bool,
+        progress: InletsProgress,
+        /// The downstream's pending pull, forwarded to the current inlet.
+        pulled: Option<usize>,
+        rx_events: RxEventStream,
+        outlet_tx: ProducerTx,
+        inlet_txs: Vec<ConsumerTx>,
+    },
+    SendingToUpstreams {
+        sents: UpstreamsSend,
+        and_then: Continue<(Vec<ConsumerTx>,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl ConcatFSM {
+    pub fn new(
+        inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        outlet: (ProducerTx, ProducerRx),
+        eagerly_fail: bool,
+    ) -> Self {
+        let (outlet_tx, outlet_rx) = outlet;
+        let (inlet_txs, inlet_rxs): (Vec<_>, Vec<_>) = inlets.into_iter().unzip();
+
+        let rx_events = rxs_into_event_stream(inlet_rxs, outlet_rx);
+
+        let progress = InletsProgress::new(inlet_txs.len());
+
+        ConcatFSM::ReceiveEvent {
+            eagerly_fail,
+            progress,
+            pulled: None,
+            rx_events,
+            outlet_tx,
+            inlet_txs,
+        }
+    }
+}
+
+impl FSM for ConcatFSM {
+    type Item = ();
+    type Error = ConcatError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            ConcatFSM::SendingToUpstreams {
+                mut sents,
+                and_then,
+            } => sents
+                .poll()
+                .map_err(|err| ConcatError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ConcatFSM::SendingToUpstreams {
+                        sents,
+                        and_then,
+                    })),
+
+                    Async::Ready(inlet_txs) => and_then.call(inlet_txs),
+                }),
+
+            ConcatFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| ConcatError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ConcatFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            ConcatFSM::ReceiveEvent {
+                eagerly_fail,
+                progress,
+                pulled,
+                mut rx_events,
+                outlet_tx,
+                inlet_txs,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(ConcatFSM::ReceiveEvent {
+                    eagerly_fail,
+                    progress,
+                    pulled,
+                    rx_events,
+                    outlet_tx,
+                    inlet_txs,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => handle_rx_event(
+                    event,
+                    eagerly_fail,
+                    progress,
+                    pulled,
+                    rx_events,
+                    outlet_tx,
+                    inlet_txs,
+                ),
+            }),
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+
+    eagerly_fail: bool,
+    mut progress: InletsProgress,
+    pulled: Option<usize>,
+    rx_events: RxEventStream,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<ConcatFSM> {
+    match event {
+        Event::ConsumerMessage(ConsumerMessage::Pull { .. }) if pulled.is_some() => {
+            Err(ConcatError::PulledTwice)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => match progress.current() {
+            None => shutdown(progress, ProducerMessage::Complete, outlet_tx, inlet_txs),
+            Some(current) => pull_current_upstream(
+                eagerly_fail,
+                progress,
+                current,
+                max_items,
+                rx_events,
+                outlet_tx,
+                inlet_txs,
+            ),
+        },
+
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => {
+            shutdown(progress, ProducerMessage::Complete, outlet_tx, inlet_txs)
+        }
+
+        Event::ProducerMessage(_, ProducerMessage::Push { items }) => {
+            let downstream_push_sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+            let into_receiving_events = move |outlet_tx| {
+                Ok(TurnOk::PollMore(ConcatFSM::ReceiveEvent {
+                    eagerly_fail,
+                    progress,
+                    pulled: None,
+                    rx_events,
+                    outlet_tx,
+                    inlet_txs,
+                }))
+            };
+            Ok(TurnOk::PollMore(ConcatFSM::SendingToDownstream {
+                sent: downstream_push_sent,
+                and_then: SendBoxFnOnce::from(into_receiving_events),
+            }))
+        }
+
+        Event::ProducerMessage(producer_idx, ProducerMessage::Fail { failure }) if eagerly_fail => {
+            progress.finish(producer_idx);
+            shutdown(
+                progress,
+                ProducerMessage::Fail { failure },
+                outlet_tx,
+                inlet_txs,
+            )
+        }
+
+        // a failed upstream is then treated as a completed one, the same way `merge` does
+        Event::ProducerMessage(producer_idx, _) => match (progress.finish(producer_idx), pulled) {
+            (Progress::Done, _) => {
+                shutdown(progress, ProducerMessage::Complete, outlet_tx, inlet_txs)
+            }
+
+            (Progress::MovedOn(next), Some(max_items)) => pull_current_upstream(
+                eagerly_fail,
+                progress,
+                next,
+                max_items,
+                rx_events,
+                outlet_tx,
+                inlet_txs,
+            ),
+
+            (_, pulled) => Ok(TurnOk::PollMore(ConcatFSM::ReceiveEvent {
+                eagerly_fail,
+                progress,
+                pulled,
+                rx_events,
+                outlet_tx,
+                inlet_txs,
+            })),
+        },
+    }
+}
+
+/// Forwards the downstream's pull to the inlet being drained.
+fn pull_current_upstream(
+    eagerly_fail: bool,
+    progress: InletsProgress,
+    current: usize,
+    max_items: usize,
+    rx_events: RxEventStream,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<ConcatFSM> {
+    let upstream_pull_sents = Box::new(future::join_all(
+        inlet_txs
+            .into_iter()
+            .enumerate()
+            .map::<UpstreamSendSingle, _>(move |(inlet_idx, inlet_tx)| {
+                if inlet_idx == current {
+                    Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items }))
+                } else {
+                    Box::new(future::ok(inlet_tx))
+                }
+            }),
+    ));
+    let into_receiving_events = move |inlet_txs| {
+        Ok(TurnOk::PollMore(ConcatFSM::ReceiveEvent {
+            eagerly_fail,
+            progress,
+            pulled: Some(max_items),
+            rx_events,
+            outlet_tx,
+            inlet_txs,
+        }))
+    };
+    Ok(TurnOk::PollMore(ConcatFSM::SendingToUpstreams {
+        sents: upstream_pull_sents,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }))
+}
+
+/// Cancels the unfinished upstreams, then sends `downstream_bye_message`.
+fn shutdown(
+    progress: InletsProgress,
+    downstream_bye_message: ProducerMessage,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<ConcatFSM> {
+    let upstream_cancel_sents = Box::new(future::join_all(
+        progress
+            .finished()
+            .to_vec()
+            .into_iter()
+            .zip(inlet_txs.into_iter())
+            .map::<UpstreamSendSingle, _>(|(finished, inlet_tx)| {
+                if finished {
+                    Box::new(future::ok(inlet_tx))
+                } else {
+                    Box::new(inlet_tx.send(ConsumerMessage::Cancel))
+                }
+            }),
+    ));
+
+    let send_downstream_termination = move |_inlet_txs| {
+        let downstream_terminate_sent = Box::new(outlet_tx.send(downstream_bye_message));
+        let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+        Ok(TurnOk::PollMore(ConcatFSM::SendingToDownstream {
+            sent: downstream_terminate_sent,
+            and_then: SendBoxFnOnce::from(shutdown),
+        }))
+    };
+
+    Ok(TurnOk::PollMore(ConcatFSM::SendingToUpstreams {
+        sents: upstream_cancel_sents,
+        and_then: SendBoxFnOnce::from(send_downstream_termination),
+    }))
+}
+
+fn rxs_into_event_stream(
+    inlet_rxs: Vec<ConsumerRx>,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, ConcatError> {
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| ConcatError::RxError);
+
+    let inlet_rx_events = inlet_rxs
+        .into_iter()
+        .enumerate()
+        .map(move |(inlet_idx, inlet_rx)| {
+            inlet_rx
+                .map(move |producer_message| Event::ProducerMessage(inlet_idx, producer_message))
+                .map_err(|()| ConcatError::RxError)
+        });
+
+    inlet_rx_events.fold::<SendBoxedStream<Event, ConcatError>, _>(
+        Box::new(outlet_rx_events),
+        |acc, inlet_rx| Box::new(acc.select(inlet_rx)),
+    )
+}
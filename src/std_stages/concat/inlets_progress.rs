@@ -0,0 +1,77 @@
+/// Which of the inlets the `concat` std stage drains: the first one that has not finished yet.
+#[derive(Debug)]
+pub struct InletsProgress {
+    current: usize,
+    finished: Vec<bool>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Progress {
+    /// The same inlet is still being drained.
+    Unchanged,
+    /// The given inlet is to be drained from now on: the pending pull (if any) goes to it.
+    MovedOn(usize),
+    /// All of the inlets have finished.
+    Done,
+}
+
+impl InletsProgress {
+    pub fn new(inlets_count: usize) -> Self {
+        Self {
+            current: 0,
+            finished: vec![false; inlets_count],
+        }
+    }
+
+    /// The inlet being drained; none once all of them have finished.
+    pub fn current(&self) -> Option<usize> {
+        if self.current < self.finished.len() {
+            Some(self.current)
+        } else {
+            None
+        }
+    }
+
+    pub fn finished(&self) -> &[bool] {
+        &self.finished
+    }
+
+    /// Marks the inlet as completed (or failed): the later inlets may finish before the current one.
+    pub fn finish(&mut self, inlet_idx: usize) -> Progress {
+        self.finished[inlet_idx] = true;
+
+        let next = (self.current..self.finished.len())
+            .find(|idx| !self.finished[*idx])
+            .unwrap_or(self.finished.len());
+        let progress = if next == self.finished.len() {
+            Progress::Done
+        } else if next == self.current {
+            Progress::Unchanged
+        } else {
+            Progress::MovedOn(next)
+        };
+        self.current = next;
+        progress
+    }
+}
+
+#[test]
+fn inlets_progress_test() {
+    let mut progress = InletsProgress::new(4);
+    assert_eq!(progress.current(), Some(0));
+
+    // a later inlet finishes first: the current one is still drained
+    assert_eq!(progress.finish(2), Progress::Unchanged);
+    assert_eq!(progress.current(), Some(0));
+
+    // the finished inlets are skipped
+    assert_eq!(progress.finish(0), Progress::MovedOn(1));
+    assert_eq!(progress.finish(1), Progress::MovedOn(3));
+    assert_eq!(progress.current(), Some(3));
+
+    assert_eq!(progress.finish(3), Progress::Done);
+    assert_eq!(progress.current(), None);
+    assert_eq!(progress.finished(), &[true, true, true, true]);
+
+    assert_eq!(InletsProgress::new(0).current(), None);
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod concat;
+pub use concat::Concat;
+
+mod inlets_progress;
+use inlets_progress::{InletsProgress, Progress};
+
+mod concat_impl_std_stage;
+
+mod concat_fsm;
+use concat_fsm::ConcatFSM;
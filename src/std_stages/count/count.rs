@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use crate::protocol::Schema;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct Count {
+    pub schema: Schema,
+    pub report_interval: Duration,
+    pub expected_min: Option<u64>,
+    pub expected_max: Option<u64>,
+}
+
+impl Count {
+    pub fn new(
+        schema: Schema,
+        report_interval: Duration,
+        expected_min: Option<u64>,
+        expected_max: Option<u64>,
+    ) -> Result<Self, StdStageError> {
+        if report_interval == Duration::from_millis(0) {
+            return Err(StdStageError::InvalidParameter("report_interval_ms"));
+        }
+        if let (Some(min), Some(max)) = (expected_min, expected_max) {
+            if min > max {
+                return Err(StdStageError::InvalidParameter("expected_min"));
+            }
+        }
+
+        Ok(Self {
+            schema,
+            report_interval,
+            expected_min,
+            expected_max,
+        })
+    }
+}
@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+use tokio::timer::Delay;
+
+use crate::futures::fsm::*;
+use crate::futures::SendBoxedFuture;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+
+use super::counter::CounterError;
+use super::*;
+
+const PULL_SIZE: usize = 256;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<CountFSM>>;
+
+#[derive(Fail, Debug)]
+pub enum CountError {
+    #[fail(display = "CountError::RxError")]
+    RxError,
+
+    #[fail(display = "CountError::TimerError")]
+    TimerError(#[cause] tokio::timer::Error),
+
+    #[fail(display = "CountError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "CountError::UnexpectedTotal")]
+    UnexpectedTotal(#[cause] CounterError),
+
+    #[fail(display = "CountError::UpstreamFailed: {}", _0)]
+    UpstreamFailed(String),
+}
+
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub struct Reporting {
+    counter: Counter,
+    interval: Duration,
+    next_report: Delay,
+    expected_min: Option<u64>,
+    expected_max: Option<u64>,
+}
+
+pub enum CountFSM {
+    ReceiveEvent {
+        reporting: Reporting,
+        inlet_rx: ConsumerRx,
+        inlet_tx: ConsumerTx,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+}
+
+impl CountFSM {
+    pub fn new(
+        counter: Counter,
+        interval: Duration,
+        expected_min: Option<u64>,
+        expected_max: Option<u64>,
+        inlet: (ConsumerTx, ConsumerRx),
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let reporting = Reporting {
+            counter,
+            interval,
+            next_report: Delay::new(Instant::now() + interval),
+            expected_min,
+            expected_max,
+        };
+        pulling_upstream(reporting, inlet_rx, inlet_tx)
+    }
+}
+
+impl FSM for CountFSM {
+    type Item = ();
+    type Error = CountError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            CountFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| CountError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(CountFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            CountFSM::ReceiveEvent {
+                mut reporting,
+                mut inlet_rx,
+                inlet_tx,
+            } => {
+                let report_due = reporting
+                    .next_report
+                    .poll()
+                    .map_err(|err| CountError::TimerError(err))?
+                    .is_ready();
+                if report_due {
+                    let now = Instant::now();
+                    info!("Count: {}", reporting.counter.report(now));
+                    reporting.next_report = Delay::new(now + reporting.interval);
+                    return Ok(TurnOk::PollMore(CountFSM::ReceiveEvent {
+                        reporting,
+                        inlet_rx,
+                        inlet_tx,
+                    }));
+                }
+
+                match inlet_rx.poll() {
+                    Err(()) => Err(CountError::RxError),
+
+                    Ok(Async::NotReady) => Ok(TurnOk::Suspend(CountFSM::ReceiveEvent {
+                        reporting,
+                        inlet_rx,
+                        inlet_tx,
+                    })),
+
+                    Ok(Async::Ready(None)) => Ok(TurnOk::Ready(())),
+
+                    Ok(Async::Ready(Some(ProducerMessage::Push { items }))) => {
+                        reporting.counter.record(&items);
+                        Ok(TurnOk::PollMore(pulling_upstream(
+                            reporting, inlet_rx, inlet_tx,
+                        )))
+                    }
+
+                    Ok(Async::Ready(Some(ProducerMessage::Complete))) => {
+                        info!(
+                            "Count complete: {}",
+                            reporting.counter.summary(Instant::now())
+                        );
+                        reporting
+                            .counter
+                            .check_total(reporting.expected_min, reporting.expected_max)
+                            .map(|()| TurnOk::Ready(()))
+                            .map_err(|reason| CountError::UnexpectedTotal(reason))
+                    }
+
+                    Ok(Async::Ready(Some(ProducerMessage::Fail { failure }))) => {
+                        info!(
+                            "Count failed: {}",
+                            reporting.counter.summary(Instant::now())
+                        );
+                        Err(CountError::UpstreamFailed(failure.message))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn pulling_upstream(reporting: Reporting, inlet_rx: ConsumerRx, inlet_tx: ConsumerTx) -> CountFSM {
+    let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull {
+        max_items: PULL_SIZE,
+    }));
+    let into_receiving_events = move |inlet_tx| {
+        Ok(TurnOk::PollMore(CountFSM::ReceiveEvent {
+            reporting,
+            inlet_rx,
+            inlet_tx,
+        }))
+    };
+    CountFSM::SendingToUpstream {
+        sent,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }
+}
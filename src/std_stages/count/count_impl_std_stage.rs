@@ -0,0 +1,45 @@
+use std::time::Instant;
+
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for Count {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.schema]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        mut inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.len() == 1);
+        assert!(outlets.is_empty());
+        let inlet = inlets.pop().unwrap();
+
+        let counter = Counter::new(Instant::now());
+
+        Box::new(
+            CountFSM::new(
+                counter,
+                self.report_interval,
+                self.expected_min,
+                self.expected_max,
+                inlet,
+            )
+            .into_fsm_future()
+            .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
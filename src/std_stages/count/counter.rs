@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+#[derive(Fail, Debug)]
+pub enum CounterError {
+    #[fail(
+        display = "CounterError::UnexpectedTotal [total: {}; expected-min: {:?}; expected-max: {:?}]",
+        total, expected_min, expected_max
+    )]
+    UnexpectedTotal {
+        total: u64,
+        expected_min: Option<u64>,
+        expected_max: Option<u64>,
+    },
+}
+
+/// Counts the items and their bytes, overall and since the last report.
+#[derive(Debug)]
+pub struct Counter {
+    started_at: Instant,
+    items_total: u64,
+    bytes_total: u64,
+    reported_at: Instant,
+    items_reported: u64,
+    bytes_reported: u64,
+}
+
+impl Counter {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started_at: now,
+            items_total: 0,
+            bytes_total: 0,
+            reported_at: now,
+            items_reported: 0,
+            bytes_reported: 0,
+        }
+    }
+
+    pub fn record(&mut self, items: &[Vec<u8>]) {
+        self.items_total += items.len() as u64;
+        self.bytes_total += items.iter().map(|item| item.len() as u64).sum::<u64>();
+    }
+
+    /// The throughput since the previous report.
+    pub fn report(&mut self, now: Instant) -> String {
+        let secs = secs(now.duration_since(self.reported_at));
+        let items = self.items_total - self.items_reported;
+        let bytes = self.bytes_total - self.bytes_reported;
+
+        self.reported_at = now;
+        self.items_reported = self.items_total;
+        self.bytes_reported = self.bytes_total;
+
+        format!(
+            "{} item(s), {:.1} items/sec, {:.1} bytes/sec",
+            items,
+            rate(items, secs),
+            rate(bytes, secs)
+        )
+    }
+
+    pub fn summary(&self, now: Instant) -> String {
+        let secs = secs(now.duration_since(self.started_at));
+        format!(
+            "{} item(s), {} byte(s) in {:.3} sec: {:.1} items/sec, {:.1} bytes/sec",
+            self.items_total,
+            self.bytes_total,
+            secs,
+            rate(self.items_total, secs),
+            rate(self.bytes_total, secs)
+        )
+    }
+
+    pub fn check_total(
+        &self,
+        expected_min: Option<u64>,
+        expected_max: Option<u64>,
+    ) -> Result<(), CounterError> {
+        let total = self.items_total;
+        let too_few = expected_min.map(|min| total < min).unwrap_or(false);
+        let too_many = expected_max.map(|max| total > max).unwrap_or(false);
+        if too_few || too_many {
+            Err(CounterError::UnexpectedTotal {
+                total,
+                expected_min,
+                expected_max,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) * 1e-9
+}
+
+fn rate(amount: u64, secs: f64) -> f64 {
+    if secs > 0.0 {
+        amount as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+#[test]
+fn counter_test() {
+    let t0 = Instant::now();
+    let mut counter = Counter::new(t0);
+
+    counter.record(&[vec![0; 10], vec![0; 30]]);
+    assert_eq!(
+        counter.report(t0 + Duration::from_secs(2)),
+        "2 item(s), 1.0 items/sec, 20.0 bytes/sec"
+    );
+    counter.record(&[vec![0; 4]]);
+    assert_eq!(
+        counter.report(t0 + Duration::from_secs(3)),
+        "1 item(s), 1.0 items/sec, 4.0 bytes/sec"
+    );
+    assert_eq!(
+        counter.summary(t0 + Duration::from_secs(5)),
+        "3 item(s), 44 byte(s) in 5.000 sec: 0.6 items/sec, 8.8 bytes/sec"
+    );
+
+    assert!(counter.check_total(Some(3), Some(3)).is_ok());
+    assert!(counter.check_total(None, None).is_ok());
+    assert!(counter.check_total(Some(4), None).is_err());
+    assert!(counter.check_total(None, Some(2)).is_err());
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod count;
+pub use count::Count;
+
+mod counter;
+use counter::Counter;
+
+mod count_impl_std_stage;
+
+mod count_fsm;
+use count_fsm::CountFSM;
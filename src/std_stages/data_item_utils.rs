@@ -0,0 +1,209 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::protocol::DataItem;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A hash of the value that does not depend on the process or the platform (FNV-1a).
+///
+/// Every value is fed with its type tag, the variable-size ones also with their lengths,
+/// so that e.g. `["ab", "c"]` and `["a", "bc"]` are distinct inputs. Unions are transparent.
+pub fn stable_hash(data_item: &DataItem) -> u64 {
+    let mut state = FNV_OFFSET_BASIS;
+    feed(&mut state, data_item);
+    state
+}
+
+fn feed_bytes(state: &mut u64, bytes: &[u8]) {
+    for byte in bytes {
+        *state ^= u64::from(*byte);
+        *state = state.wrapping_mul(FNV_PRIME);
+    }
+}
+
+fn feed_len(state: &mut u64, len: usize) {
+    feed_bytes(state, &(len as u64).to_le_bytes());
+}
+
+fn feed_sized(state: &mut u64, bytes: &[u8]) {
+    feed_len(state, bytes.len());
+    feed_bytes(state, bytes);
+}
+
+fn feed(state: &mut u64, data_item: &DataItem) {
+    match data_item {
+        DataItem::Null => feed_bytes(state, &[0]),
+        DataItem::Boolean(b) => feed_bytes(state, &[1, *b as u8]),
+        DataItem::Int(i) => {
+            feed_bytes(state, &[2]);
+            feed_bytes(state, &i.to_le_bytes());
+        }
+        DataItem::Long(l) => {
+            feed_bytes(state, &[3]);
+            feed_bytes(state, &l.to_le_bytes());
+        }
+        DataItem::Float(f) => {
+            feed_bytes(state, &[4]);
+            feed_bytes(state, &f.to_bits().to_le_bytes());
+        }
+        DataItem::Double(d) => {
+            feed_bytes(state, &[5]);
+            feed_bytes(state, &d.to_bits().to_le_bytes());
+        }
+        DataItem::Bytes(bytes) => {
+            feed_bytes(state, &[6]);
+            feed_sized(state, bytes);
+        }
+        DataItem::String(s) => {
+            feed_bytes(state, &[7]);
+            feed_sized(state, s.as_bytes());
+        }
+        DataItem::Fixed(_, bytes) => {
+            feed_bytes(state, &[8]);
+            feed_sized(state, bytes);
+        }
+        DataItem::Enum(_, symbol) => {
+            feed_bytes(state, &[9]);
+            feed_sized(state, symbol.as_bytes());
+        }
+        DataItem::Union(inner) => feed(state, inner),
+        DataItem::Array(items) => {
+            feed_bytes(state, &[10]);
+            feed_len(state, items.len());
+            for item in items {
+                feed(state, item);
+            }
+        }
+        DataItem::Map(entries) => {
+            feed_bytes(state, &[11]);
+            feed_len(state, entries.len());
+            for (key, value) in entries.iter().collect::<BTreeMap<_, _>>() {
+                feed_sized(state, key.as_bytes());
+                feed(state, value);
+            }
+        }
+        DataItem::Record(fields) => {
+            feed_bytes(state, &[12]);
+            feed_len(state, fields.len());
+            for (_, value) in fields {
+                feed(state, value);
+            }
+        }
+    }
+}
+
+/// Whether the value equals to the given JSON value (enums are compared by their symbols).
+pub fn eq_json(data_item: &DataItem, json: &serde_json::Value) -> bool {
+    use serde_json::Value as Json;
+
+    match (data_item, json) {
+        (DataItem::Union(inner), _) => eq_json(inner, json),
+        (DataItem::Null, Json::Null) => true,
+        (DataItem::Boolean(b), Json::Bool(j)) => b == j,
+        (DataItem::Int(i), Json::Number(n)) => n.as_i64() == Some(i64::from(*i)),
+        (DataItem::Long(l), Json::Number(n)) => n.as_i64() == Some(*l),
+        (DataItem::Float(f), Json::Number(n)) => n.as_f64() == Some(f64::from(*f)),
+        (DataItem::Double(d), Json::Number(n)) => n.as_f64() == Some(*d),
+        (DataItem::String(s), Json::String(j)) | (DataItem::Enum(_, s), Json::String(j)) => s == j,
+        (DataItem::Bytes(b), Json::String(j)) => b.as_slice() == j.as_bytes(),
+        (DataItem::Array(items), Json::Array(js)) => {
+            items.len() == js.len() && items.iter().zip(js).all(|(item, j)| eq_json(item, j))
+        }
+        (_, _) => false,
+    }
+}
+
+/// Orders the values of the same schema: numbers by value (whatever their types),
+/// enums by their symbols' positions, arrays and records lexicographically, `null` first.
+/// The values that cannot be ordered (e.g. maps) are considered equal.
+pub fn compare(left: &DataItem, right: &DataItem) -> Ordering {
+    match (left, right) {
+        (DataItem::Union(inner), _) => compare(inner, right),
+        (_, DataItem::Union(inner)) => compare(left, inner),
+
+        (DataItem::Null, DataItem::Null) => Ordering::Equal,
+        (DataItem::Null, _) => Ordering::Less,
+        (_, DataItem::Null) => Ordering::Greater,
+
+        (DataItem::Boolean(l), DataItem::Boolean(r)) => l.cmp(r),
+        (DataItem::String(l), DataItem::String(r)) => l.cmp(r),
+        (DataItem::Bytes(l), DataItem::Bytes(r))
+        | (DataItem::Fixed(_, l), DataItem::Fixed(_, r)) => l.cmp(r),
+        (DataItem::Enum(l, _), DataItem::Enum(r, _)) => l.cmp(r),
+
+        (DataItem::Array(l), DataItem::Array(r)) => l
+            .iter()
+            .zip(r)
+            .map(|(l, r)| compare(l, r))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| l.len().cmp(&r.len())),
+        (DataItem::Record(l), DataItem::Record(r)) => l
+            .iter()
+            .zip(r)
+            .map(|((_, l), (_, r))| compare(l, r))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal),
+
+        (l, r) => match (as_i64(l), as_i64(r)) {
+            (Some(l), Some(r)) => l.cmp(&r),
+            _ => match (as_f64(l), as_f64(r)) {
+                (Some(l), Some(r)) => l.partial_cmp(&r).unwrap_or(Ordering::Equal),
+                _ => Ordering::Equal,
+            },
+        },
+    }
+}
+
+fn as_i64(data_item: &DataItem) -> Option<i64> {
+    match data_item {
+        DataItem::Int(i) => Some(i64::from(*i)),
+        DataItem::Long(l) => Some(*l),
+        _ => None,
+    }
+}
+
+fn as_f64(data_item: &DataItem) -> Option<f64> {
+    match data_item {
+        DataItem::Int(i) => Some(f64::from(*i)),
+        DataItem::Long(l) => Some(*l as f64),
+        DataItem::Float(f) => Some(f64::from(*f)),
+        DataItem::Double(d) => Some(*d),
+        _ => None,
+    }
+}
+
+#[test]
+fn stable_hash_test() {
+    let string = |s: &str| DataItem::String(s.to_owned());
+    let distinct = vec![
+        DataItem::Array(vec![string("ab"), string("c")]),
+        DataItem::Array(vec![string("a"), string("bc")]),
+        DataItem::Array(vec![DataItem::Null, string("x")]),
+        DataItem::Array(vec![string("\0x")]),
+        DataItem::Array(vec![DataItem::Array(vec![]), DataItem::Null]),
+        DataItem::Array(vec![DataItem::Array(vec![DataItem::Null])]),
+        DataItem::Int(1),
+        DataItem::Long(1),
+        DataItem::Bytes(b"ab".to_vec()),
+        string("ab"),
+        DataItem::Enum(0, "ab".to_owned()),
+    ];
+    for (idx, left) in distinct.iter().enumerate() {
+        for right in &distinct[..idx] {
+            assert_ne!(
+                stable_hash(left),
+                stable_hash(right),
+                "{:?} {:?}",
+                left,
+                right
+            );
+        }
+    }
+
+    assert_eq!(
+        stable_hash(&DataItem::Union(Box::new(DataItem::Long(1)))),
+        stable_hash(&DataItem::Long(1))
+    );
+}
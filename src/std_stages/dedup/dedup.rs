@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use crate::protocol::Schema;
+use crate::spec::DedupWindowSpec;
+
+use super::super::field_path::FieldPath;
+use super::*;
+
+#[derive(Debug)]
+pub struct Dedup {
+    pub schema: Schema,
+    pub keys: Vec<FieldPath>,
+    pub capacity: usize,
+    pub ttl: Option<Duration>,
+}
+
+impl Dedup {
+    pub fn new(
+        schema: Schema,
+        keys: Vec<String>,
+        window: DedupWindowSpec,
+    ) -> Result<Self, StdStageError> {
+        if keys.is_empty() {
+            return Err(StdStageError::InvalidParameter("keys"));
+        }
+        let keys = keys
+            .iter()
+            .map(|key| FieldPath::new(key, &schema))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (capacity, ttl) = match window {
+            DedupWindowSpec::Last { count } => (count, None),
+            DedupWindowSpec::Ttl { ttl_ms, max_keys } => {
+                (max_keys, Some(Duration::from_millis(ttl_ms)))
+            }
+        };
+        if capacity == 0 {
+            return Err(StdStageError::InvalidParameter("window"));
+        }
+
+        Ok(Self {
+            schema,
+            keys,
+            capacity,
+            ttl,
+        })
+    }
+}
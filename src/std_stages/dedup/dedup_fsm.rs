@@ -0,0 +1,262 @@
+use std::time::Instant;
+
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<DedupFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum DedupError {
+    #[fail(display = "DedupError::RxError")]
+    RxError,
+
+    #[fail(display = "DedupError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "DedupError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, DedupError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum DedupFSM {
+    ReceiveEvent {
+        deduplicator: Deduplicator,
+        /// The downstream's pending pull.
+        demand: usize,
+        rx_events: RxEventStream,
+        inlet_tx: ConsumerTx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl DedupFSM {
+    pub fn new(
+        deduplicator: Deduplicator,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_tx, outlet_rx) = outlet;
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rx);
+
+        DedupFSM::ReceiveEvent {
+            deduplicator,
+            demand: 0,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for DedupFSM {
+    type Item = ();
+    type Error = DedupError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            DedupFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| DedupError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(DedupFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            DedupFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| DedupError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(DedupFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            DedupFSM::ReceiveEvent {
+                deduplicator,
+                demand,
+                mut rx_events,
+                inlet_tx,
+                outlet_tx,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(DedupFSM::ReceiveEvent {
+                    deduplicator,
+                    demand,
+                    rx_events,
+                    inlet_tx,
+                    outlet_tx,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => {
+                    handle_rx_event(event, deduplicator, demand, rx_events, inlet_tx, outlet_tx)
+                }
+            }),
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    mut deduplicator: Deduplicator,
+    demand: usize,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<DedupFSM> {
+    match event {
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => {
+            pull_upstream(deduplicator, max_items, rx_events, inlet_tx, outlet_tx)
+        }
+
+        // the upstream is still running: its Complete or Fail would have shut the stage down
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => {
+            report_dropped(&deduplicator);
+            let send_downstream_complete = move || {
+                let sent = Box::new(outlet_tx.send(ProducerMessage::Complete));
+                let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+                Ok(TurnOk::PollMore(DedupFSM::SendingToDownstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(shutdown),
+                }))
+            };
+            let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+            Ok(TurnOk::PollMore(DedupFSM::SendingToUpstream {
+                sent,
+                and_then: SendBoxFnOnce::from(move |_inlet_tx| send_downstream_complete()),
+            }))
+        }
+
+        Event::ProducerMessage(ProducerMessage::Push { items }) => {
+            match deduplicator.dedup(items, Instant::now()) {
+                Err(reason) => {
+                    report_dropped(&deduplicator);
+                    let failure = PortFailure::from(reason);
+                    let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+                    let into_failing_downstream = move |_inlet_tx| {
+                        let sent = Box::new(outlet_tx.send(ProducerMessage::Fail { failure }));
+                        let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+                        Ok(TurnOk::PollMore(DedupFSM::SendingToDownstream {
+                            sent,
+                            and_then: SendBoxFnOnce::from(shutdown),
+                        }))
+                    };
+                    Ok(TurnOk::PollMore(DedupFSM::SendingToUpstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(into_failing_downstream),
+                    }))
+                }
+
+                // all of the pushed items have been dropped: the downstream still awaits its items
+                Ok(ref items) if items.is_empty() && demand > 0 => {
+                    pull_upstream(deduplicator, demand, rx_events, inlet_tx, outlet_tx)
+                }
+
+                Ok(items) => {
+                    let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+                    let into_receiving_events = move |outlet_tx| {
+                        Ok(TurnOk::PollMore(DedupFSM::ReceiveEvent {
+                            deduplicator,
+                            demand: 0,
+                            rx_events,
+                            inlet_tx,
+                            outlet_tx,
+                        }))
+                    };
+                    Ok(TurnOk::PollMore(DedupFSM::SendingToDownstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(into_receiving_events),
+                    }))
+                }
+            }
+        }
+
+        Event::ProducerMessage(bye_message) => {
+            report_dropped(&deduplicator);
+            let sent = Box::new(outlet_tx.send(bye_message));
+            let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+            Ok(TurnOk::PollMore(DedupFSM::SendingToDownstream {
+                sent,
+                and_then: SendBoxFnOnce::from(shutdown),
+            }))
+        }
+    }
+}
+
+fn pull_upstream(
+    deduplicator: Deduplicator,
+    max_items: usize,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<DedupFSM> {
+    let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items }));
+    let into_receiving_events = move |inlet_tx| {
+        Ok(TurnOk::PollMore(DedupFSM::ReceiveEvent {
+            deduplicator,
+            demand: max_items,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }))
+    };
+    Ok(TurnOk::PollMore(DedupFSM::SendingToUpstream {
+        sent,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }))
+}
+
+fn report_dropped(deduplicator: &Deduplicator) {
+    info!(
+        "Dedup shutting down, {} duplicate(s) dropped in total",
+        deduplicator.dropped_total
+    );
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, DedupError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| DedupError::RxError);
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| DedupError::RxError);
+
+    Box::new(inlet_rx_events.select(outlet_rx_events))
+}
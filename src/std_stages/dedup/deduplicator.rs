@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use bytes::IntoBuf;
+
+use crate::protocol::{DataItem, Schema};
+
+use super::super::data_item_utils::stable_hash;
+use super::super::field_path::FieldPath;
+
+/// Drops the items whose key has been seen recently.
+///
+/// The keys are remembered by their 64-bit `stable_hash`es, so two distinct keys are only
+/// confused on a (rare) hash collision; an absent key field counts as `null`.
+#[derive(Debug)]
+pub struct Deduplicator {
+    schema: Schema,
+    keys: Vec<FieldPath>,
+    window: KeyWindow,
+    pub dropped_total: usize,
+}
+
+impl Deduplicator {
+    pub fn new(
+        schema: Schema,
+        keys: Vec<FieldPath>,
+        capacity: usize,
+        ttl: Option<Duration>,
+    ) -> Self {
+        Self {
+            schema,
+            keys,
+            window: KeyWindow::new(capacity, ttl),
+            dropped_total: 0,
+        }
+    }
+
+    pub fn dedup(
+        &mut self,
+        items: Vec<Vec<u8>>,
+        now: Instant,
+    ) -> Result<Vec<Vec<u8>>, failure::Error> {
+        let mut passed = Vec::with_capacity(items.len());
+        for item in items {
+            let data_item =
+                avro_rs::from_avro_datum(&self.schema, &mut (&item[..]).into_buf(), None)?;
+            let key = DataItem::Array(
+                self.keys
+                    .iter()
+                    .map(|key| key.get(&data_item).cloned().unwrap_or(DataItem::Null))
+                    .collect(),
+            );
+
+            if self.window.seen(stable_hash(&key), now) {
+                self.dropped_total += 1;
+            } else {
+                passed.push(item);
+            }
+        }
+        Ok(passed)
+    }
+}
+
+/// An LRU set of keys, which also forgets the keys first seen more than `ttl` ago (if any).
+#[derive(Debug)]
+struct KeyWindow {
+    capacity: usize,
+    ttl: Option<Duration>,
+    next_seq: u64,
+    /// key -> (recency seq, age seq, first seen at)
+    entries: HashMap<u64, (u64, u64, Instant)>,
+    /// recency seq -> key, the least recently seen first
+    by_recency: BTreeMap<u64, u64>,
+    /// age seq -> key, the earliest first seen first
+    by_age: BTreeMap<u64, u64>,
+}
+
+impl KeyWindow {
+    fn new(capacity: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            capacity,
+            ttl,
+            next_seq: 0,
+            entries: HashMap::new(),
+            by_recency: BTreeMap::new(),
+            by_age: BTreeMap::new(),
+        }
+    }
+
+    /// Whether the key is in the window; either way, it becomes the most recently seen one.
+    /// A sighting doesn't postpone the key's expiry.
+    fn seen(&mut self, key: u64, now: Instant) -> bool {
+        self.expire(now);
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.by_recency.insert(seq, key);
+
+        match self.entries.get_mut(&key) {
+            Some((recency_seq, _, _)) => {
+                self.by_recency.remove(recency_seq);
+                *recency_seq = seq;
+                true
+            }
+            None => {
+                self.entries.insert(key, (seq, seq, now));
+                self.by_age.insert(seq, key);
+                if self.entries.len() > self.capacity {
+                    if let Some(&oldest) = self.by_recency.values().next() {
+                        self.forget(oldest);
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        if let Some(ttl) = self.ttl {
+            while let Some((_, &key)) = self.by_age.iter().next() {
+                let (_, _, first_seen_at) = self.entries[&key];
+                if now.duration_since(first_seen_at) < ttl {
+                    break;
+                }
+                self.forget(key);
+            }
+        }
+    }
+
+    fn forget(&mut self, key: u64) {
+        if let Some((recency_seq, age_seq, _)) = self.entries.remove(&key) {
+            self.by_recency.remove(&recency_seq);
+            self.by_age.remove(&age_seq);
+        }
+    }
+}
+
+#[test]
+fn key_window_test() {
+    let t0 = Instant::now();
+    let at = |ms| t0 + Duration::from_millis(ms);
+
+    let mut last = KeyWindow::new(2, None);
+    assert!(!last.seen(1, at(0)));
+    assert!(!last.seen(2, at(0)));
+    assert!(last.seen(1, at(0)));
+    // 2 is the least recently seen one
+    assert!(!last.seen(3, at(0)));
+    assert!(!last.seen(2, at(0)));
+    assert!(!last.seen(1, at(0)));
+
+    let mut ttl = KeyWindow::new(10, Some(Duration::from_millis(100)));
+    assert!(!ttl.seen(1, at(0)));
+    assert!(!ttl.seen(2, at(20)));
+    assert!(ttl.seen(1, at(50)));
+    // 1 expires 100ms after it was first seen, despite the sighting at 50
+    assert!(!ttl.seen(1, at(100)));
+    assert!(ttl.seen(2, at(110)));
+    assert!(!ttl.seen(2, at(120)));
+    assert!(ttl.seen(1, at(190)));
+    assert_eq!(ttl.entries.len(), 2);
+    assert_eq!(ttl.by_recency.len(), 2);
+    assert_eq!(ttl.by_age.len(), 2);
+}
+
+#[test]
+fn deduplicator_test() {
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "pair", "fields": [
+            {"name": "left", "type": "string"},
+            {"name": "right", "type": "string"}
+        ]}"#,
+    )
+    .unwrap();
+    let keys = vec![
+        FieldPath::new("left", &schema).unwrap(),
+        FieldPath::new("right", &schema).unwrap(),
+    ];
+    let datum = |left: &str, right: &str| {
+        let data_item = DataItem::Record(vec![
+            ("left".to_owned(), DataItem::String(left.to_owned())),
+            ("right".to_owned(), DataItem::String(right.to_owned())),
+        ]);
+        avro_rs::to_avro_datum(&schema, data_item).unwrap()
+    };
+
+    let mut deduplicator = Deduplicator::new(schema.clone(), keys, 10, None);
+    let items = vec![datum("ab", "c"), datum("a", "bc"), datum("ab", "c")];
+    let passed = deduplicator.dedup(items.clone(), Instant::now()).unwrap();
+    assert_eq!(passed, items[..2].to_vec());
+    assert_eq!(deduplicator.dropped_total, 1);
+}
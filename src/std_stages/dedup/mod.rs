@@ -0,0 +1,12 @@
+use super::*;
+
+mod dedup;
+pub use dedup::Dedup;
+
+mod deduplicator;
+use deduplicator::Deduplicator;
+
+mod dedup_impl_std_stage;
+
+mod dedup_fsm;
+use dedup_fsm::DedupFSM;
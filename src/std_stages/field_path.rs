@@ -0,0 +1,91 @@
+use crate::protocol::{DataItem, Schema};
+
+use super::*;
+
+/// A dot-separated path to a (possibly nested) record field, e.g. `user.id`.
+#[derive(Debug, Clone)]
+pub struct FieldPath {
+    names: Vec<String>,
+}
+
+impl FieldPath {
+    /// Parses the path and checks that it leads to a field of records described by `schema`.
+    pub fn new(path: &str, schema: &Schema) -> Result<Self, StdStageError> {
+        let names = path
+            .split('.')
+            .map(|name| name.to_owned())
+            .collect::<Vec<_>>();
+
+        if field_schema(schema, &names).is_some() {
+            Ok(Self { names })
+        } else {
+            Err(StdStageError::UnknownField(path.to_owned()))
+        }
+    }
+
+    /// The schema of the field within records described by `schema`.
+    pub fn schema<'a>(&self, schema: &'a Schema) -> Option<&'a Schema> {
+        field_schema(schema, &self.names)
+    }
+
+    /// Whether the field may be absent from the records described by `schema`,
+    /// i.e. whether there is a union (e.g. a nullable record) along the path.
+    pub fn is_optional(&self, schema: &Schema) -> bool {
+        crosses_union(schema, &self.names)
+    }
+
+    pub fn get<'a>(&self, data_item: &'a DataItem) -> Option<&'a DataItem> {
+        self.names
+            .iter()
+            .fold(Some(data_item), |acc, name| {
+                acc.and_then(|item| field(item, name))
+            })
+            .map(unwrap_union)
+    }
+}
+
+fn field<'a>(data_item: &'a DataItem, name: &str) -> Option<&'a DataItem> {
+    match unwrap_union(data_item) {
+        DataItem::Record(fields) => fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+fn unwrap_union(data_item: &DataItem) -> &DataItem {
+    match data_item {
+        DataItem::Union(inner) => unwrap_union(inner),
+        as_is => as_is,
+    }
+}
+
+fn field_schema<'a>(schema: &'a Schema, names: &[String]) -> Option<&'a Schema> {
+    match (names.split_first(), schema) {
+        (None, _) => Some(schema),
+        (Some(_), Schema::Union(union_schema)) => union_schema
+            .variants()
+            .iter()
+            .filter_map(|variant| field_schema(variant, names))
+            .next(),
+        (Some((name, rest)), Schema::Record { fields, .. }) => fields
+            .iter()
+            .find(|field| field.name == *name)
+            .and_then(|field| field_schema(&field.schema, rest)),
+        (Some(_), _) => None,
+    }
+}
+
+fn crosses_union(schema: &Schema, names: &[String]) -> bool {
+    match (names.split_first(), schema) {
+        (None, _) => false,
+        (Some(_), Schema::Union(_)) => true,
+        (Some((name, rest)), Schema::Record { fields, .. }) => fields
+            .iter()
+            .find(|field| field.name == *name)
+            .map(|field| crosses_union(&field.schema, rest))
+            .unwrap_or(false),
+        (Some(_), _) => false,
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use crate::protocol::{DataItem, Schema};
+use crate::spec::FieldOverrideSpec;
+
+use super::super::field_path::FieldPath;
+use super::super::rng::Rng;
+use super::*;
+
+const MAX_STRING_LEN: u64 = 16;
+const MAX_COLLECTION_LEN: u64 = 4;
+
+const DIGITS: &[u8] = b"0123456789";
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+#[derive(Debug, Clone)]
+pub enum PatternChar {
+    Digit,
+    Lowercase,
+    Alphanumeric,
+    Literal(char),
+}
+
+/// A `FieldOverrideSpec` checked against the field's schema.
+#[derive(Debug, Clone)]
+pub enum FieldOverride {
+    Range { min: f64, max: f64 },
+    EnumWeights { weights: HashMap<String, f64> },
+    Pattern(Vec<PatternChar>),
+}
+
+impl FieldOverride {
+    pub fn new(
+        path: &str,
+        override_spec: FieldOverrideSpec,
+        schema: &Schema,
+    ) -> Result<Self, StdStageError> {
+        let field_schema = FieldPath::new(path, schema)?
+            .schema(schema)
+            .ok_or_else(|| StdStageError::UnknownField(path.to_owned()))?;
+
+        let field_override = match override_spec {
+            FieldOverrideSpec::Range { min, max }
+                if min.is_finite() && max.is_finite() && min <= max =>
+            {
+                FieldOverride::Range { min, max }
+            }
+            FieldOverrideSpec::EnumWeights { weights }
+                if weights.values().all(|weight| *weight >= 0.0)
+                    && weights.values().any(|weight| *weight > 0.0) =>
+            {
+                FieldOverride::EnumWeights { weights }
+            }
+            FieldOverrideSpec::Pattern { pattern } => {
+                FieldOverride::Pattern(parse_pattern(&pattern))
+            }
+            _ => return Err(StdStageError::InvalidParameter("overrides")),
+        };
+
+        if field_override.fits(field_schema) {
+            Ok(field_override)
+        } else {
+            Err(StdStageError::InvalidParameter("overrides"))
+        }
+    }
+
+    fn fits(&self, schema: &Schema) -> bool {
+        match (self, schema) {
+            (_, Schema::Union(union_schema)) => union_schema
+                .variants()
+                .iter()
+                .any(|variant| self.fits(variant)),
+            (FieldOverride::Range { min, max }, Schema::Int) => {
+                min.ceil() <= max.floor()
+                    && min.ceil() >= f64::from(i32::min_value())
+                    && max.floor() <= f64::from(i32::max_value())
+            }
+            (FieldOverride::Range { min, max }, Schema::Long) => min.ceil() <= max.floor(),
+            (FieldOverride::Range { .. }, Schema::Float)
+            | (FieldOverride::Range { .. }, Schema::Double) => true,
+            (FieldOverride::EnumWeights { weights }, Schema::Enum { symbols, .. }) => {
+                weights.keys().all(|symbol| symbols.contains(symbol))
+            }
+            (FieldOverride::Pattern(_), Schema::String) => true,
+            (_, _) => false,
+        }
+    }
+}
+
+/// Makes up random values of a schema, seeded for the runs to be reproducible.
+///
+/// The overrides apply to the fields reached through records and unions (see `FieldPath`),
+/// not to those within arrays or maps.
+#[derive(Debug)]
+pub struct DataGenerator {
+    schema: Schema,
+    rng: Rng,
+    overrides: HashMap<String, FieldOverride>,
+}
+
+impl DataGenerator {
+    pub fn new(schema: Schema, seed: u64, overrides: HashMap<String, FieldOverride>) -> Self {
+        Self {
+            schema,
+            rng: Rng::new(seed),
+            overrides,
+        }
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn generate(&mut self) -> DataItem {
+        generate(&mut self.rng, &self.overrides, &self.schema, Some(""))
+    }
+}
+
+/// `path` is the path of the value being generated, `None` within arrays and maps.
+fn generate(
+    rng: &mut Rng,
+    overrides: &HashMap<String, FieldOverride>,
+    schema: &Schema,
+    path: Option<&str>,
+) -> DataItem {
+    match schema {
+        Schema::Null => DataItem::Null,
+        Schema::Boolean => DataItem::Boolean(rng.below(2) == 1),
+        Schema::Int => DataItem::Int(rng.next_u64() as i32),
+        Schema::Long => DataItem::Long(rng.next_u64() as i64),
+        Schema::Float => DataItem::Float(rng.next_f64() as f32),
+        Schema::Double => DataItem::Double(rng.next_f64()),
+        Schema::Bytes => {
+            let len = rng.below(MAX_STRING_LEN + 1) as usize;
+            DataItem::Bytes(random_bytes(rng, len))
+        }
+        Schema::String => DataItem::String(random_string(rng, 0)),
+        Schema::Fixed { size, .. } => DataItem::Fixed(*size, random_bytes(rng, *size)),
+        Schema::Enum { symbols, .. } => {
+            let idx = rng.below(symbols.len() as u64) as usize;
+            DataItem::Enum(idx as i32, symbols[idx].to_owned())
+        }
+        Schema::Array(items_schema) => {
+            let len = rng.below(MAX_COLLECTION_LEN + 1);
+            DataItem::Array(
+                (0..len)
+                    .map(|_| generate(rng, overrides, items_schema, None))
+                    .collect(),
+            )
+        }
+        Schema::Map(values_schema) => {
+            let len = rng.below(MAX_COLLECTION_LEN + 1);
+            DataItem::Map(
+                (0..len)
+                    .map(|_| {
+                        let key = random_string(rng, 1);
+                        (key, generate(rng, overrides, values_schema, None))
+                    })
+                    .collect(),
+            )
+        }
+        Schema::Union(union_schema) => {
+            let variants = union_schema.variants();
+            let variant = &variants[rng.below(variants.len() as u64) as usize];
+            DataItem::Union(Box::new(generate(rng, overrides, variant, path)))
+        }
+        Schema::Record { fields, .. } => DataItem::Record(
+            fields
+                .iter()
+                .map(|field| {
+                    let field_path = path.map(|path| match path {
+                        "" => field.name.to_owned(),
+                        parent => format!("{}.{}", parent, field.name),
+                    });
+                    let value = match field_path.as_ref().and_then(|p| overrides.get(p)) {
+                        Some(field_override) => {
+                            generate_overridden(rng, field_override, &field.schema)
+                        }
+                        None => generate(
+                            rng,
+                            overrides,
+                            &field.schema,
+                            field_path.as_ref().map(String::as_str),
+                        ),
+                    };
+                    (field.name.to_owned(), value)
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn generate_overridden(rng: &mut Rng, field_override: &FieldOverride, schema: &Schema) -> DataItem {
+    match (field_override, schema) {
+        (_, Schema::Union(union_schema)) => {
+            let variant = union_schema
+                .variants()
+                .iter()
+                .find(|variant| field_override.fits(variant))
+                .expect("FieldOverride::new checks that the override fits");
+            DataItem::Union(Box::new(generate_overridden(rng, field_override, variant)))
+        }
+        (FieldOverride::Range { min, max }, Schema::Int) => {
+            DataItem::Int(random_integer(rng, *min, *max) as i32)
+        }
+        (FieldOverride::Range { min, max }, Schema::Long) => {
+            DataItem::Long(random_integer(rng, *min, *max))
+        }
+        (FieldOverride::Range { min, max }, Schema::Float) => {
+            DataItem::Float((min + rng.next_f64() * (max - min)) as f32)
+        }
+        (FieldOverride::Range { min, max }, Schema::Double) => {
+            DataItem::Double(min + rng.next_f64() * (max - min))
+        }
+        (FieldOverride::EnumWeights { weights }, Schema::Enum { symbols, .. }) => {
+            let weight_of = |symbol: &String| weights.get(symbol).cloned().unwrap_or(0.0);
+            let total = symbols.iter().map(weight_of).sum::<f64>();
+            let mut point = rng.next_f64() * total;
+            let idx = symbols
+                .iter()
+                .position(|symbol| {
+                    let weight = weight_of(symbol);
+                    point -= weight;
+                    point < 0.0 && weight > 0.0
+                })
+                // rounding may leave `point` right at the total
+                .or_else(|| symbols.iter().rposition(|symbol| weight_of(symbol) > 0.0))
+                .expect("FieldOverride::new checks that some of the weights are positive");
+            DataItem::Enum(idx as i32, symbols[idx].to_owned())
+        }
+        (FieldOverride::Pattern(pattern), Schema::String) => DataItem::String(
+            pattern
+                .iter()
+                .map(|pattern_char| match pattern_char {
+                    PatternChar::Digit => random_char(rng, DIGITS),
+                    PatternChar::Lowercase => random_char(rng, LOWERCASE),
+                    PatternChar::Alphanumeric => random_char(rng, ALPHANUMERIC),
+                    PatternChar::Literal(c) => *c,
+                })
+                .collect(),
+        ),
+        (_, _) => unreachable!("FieldOverride::new checks that the override fits"),
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Vec<PatternChar> {
+    let mut chars = pattern.chars();
+    let mut parsed = Vec::new();
+    while let Some(c) = chars.next() {
+        parsed.push(match c {
+            '#' => PatternChar::Digit,
+            '?' => PatternChar::Lowercase,
+            '*' => PatternChar::Alphanumeric,
+            '\\' => PatternChar::Literal(chars.next().unwrap_or('\\')),
+            literal => PatternChar::Literal(literal),
+        });
+    }
+    parsed
+}
+
+/// Within `[min, max]`.
+fn random_integer(rng: &mut Rng, min: f64, max: f64) -> i64 {
+    let (min, max) = (min.ceil() as i64, max.floor() as i64);
+    let span = (max as i128 - min as i128 + 1) as u128;
+    if span > u128::from(u64::max_value()) {
+        rng.next_u64() as i64
+    } else {
+        (min as i128 + i128::from(rng.below(span as u64))) as i64
+    }
+}
+
+fn random_bytes(rng: &mut Rng, len: usize) -> Vec<u8> {
+    (0..len).map(|_| rng.next_u64() as u8).collect()
+}
+
+fn random_string(rng: &mut Rng, min_len: u64) -> String {
+    let len = min_len + rng.below(MAX_STRING_LEN + 1 - min_len);
+    (0..len).map(|_| random_char(rng, ALPHANUMERIC)).collect()
+}
+
+fn random_char(rng: &mut Rng, alphabet: &[u8]) -> char {
+    char::from(alphabet[rng.below(alphabet.len() as u64) as usize])
+}
+
+#[test]
+fn data_generator_test() {
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "event", "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "tags", "type": {"type": "map", "values": "string"}},
+            {"name": "user", "type": ["null", {"type": "record", "name": "user", "fields": [
+                {"name": "age", "type": "int"},
+                {"name": "code", "type": "string"},
+                {"name": "tier", "type": {"type": "enum", "name": "tier", "symbols": ["free", "gold", "staff"]}}
+            ]}]}
+        ]}"#,
+    )
+    .unwrap();
+    let overrides = vec![
+        (
+            "user.age",
+            FieldOverrideSpec::Range {
+                min: 18.0,
+                max: 30.0,
+            },
+        ),
+        (
+            "user.code",
+            FieldOverrideSpec::Pattern {
+                pattern: "U-##\\#".to_owned(),
+            },
+        ),
+        (
+            "user.tier",
+            FieldOverrideSpec::EnumWeights {
+                weights: vec![("free".to_owned(), 1.0), ("gold".to_owned(), 1.0)]
+                    .into_iter()
+                    .collect(),
+            },
+        ),
+    ]
+    .into_iter()
+    .map(|(path, spec)| {
+        (
+            path.to_owned(),
+            FieldOverride::new(path, spec, &schema).unwrap(),
+        )
+    })
+    .collect::<HashMap<_, _>>();
+
+    let mut generator = DataGenerator::new(schema.clone(), 7, overrides.clone());
+    let generated = (0..100).map(|_| generator.generate()).collect::<Vec<_>>();
+    for data_item in &generated {
+        assert!(data_item.validate(&schema));
+
+        let user = FieldPath::new("user", &schema).unwrap();
+        if let Some(DataItem::Record(fields)) = user.get(data_item) {
+            match &fields[0].1 {
+                DataItem::Int(age) => assert!(*age >= 18 && *age <= 30),
+                unexpected => panic!("unexpected age: {:?}", unexpected),
+            }
+            match &fields[1].1 {
+                DataItem::String(code) => {
+                    assert!(code.len() == 5 && code.starts_with("U-") && code.ends_with('#'))
+                }
+                unexpected => panic!("unexpected code: {:?}", unexpected),
+            }
+            assert_ne!(fields[2].1, DataItem::Enum(2, "staff".to_owned()));
+        }
+    }
+
+    let mut same_seed = DataGenerator::new(schema.clone(), 7, overrides);
+    assert_eq!(
+        (0..100).map(|_| same_seed.generate()).collect::<Vec<_>>(),
+        generated
+    );
+
+    assert!(FieldOverride::new(
+        "id",
+        FieldOverrideSpec::Pattern {
+            pattern: "#".to_owned()
+        },
+        &schema
+    )
+    .is_err());
+}
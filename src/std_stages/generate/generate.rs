@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::protocol::Schema;
+use crate::spec::FieldOverrideSpec;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct Generate {
+    pub schema: Schema,
+    pub seed: u64,
+    pub count: Option<u64>,
+    pub duration: Option<Duration>,
+    pub items_per_second: Option<f64>,
+    pub overrides: HashMap<String, FieldOverride>,
+}
+
+impl Generate {
+    pub fn new(
+        schema: Schema,
+        seed: u64,
+        count: Option<u64>,
+        duration: Option<Duration>,
+        items_per_second: Option<f64>,
+        overrides: HashMap<String, FieldOverrideSpec>,
+    ) -> Result<Self, StdStageError> {
+        if let Some(items_per_second) = items_per_second {
+            if !(items_per_second.is_finite() && items_per_second > 0.0) {
+                return Err(StdStageError::InvalidParameter("items_per_second"));
+            }
+        }
+        let overrides = overrides
+            .into_iter()
+            .map(|(path, override_spec)| {
+                FieldOverride::new(&path, override_spec, &schema)
+                    .map(|field_override| (path, field_override))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(Self {
+            schema,
+            seed,
+            count,
+            duration,
+            items_per_second,
+            overrides,
+        })
+    }
+}
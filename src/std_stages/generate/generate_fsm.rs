@@ -0,0 +1,187 @@
+use std::time::Instant;
+
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+use tokio::timer::Delay;
+
+use crate::futures::fsm::*;
+use crate::futures::SendBoxedFuture;
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<GenerateFSM>>;
+
+#[derive(Fail, Debug)]
+pub enum GenerateError {
+    #[fail(display = "GenerateError::RxError")]
+    RxError,
+
+    #[fail(display = "GenerateError::TimerError")]
+    TimerError(#[cause] tokio::timer::Error),
+
+    #[fail(display = "GenerateError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+
+pub enum GenerateFSM {
+    ReceiveEvent {
+        data_generator: DataGenerator,
+        schedule: Schedule,
+        /// The downstream's pull waiting for the items to become due.
+        delayed_pull: Option<(Delay, usize)>,
+        outlet_rx: ProducerRx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl GenerateFSM {
+    pub fn new(
+        data_generator: DataGenerator,
+        schedule: Schedule,
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (outlet_tx, outlet_rx) = outlet;
+        GenerateFSM::ReceiveEvent {
+            data_generator,
+            schedule,
+            delayed_pull: None,
+            outlet_rx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for GenerateFSM {
+    type Item = ();
+    type Error = GenerateError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            GenerateFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| GenerateError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(GenerateFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            GenerateFSM::ReceiveEvent {
+                data_generator,
+                schedule,
+                mut delayed_pull,
+                mut outlet_rx,
+                outlet_tx,
+            } => {
+                let delayed_pull_due = match delayed_pull.as_mut() {
+                    Some((delay, max_items)) => delay
+                        .poll()
+                        .map_err(|err| GenerateError::TimerError(err))?
+                        .map(|()| *max_items),
+                    None => Async::NotReady,
+                };
+                if let Async::Ready(max_items) = delayed_pull_due {
+                    return handle_pull(max_items, data_generator, schedule, outlet_rx, outlet_tx);
+                }
+
+                match outlet_rx.poll() {
+                    Err(()) => Err(GenerateError::RxError),
+
+                    Ok(Async::NotReady) => Ok(TurnOk::Suspend(GenerateFSM::ReceiveEvent {
+                        data_generator,
+                        schedule,
+                        delayed_pull,
+                        outlet_rx,
+                        outlet_tx,
+                    })),
+
+                    Ok(Async::Ready(None)) | Ok(Async::Ready(Some(ConsumerMessage::Cancel))) => {
+                        Ok(TurnOk::Ready(()))
+                    }
+
+                    Ok(Async::Ready(Some(ConsumerMessage::Pull { max_items }))) => {
+                        handle_pull(max_items, data_generator, schedule, outlet_rx, outlet_tx)
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn handle_pull(
+    max_items: usize,
+    mut data_generator: DataGenerator,
+    mut schedule: Schedule,
+    outlet_rx: ProducerRx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<GenerateFSM> {
+    match schedule.due(max_items, Instant::now()) {
+        Due::WaitUntil(due_at) => Ok(TurnOk::PollMore(GenerateFSM::ReceiveEvent {
+            data_generator,
+            schedule,
+            delayed_pull: Some((Delay::new(due_at), max_items)),
+            outlet_rx,
+            outlet_tx,
+        })),
+
+        Due::Over => {
+            let sent = Box::new(outlet_tx.send(ProducerMessage::Complete));
+            let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+            Ok(TurnOk::PollMore(GenerateFSM::SendingToDownstream {
+                sent,
+                and_then: SendBoxFnOnce::from(shutdown),
+            }))
+        }
+
+        Due::Items(items_count) => {
+            let items = (0..items_count)
+                .map(|_| {
+                    let data_item = data_generator.generate();
+                    avro_rs::to_avro_datum(data_generator.schema(), data_item)
+                })
+                .collect::<Result<Vec<_>, _>>();
+            schedule.emitted(items_count);
+
+            match items {
+                Ok(items) => {
+                    let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+                    let into_receiving_events = move |outlet_tx| {
+                        Ok(TurnOk::PollMore(GenerateFSM::ReceiveEvent {
+                            data_generator,
+                            schedule,
+                            delayed_pull: None,
+                            outlet_rx,
+                            outlet_tx,
+                        }))
+                    };
+                    Ok(TurnOk::PollMore(GenerateFSM::SendingToDownstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(into_receiving_events),
+                    }))
+                }
+                Err(reason) => {
+                    let sent = Box::new(outlet_tx.send(ProducerMessage::Fail {
+                        failure: PortFailure::from(reason),
+                    }));
+                    let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+                    Ok(TurnOk::PollMore(GenerateFSM::SendingToDownstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(shutdown),
+                    }))
+                }
+            }
+        }
+    }
+}
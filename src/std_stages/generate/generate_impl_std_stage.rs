@@ -0,0 +1,45 @@
+use std::time::Instant;
+
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for Generate {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.schema]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        mut outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.is_empty());
+        assert!(outlets.len() == 1);
+        let outlet = outlets.pop().unwrap();
+
+        let schedule = Schedule::new(
+            self.count,
+            self.duration,
+            self.items_per_second,
+            Instant::now(),
+        );
+        let data_generator = DataGenerator::new(self.schema, self.seed, self.overrides);
+
+        Box::new(
+            GenerateFSM::new(data_generator, schedule, outlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
@@ -0,0 +1,15 @@
+use super::*;
+
+mod generate;
+pub use generate::Generate;
+
+mod data_generator;
+use data_generator::{DataGenerator, FieldOverride};
+
+mod schedule;
+use schedule::{Due, Schedule};
+
+mod generate_impl_std_stage;
+
+mod generate_fsm;
+use generate_fsm::GenerateFSM;
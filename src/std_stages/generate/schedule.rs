@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+pub enum Due {
+    Items(usize),
+    WaitUntil(Instant),
+    Over,
+}
+
+/// Tells how many items are due: no more than `count` in total, none past `ends_at`,
+/// the `k`-th one no earlier than `k / rate` seconds after the start.
+#[derive(Debug)]
+pub struct Schedule {
+    started_at: Instant,
+    count: Option<u64>,
+    ends_at: Option<Instant>,
+    rate: Option<f64>,
+    emitted: u64,
+}
+
+impl Schedule {
+    pub fn new(
+        count: Option<u64>,
+        duration: Option<Duration>,
+        rate: Option<f64>,
+        now: Instant,
+    ) -> Self {
+        Self {
+            started_at: now,
+            count,
+            ends_at: duration.map(|duration| now + duration),
+            rate,
+            emitted: 0,
+        }
+    }
+
+    pub fn due(&self, max_items: usize, now: Instant) -> Due {
+        let remaining = match self.count {
+            Some(count) => count - self.emitted,
+            None => u64::max_value(),
+        };
+        let is_over = remaining == 0 || self.ends_at.map(|ends_at| ends_at <= now).unwrap_or(false);
+        if is_over {
+            return Due::Over;
+        }
+        let max_items = std::cmp::min(max_items as u64, remaining);
+
+        match self.rate {
+            None => Due::Items(max_items as usize),
+            Some(rate) => {
+                let elapsed = now.duration_since(self.started_at);
+                let elapsed_secs =
+                    elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+                let allowed = (elapsed_secs * rate).floor() as u64 + 1;
+
+                match allowed.saturating_sub(self.emitted) {
+                    0 => {
+                        let next_secs = self.emitted as f64 / rate;
+                        let next_at = self.started_at
+                            + Duration::from_micros((next_secs * 1_000_000.0).ceil() as u64);
+                        Due::WaitUntil(match self.ends_at {
+                            Some(ends_at) => std::cmp::min(ends_at, next_at),
+                            None => next_at,
+                        })
+                    }
+                    available => Due::Items(std::cmp::min(max_items, available) as usize),
+                }
+            }
+        }
+    }
+
+    pub fn emitted(&mut self, items_count: usize) {
+        self.emitted += items_count as u64;
+    }
+}
@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+use crate::protocol::Schema;
+
+/// The output is opened upon the first write and is written within the stage's task.
+#[derive(Debug)]
+pub struct JsonlSink {
+    pub schema: Schema,
+    pub path: Option<PathBuf>,
+}
+
+impl JsonlSink {
+    pub fn new<P: Into<PathBuf>>(schema: Schema, path: Option<P>) -> Self {
+        Self {
+            schema,
+            path: path.map(Into::into),
+        }
+    }
+}
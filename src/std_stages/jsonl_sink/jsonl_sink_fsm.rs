@@ -0,0 +1,187 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::SendBoxedFuture;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+
+use super::super::blocking::poll_blocking;
+use super::*;
+
+const PULL_SIZE: usize = 256;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<JsonlSinkFSM>>;
+
+#[derive(Fail, Debug)]
+pub enum JsonlSinkError {
+    #[fail(display = "JsonlSinkError::RxError")]
+    RxError,
+
+    #[fail(display = "JsonlSinkError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "JsonlSinkError::WriteError")]
+    WriteError(#[cause] failure::Error),
+
+    #[fail(display = "JsonlSinkError::UpstreamFailed: {}", _0)]
+    UpstreamFailed(String),
+}
+
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum JsonlSinkFSM {
+    ReceiveEvent {
+        line_writer: LineWriter,
+        inlet_rx: ConsumerRx,
+        inlet_tx: ConsumerTx,
+    },
+    /// The output is written off the runtime's thread (see `poll_blocking`).
+    Appending {
+        line_writer: LineWriter,
+        items: Option<Vec<Vec<u8>>>,
+        inlet_rx: ConsumerRx,
+        inlet_tx: ConsumerTx,
+    },
+    /// What has been received is written out even if the upstream has failed.
+    Finishing {
+        line_writer: LineWriter,
+        upstream_failure: Option<String>,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+}
+
+impl JsonlSinkFSM {
+    pub fn new(line_writer: LineWriter, inlet: (ConsumerTx, ConsumerRx)) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        pulling_upstream(line_writer, inlet_rx, inlet_tx)
+    }
+}
+
+impl FSM for JsonlSinkFSM {
+    type Item = ();
+    type Error = JsonlSinkError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            JsonlSinkFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| JsonlSinkError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(JsonlSinkFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            JsonlSinkFSM::ReceiveEvent {
+                line_writer,
+                mut inlet_rx,
+                inlet_tx,
+            } => match inlet_rx.poll() {
+                Err(()) => Err(JsonlSinkError::RxError),
+
+                Ok(Async::NotReady) => Ok(TurnOk::Suspend(JsonlSinkFSM::ReceiveEvent {
+                    line_writer,
+                    inlet_rx,
+                    inlet_tx,
+                })),
+
+                Ok(Async::Ready(None)) => Ok(TurnOk::Ready(())),
+
+                Ok(Async::Ready(Some(ProducerMessage::Push { items }))) => {
+                    Ok(TurnOk::PollMore(JsonlSinkFSM::Appending {
+                        line_writer,
+                        items: Some(items),
+                        inlet_rx,
+                        inlet_tx,
+                    }))
+                }
+
+                Ok(Async::Ready(Some(ProducerMessage::Complete))) => {
+                    Ok(TurnOk::PollMore(JsonlSinkFSM::Finishing {
+                        line_writer,
+                        upstream_failure: None,
+                    }))
+                }
+
+                Ok(Async::Ready(Some(ProducerMessage::Fail { failure }))) => {
+                    Ok(TurnOk::PollMore(JsonlSinkFSM::Finishing {
+                        line_writer,
+                        upstream_failure: Some(failure.message),
+                    }))
+                }
+            },
+
+            JsonlSinkFSM::Appending {
+                mut line_writer,
+                mut items,
+                inlet_rx,
+                inlet_tx,
+            } => match poll_blocking(|| {
+                line_writer.append(items.take().expect("appended only once"))
+            }) {
+                Async::NotReady => Ok(TurnOk::Suspend(JsonlSinkFSM::Appending {
+                    line_writer,
+                    items,
+                    inlet_rx,
+                    inlet_tx,
+                })),
+                Async::Ready(Ok(())) => Ok(TurnOk::PollMore(pulling_upstream(
+                    line_writer,
+                    inlet_rx,
+                    inlet_tx,
+                ))),
+                Async::Ready(Err(reason)) => {
+                    let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+                    let and_then = move |_inlet_tx| Err(JsonlSinkError::WriteError(reason));
+                    Ok(TurnOk::PollMore(JsonlSinkFSM::SendingToUpstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(and_then),
+                    }))
+                }
+            },
+
+            JsonlSinkFSM::Finishing {
+                mut line_writer,
+                upstream_failure,
+            } => match poll_blocking(|| line_writer.finish()) {
+                Async::NotReady => Ok(TurnOk::Suspend(JsonlSinkFSM::Finishing {
+                    line_writer,
+                    upstream_failure,
+                })),
+                Async::Ready(Err(reason)) => Err(JsonlSinkError::WriteError(reason)),
+                Async::Ready(Ok(())) => match upstream_failure {
+                    None => Ok(TurnOk::Ready(())),
+                    Some(message) => Err(JsonlSinkError::UpstreamFailed(message)),
+                },
+            },
+        }
+    }
+}
+
+fn pulling_upstream(
+    line_writer: LineWriter,
+    inlet_rx: ConsumerRx,
+    inlet_tx: ConsumerTx,
+) -> JsonlSinkFSM {
+    let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull {
+        max_items: PULL_SIZE,
+    }));
+    let into_receiving_events = move |inlet_tx| {
+        Ok(TurnOk::PollMore(JsonlSinkFSM::ReceiveEvent {
+            line_writer,
+            inlet_rx,
+            inlet_tx,
+        }))
+    };
+    JsonlSinkFSM::SendingToUpstream {
+        sent,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }
+}
@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use bytes::IntoBuf;
+
+use crate::protocol::avro_json::to_json;
+use crate::protocol::Schema;
+
+/// Writes the datums of `schema` into a file (or to the stdout), a line per datum.
+pub struct LineWriter {
+    path: Option<PathBuf>,
+    schema: Schema,
+    opened: Option<Box<dyn Write + Send>>,
+}
+
+impl LineWriter {
+    pub fn new(path: Option<PathBuf>, schema: Schema) -> Self {
+        Self {
+            path,
+            schema,
+            opened: None,
+        }
+    }
+
+    pub fn append(&mut self, items: Vec<Vec<u8>>) -> Result<(), failure::Error> {
+        for item in items {
+            let data_item = avro_rs::from_avro_datum(&self.schema, &mut item.into_buf(), None)?;
+            let json = to_json(&data_item, &self.schema)?;
+
+            let output = self.output()?;
+            serde_json::to_writer(&mut *output, &json)?;
+            output.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> Result<(), failure::Error> {
+        self.output()?.flush()?;
+        Ok(())
+    }
+
+    /// The output, created (or truncated) upon the first call.
+    fn output(&mut self) -> Result<&mut Box<dyn Write + Send>, failure::Error> {
+        if self.opened.is_none() {
+            let output: Box<dyn Write + Send> = match self.path {
+                Some(ref path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(BufWriter::new(io::stdout())),
+            };
+            self.opened = Some(output);
+        }
+        Ok(self.opened.as_mut().unwrap())
+    }
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod jsonl_sink;
+pub use jsonl_sink::JsonlSink;
+
+mod line_writer;
+use line_writer::LineWriter;
+
+mod jsonl_sink_impl_std_stage;
+
+mod jsonl_sink_fsm;
+use jsonl_sink_fsm::JsonlSinkFSM;
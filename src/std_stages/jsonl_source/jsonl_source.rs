@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use crate::protocol::Schema;
+use crate::spec::MalformedLineSpec;
+
+/// The input is opened upon the first pull and is read within the stage's task.
+#[derive(Debug)]
+pub struct JsonlSource {
+    pub schema: Schema,
+    pub path: Option<PathBuf>,
+    pub on_malformed: MalformedLineSpec,
+}
+
+impl JsonlSource {
+    pub fn new<P: Into<PathBuf>>(
+        schema: Schema,
+        path: Option<P>,
+        on_malformed: MalformedLineSpec,
+    ) -> Self {
+        Self {
+            schema,
+            path: path.map(Into::into),
+            on_malformed,
+        }
+    }
+}
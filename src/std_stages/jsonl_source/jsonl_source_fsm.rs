@@ -0,0 +1,149 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::SendBoxedFuture;
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::super::blocking::poll_blocking;
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<JsonlSourceFSM>>;
+
+#[derive(Fail, Debug)]
+pub enum JsonlSourceError {
+    #[fail(display = "JsonlSourceError::RxError")]
+    RxError,
+
+    #[fail(display = "JsonlSourceError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+
+pub enum JsonlSourceFSM {
+    ReceiveEvent {
+        line_reader: LineReader,
+        outlet_rx: ProducerRx,
+        outlet_tx: ProducerTx,
+    },
+    /// The input (possibly the stdin) is read off the runtime's thread (see `poll_blocking`).
+    Reading {
+        line_reader: LineReader,
+        max_items: usize,
+        outlet_rx: ProducerRx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl JsonlSourceFSM {
+    pub fn new(line_reader: LineReader, outlet: (ProducerTx, ProducerRx)) -> Self {
+        let (outlet_tx, outlet_rx) = outlet;
+        JsonlSourceFSM::ReceiveEvent {
+            line_reader,
+            outlet_rx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for JsonlSourceFSM {
+    type Item = ();
+    type Error = JsonlSourceError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            JsonlSourceFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| JsonlSourceError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(JsonlSourceFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            JsonlSourceFSM::ReceiveEvent {
+                line_reader,
+                mut outlet_rx,
+                outlet_tx,
+            } => match outlet_rx.poll() {
+                Err(()) => Err(JsonlSourceError::RxError),
+
+                Ok(Async::NotReady) => Ok(TurnOk::Suspend(JsonlSourceFSM::ReceiveEvent {
+                    line_reader,
+                    outlet_rx,
+                    outlet_tx,
+                })),
+
+                Ok(Async::Ready(None)) | Ok(Async::Ready(Some(ConsumerMessage::Cancel))) => {
+                    Ok(TurnOk::Ready(()))
+                }
+
+                Ok(Async::Ready(Some(ConsumerMessage::Pull { max_items }))) => {
+                    Ok(TurnOk::PollMore(JsonlSourceFSM::Reading {
+                        line_reader,
+                        max_items,
+                        outlet_rx,
+                        outlet_tx,
+                    }))
+                }
+            },
+
+            JsonlSourceFSM::Reading {
+                mut line_reader,
+                max_items,
+                outlet_rx,
+                outlet_tx,
+            } => match poll_blocking(|| line_reader.read(max_items)) {
+                Async::NotReady => Ok(TurnOk::Suspend(JsonlSourceFSM::Reading {
+                    line_reader,
+                    max_items,
+                    outlet_rx,
+                    outlet_tx,
+                })),
+                Async::Ready(read) => {
+                    let (message, and_then) = match read {
+                        Ok(ref items) if items.is_empty() && max_items > 0 => {
+                            (ProducerMessage::Complete, None)
+                        }
+                        Ok(items) => (
+                            ProducerMessage::Push { items },
+                            Some((line_reader, outlet_rx)),
+                        ),
+                        Err(reason) => (
+                            ProducerMessage::Fail {
+                                failure: PortFailure::from(reason),
+                            },
+                            None,
+                        ),
+                    };
+
+                    let sent = Box::new(outlet_tx.send(message));
+                    let and_then = move |outlet_tx| match and_then {
+                        Some((line_reader, outlet_rx)) => {
+                            Ok(TurnOk::PollMore(JsonlSourceFSM::ReceiveEvent {
+                                line_reader,
+                                outlet_rx,
+                                outlet_tx,
+                            }))
+                        }
+                        None => Ok(TurnOk::Ready(())),
+                    };
+                    Ok(TurnOk::PollMore(JsonlSourceFSM::SendingToDownstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(and_then),
+                    }))
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for JsonlSource {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.schema]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        mut outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.is_empty());
+        assert!(outlets.len() == 1);
+        let outlet = outlets.pop().unwrap();
+
+        let line_reader = LineReader::new(self.path, self.schema, self.on_malformed);
+
+        Box::new(
+            JsonlSourceFSM::new(line_reader, outlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
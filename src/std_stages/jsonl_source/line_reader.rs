@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+
+use crate::protocol::avro_json::from_json;
+use crate::protocol::Schema;
+use crate::spec::MalformedLineSpec;
+
+#[derive(Fail, Debug)]
+pub enum LineReaderError {
+    #[fail(display = "LineReaderError::MalformedLine [line: {}]", line)]
+    MalformedLine {
+        line: usize,
+        #[cause]
+        reason: failure::Error,
+    },
+}
+
+/// Reads the lines of a file (or of the stdin), converting each of them into a datum of `schema`.
+pub struct LineReader {
+    path: Option<PathBuf>,
+    schema: Schema,
+    on_malformed: MalformedLineSpec,
+    opened: Option<Box<dyn BufRead + Send>>,
+    lines_read: usize,
+}
+
+impl LineReader {
+    pub fn new(path: Option<PathBuf>, schema: Schema, on_malformed: MalformedLineSpec) -> Self {
+        Self {
+            path,
+            schema,
+            on_malformed,
+            opened: None,
+            lines_read: 0,
+        }
+    }
+
+    /// Reads up to `max_items` datums; none are read once the input is over.
+    pub fn read(&mut self, max_items: usize) -> Result<Vec<Vec<u8>>, failure::Error> {
+        if self.opened.is_none() {
+            let input: Box<dyn BufRead + Send> = match self.path {
+                Some(ref path) => Box::new(BufReader::new(File::open(path)?)),
+                None => Box::new(BufReader::new(io::stdin())),
+            };
+            self.opened = Some(input);
+        }
+        let input = self.opened.as_mut().unwrap();
+
+        let mut items = Vec::new();
+        let mut line = String::new();
+        while items.len() < max_items {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+            self.lines_read += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+            match to_datum(&line, &self.schema) {
+                Ok(datum) => items.push(datum),
+                Err(reason) => match self.on_malformed {
+                    MalformedLineSpec::Fail => {
+                        return Err(LineReaderError::MalformedLine {
+                            line: self.lines_read,
+                            reason,
+                        }
+                        .into())
+                    }
+                    MalformedLineSpec::Skip => (),
+                    MalformedLineSpec::Log => {
+                        warn!("skipping malformed line #{}: {}", self.lines_read, reason)
+                    }
+                },
+            }
+        }
+        Ok(items)
+    }
+}
+
+fn to_datum(line: &str, schema: &Schema) -> Result<Vec<u8>, failure::Error> {
+    let json = serde_json::from_str(line)?;
+    let data_item = from_json(&json, schema)?;
+    avro_rs::to_avro_datum(schema, data_item)
+}
+
+#[test]
+fn line_reader_test() {
+    use crate::protocol::DataItem;
+
+    let path = std::env::temp_dir().join(format!(
+        "raffineria-line-reader-{}.jsonl",
+        std::process::id()
+    ));
+    std::fs::write(&path, "1\n\n{\"oops\n3\n").unwrap();
+    let read_all = |on_malformed| -> Result<Vec<Vec<u8>>, failure::Error> {
+        let mut reader = LineReader::new(Some(path.clone()), Schema::Long, on_malformed);
+        let mut read = Vec::new();
+        loop {
+            let chunk = reader.read(1)?;
+            if chunk.is_empty() {
+                return Ok(read);
+            }
+            read.extend(chunk);
+        }
+    };
+    let fail = read_all(MalformedLineSpec::Fail);
+    let skip = read_all(MalformedLineSpec::Skip);
+    let log = read_all(MalformedLineSpec::Log);
+    std::fs::remove_file(&path).unwrap();
+
+    match fail
+        .unwrap_err()
+        .downcast::<LineReaderError>()
+        .expect("a LineReaderError")
+    {
+        LineReaderError::MalformedLine { line, .. } => assert_eq!(line, 3),
+    }
+
+    let expected = vec![
+        avro_rs::to_avro_datum(&Schema::Long, DataItem::Long(1)).unwrap(),
+        avro_rs::to_avro_datum(&Schema::Long, DataItem::Long(3)).unwrap(),
+    ];
+    assert_eq!(skip.unwrap(), expected);
+    assert_eq!(log.unwrap(), expected);
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod jsonl_source;
+pub use jsonl_source::JsonlSource;
+
+mod line_reader;
+use line_reader::LineReader;
+
+mod jsonl_source_impl_std_stage;
+
+mod jsonl_source_fsm;
+use jsonl_source_fsm::JsonlSourceFSM;
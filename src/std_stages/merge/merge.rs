@@ -1,4 +1,7 @@
 use crate::protocol::Schema;
+use crate::spec::MergeModeSpec;
+
+use super::*;
 
 #[derive(Debug)]
 pub struct Merge {
@@ -6,6 +9,7 @@ pub struct Merge {
     pub inlets_count: usize,
     pub eagerly_complete: bool,
     pub eagerly_fail: bool,
+    pub mode: MergeModeSpec,
 }
 
 impl Merge {
@@ -14,12 +18,28 @@ impl Merge {
         inlets_count: usize,
         eagerly_complete: bool,
         eagerly_fail: bool,
-    ) -> Self {
-        Self {
+        mode: MergeModeSpec,
+    ) -> Result<Self, StdStageError> {
+        match mode {
+            MergeModeSpec::Arrival => (),
+            MergeModeSpec::Priority { ref priorities } => {
+                if priorities.len() != inlets_count {
+                    return Err(StdStageError::InvalidParameter("priorities"));
+                }
+            }
+            MergeModeSpec::Weighted { ref weights } => {
+                if weights.len() != inlets_count || weights.contains(&0) {
+                    return Err(StdStageError::InvalidParameter("weights"));
+                }
+            }
+        }
+
+        Ok(Self {
             schema,
             inlets_count,
             eagerly_complete,
             eagerly_fail,
-        }
+            mode,
+        })
     }
 }
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+
+use crate::spec::MergeModeSpec;
+
+/// The items received from the upstreams and not sent downstream yet.
+#[derive(Debug)]
+pub enum MergeBuffer {
+    Arrival(VecDeque<Vec<u8>>),
+    Priority {
+        /// The inlets' indices, the greatest priority first.
+        order: Vec<usize>,
+        queues: Vec<VecDeque<Vec<u8>>>,
+    },
+    /// Smooth weighted round-robin among the inlets that have items.
+    Weighted {
+        weights: Vec<i64>,
+        current: Vec<i64>,
+        queues: Vec<VecDeque<Vec<u8>>>,
+    },
+}
+
+impl MergeBuffer {
+    pub fn new(mode: MergeModeSpec, inlets_count: usize) -> Self {
+        let queues = (0..inlets_count).map(|_| VecDeque::new()).collect();
+        match mode {
+            MergeModeSpec::Arrival => MergeBuffer::Arrival(VecDeque::new()),
+            MergeModeSpec::Priority { priorities } => {
+                let mut order = (0..inlets_count).collect::<Vec<_>>();
+                // stable: the inlets of the same priority are served in the order of their indices
+                order.sort_by_key(|inlet_idx| std::cmp::Reverse(priorities[*inlet_idx]));
+                MergeBuffer::Priority { order, queues }
+            }
+            MergeModeSpec::Weighted { weights } => MergeBuffer::Weighted {
+                weights: weights.into_iter().map(i64::from).collect(),
+                current: vec![0; inlets_count],
+                queues,
+            },
+        }
+    }
+
+    /// Whether the upstreams are worth pulling while there are items to send:
+    /// only if the items they would push could get ahead of the buffered ones.
+    pub fn pulls_eagerly(&self) -> bool {
+        match self {
+            MergeBuffer::Arrival(_) => false,
+            MergeBuffer::Priority { .. } | MergeBuffer::Weighted { .. } => true,
+        }
+    }
+
+    pub fn push(&mut self, inlet_idx: usize, items: Vec<Vec<u8>>) {
+        match self {
+            MergeBuffer::Arrival(queue) => queue.extend(items),
+            MergeBuffer::Priority { queues, .. } | MergeBuffer::Weighted { queues, .. } => {
+                queues[inlet_idx].extend(items)
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            MergeBuffer::Arrival(queue) => queue.is_empty(),
+            MergeBuffer::Priority { queues, .. } | MergeBuffer::Weighted { queues, .. } => {
+                queues.iter().all(VecDeque::is_empty)
+            }
+        }
+    }
+
+    pub fn has_items_of(&self, inlet_idx: usize) -> bool {
+        match self {
+            MergeBuffer::Arrival(_) => false,
+            MergeBuffer::Priority { queues, .. } | MergeBuffer::Weighted { queues, .. } => {
+                !queues[inlet_idx].is_empty()
+            }
+        }
+    }
+
+    pub fn take(&mut self, max_items: usize) -> Vec<Vec<u8>> {
+        match self {
+            MergeBuffer::Arrival(queue) => {
+                let items_to_send = std::cmp::min(queue.len(), max_items);
+                queue.drain(0..items_to_send).collect()
+            }
+
+            MergeBuffer::Priority { order, queues } => {
+                let mut items = Vec::new();
+                for inlet_idx in order.iter() {
+                    let queue = &mut queues[*inlet_idx];
+                    let items_to_send = std::cmp::min(queue.len(), max_items - items.len());
+                    items.extend(queue.drain(0..items_to_send));
+                }
+                items
+            }
+
+            MergeBuffer::Weighted {
+                weights,
+                current,
+                queues,
+            } => {
+                let mut items = Vec::new();
+                while items.len() < max_items {
+                    let backlogged = (0..queues.len())
+                        .filter(|inlet_idx| !queues[*inlet_idx].is_empty())
+                        .collect::<Vec<_>>();
+                    if backlogged.is_empty() {
+                        break;
+                    }
+
+                    let total_weight = backlogged.iter().map(|idx| weights[*idx]).sum::<i64>();
+                    for inlet_idx in backlogged.iter() {
+                        current[*inlet_idx] += weights[*inlet_idx];
+                    }
+                    let chosen = *backlogged
+                        .iter()
+                        .max_by_key(|inlet_idx| {
+                            (current[**inlet_idx], std::cmp::Reverse(**inlet_idx))
+                        })
+                        .unwrap();
+                    current[chosen] -= total_weight;
+
+                    items.push(queues[chosen].pop_front().unwrap());
+                }
+                items
+            }
+        }
+    }
+}
+
+#[test]
+fn merge_buffer_test() {
+    let items = |inlet: u8, count: u8| (0..count).map(|i| vec![inlet, i]).collect::<Vec<_>>();
+    let inlets_of = |taken: Vec<Vec<u8>>| taken.into_iter().map(|item| item[0]).collect::<Vec<_>>();
+
+    let mut arrival = MergeBuffer::new(MergeModeSpec::Arrival, 2);
+    arrival.push(1, items(1, 2));
+    arrival.push(0, items(0, 2));
+    assert_eq!(inlets_of(arrival.take(3)), vec![1, 1, 0]);
+
+    let mut priority = MergeBuffer::new(
+        MergeModeSpec::Priority {
+            priorities: vec![1, 5, 1],
+        },
+        3,
+    );
+    priority.push(0, items(0, 2));
+    priority.push(2, items(2, 1));
+    priority.push(1, items(1, 2));
+    assert_eq!(inlets_of(priority.take(4)), vec![1, 1, 0, 0]);
+    assert_eq!(inlets_of(priority.take(4)), vec![2]);
+    assert!(priority.is_empty());
+
+    let mut weighted = MergeBuffer::new(
+        MergeModeSpec::Weighted {
+            weights: vec![3, 1],
+        },
+        2,
+    );
+    weighted.push(0, items(0, 10));
+    weighted.push(1, items(1, 10));
+    let taken = inlets_of(weighted.take(8));
+    assert_eq!(taken.iter().filter(|inlet| **inlet == 0).count(), 6);
+    assert_eq!(taken.iter().filter(|inlet| **inlet == 1).count(), 2);
+    let taken = inlets_of(weighted.take(100));
+    assert_eq!(taken.len(), 12);
+    // the inlet #0 runs out first, then the inlet #1 takes all of the credit
+    assert_eq!(taken[8..], [1, 1, 1, 1]);
+}
@@ -1,5 +1,3 @@
-use std::collections::VecDeque;
-
 use futures::future;
 use futures::prelude::*;
 use futures::sync::mpsc;
@@ -13,6 +11,8 @@ use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
 use crate::protocol::streams::{ConsumerRx, ConsumerTx};
 use crate::protocol::streams::{ProducerRx, ProducerTx};
 
+use super::*;
+
 type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<MergeFSM>>;
 
 pub enum Event {
@@ -41,8 +41,6 @@ pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMe
 pub type UpstreamSendSingle = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
 pub type UpstreamsSend = SendBoxedFuture<Vec<ConsumerTx>, mpsc::SendError<ConsumerMessage>>;
 
-pub type ItemsBuffer = VecDeque<Vec<u8>>;
-
 #[derive(Debug, Clone)]
 pub enum UpstreamState {
     Pulled,
@@ -55,7 +53,7 @@ pub enum MergeFSM {
     Busy {
         eagerly_complete: bool,
         eagerly_fail: bool,
-        buffer: ItemsBuffer,
+        buffer: MergeBuffer,
         rx_events: RxEventStream,
         outlet_tx: ProducerTx,
         inlet_txs: Vec<ConsumerTx>,
@@ -64,6 +62,7 @@ pub enum MergeFSM {
     WaitingForUpstreams {
         eagerly_complete: bool,
         eagerly_fail: bool,
+        buffer: MergeBuffer,
         max_items: usize,
         rx_events: RxEventStream,
         outlet_tx: ProducerTx,
@@ -82,6 +81,7 @@ pub enum MergeFSM {
 
 impl MergeFSM {
     pub fn new(
+        buffer: MergeBuffer,
         inlets: Vec<(ConsumerTx, ConsumerRx)>,
         outlet: (ProducerTx, ProducerRx),
         eagerly_complete: bool,
@@ -97,7 +97,7 @@ impl MergeFSM {
         MergeFSM::Busy {
             eagerly_complete,
             eagerly_fail,
-            buffer: VecDeque::new(),
+            buffer,
             rx_events,
             outlet_tx,
             inlet_txs,
@@ -142,6 +142,7 @@ impl FSM for MergeFSM {
             MergeFSM::WaitingForUpstreams {
                 eagerly_complete,
                 eagerly_fail,
+                buffer,
                 max_items,
                 outlet_tx,
                 inlet_txs,
@@ -151,6 +152,7 @@ impl FSM for MergeFSM {
                 Async::NotReady => Ok(TurnOk::Suspend(MergeFSM::WaitingForUpstreams {
                     eagerly_complete,
                     eagerly_fail,
+                    buffer,
                     max_items,
                     outlet_tx,
                     inlet_txs,
@@ -164,6 +166,7 @@ impl FSM for MergeFSM {
                     event,
                     eagerly_complete,
                     eagerly_fail,
+                    buffer,
                     max_items,
                     outlet_tx,
                     inlet_txs,
@@ -213,6 +216,7 @@ fn handle_rx_event_while_waiting_for_upstreams(
 
     eagerly_complete: bool,
     eagerly_fail: bool,
+    mut buffer: MergeBuffer,
     max_items: usize,
     outlet_tx: ProducerTx,
     inlet_txs: Vec<ConsumerTx>,
@@ -264,6 +268,7 @@ fn handle_rx_event_while_waiting_for_upstreams(
                             rx_events,
                             eagerly_complete,
                             eagerly_fail,
+                            buffer,
                             max_items,
                         }))
                     }),
@@ -293,6 +298,7 @@ fn handle_rx_event_while_waiting_for_upstreams(
                             rx_events,
                             eagerly_complete,
                             eagerly_fail,
+                            buffer,
                             max_items,
                         }))
                     }),
@@ -303,19 +309,16 @@ fn handle_rx_event_while_waiting_for_upstreams(
         Event::ProducerMessage(producer_idx, ProducerMessage::Push { items }) => {
             upstream_states[producer_idx] = UpstreamState::Idle;
 
-            let items_available = items.len();
-            let items_to_send = std::cmp::min(max_items, items_available);
-            let mut items: VecDeque<_> = items.into();
-            let items_to_send = items.drain(0..items_to_send).collect();
+            buffer.push(producer_idx, items);
             let push_message = ProducerMessage::Push {
-                items: items_to_send,
+                items: buffer.take(max_items),
             };
 
             let downstream_push_sent = Box::new(outlet_tx.send(push_message));
 
             let into_busy = move |outlet_tx| {
                 Ok(TurnOk::PollMore(MergeFSM::Busy {
-                    buffer: items,
+                    buffer,
                     outlet_tx,
                     inlet_txs,
                     rx_events,
@@ -342,7 +345,7 @@ fn handle_rx_event_while_busy(
     inlet_txs: Vec<ConsumerTx>,
     rx_events: RxEventStream,
 
-    mut buffer: ItemsBuffer,
+    mut buffer: MergeBuffer,
     mut upstream_states: Vec<UpstreamState>,
 ) -> TurnResult<MergeFSM> {
     match event {
@@ -371,6 +374,7 @@ fn handle_rx_event_while_busy(
                         inlet_txs,
                         outlet_tx,
                         rx_events,
+                        buffer,
                         max_items,
                         upstream_states,
                         eagerly_complete,
@@ -383,29 +387,54 @@ fn handle_rx_event_while_busy(
                     and_then: SendBoxFnOnce::from(into_waiting_for_upstreams),
                 }))
             } else {
-                let items_available = buffer.len();
-                let items_to_send = std::cmp::min(items_available, max_items);
-                let items_to_send = buffer.drain(0..items_to_send).collect();
                 let push_sent = Box::new(outlet_tx.send(ProducerMessage::Push {
-                    items: items_to_send,
+                    items: buffer.take(max_items),
                 }));
 
-                let into_busy = move |outlet_tx| {
-                    Ok(TurnOk::PollMore(MergeFSM::Busy {
-                        outlet_tx,
-                        inlet_txs,
-                        rx_events,
+                // what the idle upstreams push may get ahead of what is buffered
+                let (upstream_states, upstream_pull_sents): (Vec<_>, Vec<_>) = upstream_states
+                    .into_iter()
+                    .zip(inlet_txs.into_iter())
+                    .enumerate()
+                    .map::<(UpstreamState, UpstreamSendSingle), _>(
+                        |(inlet_idx, (upstream_state, inlet_tx))| match upstream_state {
+                            UpstreamState::Idle
+                                if buffer.pulls_eagerly() && !buffer.has_items_of(inlet_idx) =>
+                            {
+                                (
+                                    UpstreamState::Pulled,
+                                    Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items })),
+                                )
+                            }
 
-                        buffer,
-                        upstream_states,
-                        eagerly_complete,
-                        eagerly_fail,
+                            as_is => (as_is, Box::new(future::ok(inlet_tx))),
+                        },
+                    )
+                    .unzip();
+                let upstream_pull_sents = Box::new(future::join_all(upstream_pull_sents));
+
+                let into_pushing_downstream = move |inlet_txs| {
+                    let into_busy = move |outlet_tx| {
+                        Ok(TurnOk::PollMore(MergeFSM::Busy {
+                            outlet_tx,
+                            inlet_txs,
+                            rx_events,
+
+                            buffer,
+                            upstream_states,
+                            eagerly_complete,
+                            eagerly_fail,
+                        }))
+                    };
+                    Ok(TurnOk::PollMore(MergeFSM::SendingToDownstream {
+                        sent: push_sent,
+                        and_then: SendBoxFnOnce::from(into_busy),
                     }))
                 };
 
-                Ok(TurnOk::PollMore(MergeFSM::SendingToDownstream {
-                    sent: push_sent,
-                    and_then: SendBoxFnOnce::from(into_busy),
+                Ok(TurnOk::PollMore(MergeFSM::SendingToUpstreams {
+                    sents: upstream_pull_sents,
+                    and_then: SendBoxFnOnce::from(into_pushing_downstream),
                 }))
             }
         }
@@ -491,7 +520,7 @@ fn handle_rx_event_while_busy(
 
         Event::ProducerMessage(producer_idx, ProducerMessage::Push { items }) => {
             upstream_states[producer_idx] = UpstreamState::Idle;
-            buffer.append(&mut items.into());
+            buffer.push(producer_idx, items);
 
             Ok(TurnOk::PollMore(MergeFSM::Busy {
                 rx_events,
@@ -24,10 +24,18 @@ impl StdStage for Merge {
         assert!(outlets.len() == 1);
         let outlet = outlets.pop().unwrap();
 
+        let buffer = MergeBuffer::new(self.mode, self.inlets_count);
+
         Box::new(
-            MergeFSM::new(inlets, outlet, self.eagerly_complete, self.eagerly_fail)
-                .into_fsm_future()
-                .map_err(|err| StdStageError::Generic(err.into())),
+            MergeFSM::new(
+                buffer,
+                inlets,
+                outlet,
+                self.eagerly_complete,
+                self.eagerly_fail,
+            )
+            .into_fsm_future()
+            .map_err(|err| StdStageError::Generic(err.into())),
         )
     }
 }
@@ -3,6 +3,9 @@ use super::*;
 mod merge;
 pub use merge::Merge;
 
+mod merge_buffer;
+use merge_buffer::MergeBuffer;
+
 mod merge_impl_std_stage;
 
 mod merge_fsm;
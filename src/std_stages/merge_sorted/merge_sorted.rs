@@ -0,0 +1,35 @@
+use crate::protocol::Schema;
+
+use super::super::field_path::FieldPath;
+use super::*;
+
+/// Merges the inlets, each sorted by the value at `key`, keeping the order;
+/// the inlets are trusted to be sorted.
+#[derive(Debug)]
+pub struct MergeSorted {
+    pub schema: Schema,
+    pub inlets_count: usize,
+    pub key: FieldPath,
+    pub eagerly_complete: bool,
+    pub eagerly_fail: bool,
+}
+
+impl MergeSorted {
+    pub fn new(
+        schema: Schema,
+        inlets_count: usize,
+        key: &str,
+        eagerly_complete: bool,
+        eagerly_fail: bool,
+    ) -> Result<Self, StdStageError> {
+        let key = FieldPath::new(key, &schema)?;
+
+        Ok(Self {
+            schema,
+            inlets_count,
+            key,
+            eagerly_complete,
+            eagerly_fail,
+        })
+    }
+}
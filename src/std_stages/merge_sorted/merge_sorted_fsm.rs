@@ -0,0 +1,378 @@
+use futures::future;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use boxfnonce::SendBoxFnOnce;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<MergeSortedFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(usize, ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum MergeSortedError {
+    #[fail(display = "MergeSortedError::RxError")]
+    RxError,
+
+    #[fail(display = "MergeSortedError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "MergeSortedError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+
+    #[fail(display = "MergeSortedError::PulledTwice")]
+    PulledTwice,
+}
+
+pub type RxEventStream = SendBoxedStream<Event, MergeSortedError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSendSingle = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+pub type UpstreamsSend = SendBoxedFuture<Vec<ConsumerTx>, mpsc::SendError<ConsumerMessage>>;
+
+#[derive(Debug, Clone)]
+pub enum UpstreamState {
+    Pulled,
+    Idle,
+    Complete,
+    Failed,
+}
+
+impl UpstreamState {
+    fn is_finished(&self) -> bool {
+        match self {
+            UpstreamState::Complete | UpstreamState::Failed => true,
+            UpstreamState::Idle | UpstreamState::Pulled => false,
+        }
+    }
+}
+
+pub struct MergeSortedState {
+    eagerly_complete: bool,
+    eagerly_fail: bool,
+    sorted_heads: SortedHeads,
+    upstream_states: Vec<UpstreamState>,
+    /// The downstream's pending pull.
+    demand: Option<usize>,
+    rx_events: RxEventStream,
+}
+
+pub enum MergeSortedFSM {
+    ReceiveEvent {
+        state: MergeSortedState,
+        outlet_tx: ProducerTx,
+        inlet_txs: Vec<ConsumerTx>,
+    },
+    SendingToUpstreams {
+        sents: UpstreamsSend,
+        and_then: Continue<(Vec<ConsumerTx>,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl MergeSortedFSM {
+    pub fn new(
+        sorted_heads: SortedHeads,
+        inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        outlet: (ProducerTx, ProducerRx),
+        eagerly_complete: bool,
+        eagerly_fail: bool,
+    ) -> Self {
+        let (outlet_tx, outlet_rx) = outlet;
+        let (inlet_txs, inlet_rxs): (Vec<_>, Vec<_>) = inlets.into_iter().unzip();
+
+        let rx_events = rxs_into_event_stream(inlet_rxs, outlet_rx);
+
+        let upstream_states = inlet_txs.iter().map(|_| UpstreamState::Idle).collect();
+
+        MergeSortedFSM::ReceiveEvent {
+            state: MergeSortedState {
+                eagerly_complete,
+                eagerly_fail,
+                sorted_heads,
+                upstream_states,
+                demand: None,
+                rx_events,
+            },
+            outlet_tx,
+            inlet_txs,
+        }
+    }
+}
+
+impl FSM for MergeSortedFSM {
+    type Item = ();
+    type Error = MergeSortedError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            MergeSortedFSM::SendingToUpstreams {
+                mut sents,
+                and_then,
+            } => sents
+                .poll()
+                .map_err(|err| MergeSortedError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(MergeSortedFSM::SendingToUpstreams {
+                        sents,
+                        and_then,
+                    })),
+
+                    Async::Ready(inlet_txs) => and_then.call(inlet_txs),
+                }),
+
+            MergeSortedFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| MergeSortedError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(MergeSortedFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            MergeSortedFSM::ReceiveEvent {
+                mut state,
+                outlet_tx,
+                inlet_txs,
+            } => state.rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(MergeSortedFSM::ReceiveEvent {
+                    state,
+                    outlet_tx,
+                    inlet_txs,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => handle_rx_event(event, state, outlet_tx, inlet_txs),
+            }),
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    mut state: MergeSortedState,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<MergeSortedFSM> {
+    match event {
+        Event::ConsumerMessage(ConsumerMessage::Pull { .. }) if state.demand.is_some() => {
+            Err(MergeSortedError::PulledTwice)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => {
+            state.demand = Some(max_items);
+            make_progress(state, outlet_tx, inlet_txs)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => shutdown(
+            state.upstream_states,
+            ProducerMessage::Complete,
+            outlet_tx,
+            inlet_txs,
+        ),
+
+        Event::ProducerMessage(producer_idx, ProducerMessage::Push { items }) => {
+            state.upstream_states[producer_idx] = UpstreamState::Idle;
+            match state.sorted_heads.push(producer_idx, items) {
+                Ok(()) => make_progress(state, outlet_tx, inlet_txs),
+                Err(reason) => shutdown(
+                    state.upstream_states,
+                    ProducerMessage::Fail {
+                        failure: PortFailure::from(reason),
+                    },
+                    outlet_tx,
+                    inlet_txs,
+                ),
+            }
+        }
+
+        Event::ProducerMessage(producer_idx, ProducerMessage::Complete) => {
+            state.upstream_states[producer_idx] = UpstreamState::Complete;
+            if state.eagerly_complete {
+                shutdown(
+                    state.upstream_states,
+                    ProducerMessage::Complete,
+                    outlet_tx,
+                    inlet_txs,
+                )
+            } else {
+                make_progress(state, outlet_tx, inlet_txs)
+            }
+        }
+
+        Event::ProducerMessage(producer_idx, ProducerMessage::Fail { failure }) => {
+            state.upstream_states[producer_idx] = UpstreamState::Failed;
+            if state.eagerly_fail {
+                shutdown(
+                    state.upstream_states,
+                    ProducerMessage::Fail { failure },
+                    outlet_tx,
+                    inlet_txs,
+                )
+            } else {
+                make_progress(state, outlet_tx, inlet_txs)
+            }
+        }
+    }
+}
+
+/// Completes the downstream once everything is sent,
+/// pushes the items that can be ordered already if the downstream has pulled,
+/// pulls the upstreams that have no items to compare otherwise.
+fn make_progress(
+    mut state: MergeSortedState,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<MergeSortedFSM> {
+    let finished = state
+        .upstream_states
+        .iter()
+        .map(UpstreamState::is_finished)
+        .collect::<Vec<_>>();
+
+    if finished.iter().all(|finished| *finished) && state.sorted_heads.is_drained() {
+        return shutdown(
+            state.upstream_states,
+            ProducerMessage::Complete,
+            outlet_tx,
+            inlet_txs,
+        );
+    }
+
+    let max_items = match state.demand {
+        Some(max_items) => max_items,
+        None => {
+            return Ok(TurnOk::PollMore(MergeSortedFSM::ReceiveEvent {
+                state,
+                outlet_tx,
+                inlet_txs,
+            }))
+        }
+    };
+
+    let items = state.sorted_heads.pop_sorted(max_items, &finished);
+    if !items.is_empty() {
+        state.demand = None;
+        let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+        let into_receiving_events = move |outlet_tx| {
+            Ok(TurnOk::PollMore(MergeSortedFSM::ReceiveEvent {
+                state,
+                outlet_tx,
+                inlet_txs,
+            }))
+        };
+        return Ok(TurnOk::PollMore(MergeSortedFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(into_receiving_events),
+        }));
+    }
+
+    let sorted_heads = &state.sorted_heads;
+    let (upstream_states, upstream_pull_sents): (Vec<_>, Vec<_>) = state
+        .upstream_states
+        .into_iter()
+        .zip(inlet_txs.into_iter())
+        .enumerate()
+        .map::<(UpstreamState, UpstreamSendSingle), _>(|(inlet_idx, (upstream_state, inlet_tx))| {
+            match upstream_state {
+                UpstreamState::Idle if sorted_heads.is_empty(inlet_idx) => (
+                    UpstreamState::Pulled,
+                    Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items })),
+                ),
+                as_is => (as_is, Box::new(future::ok(inlet_tx))),
+            }
+        })
+        .unzip();
+    state.upstream_states = upstream_states;
+    let upstream_pull_sents = Box::new(future::join_all(upstream_pull_sents));
+
+    let into_receiving_events = move |inlet_txs| {
+        Ok(TurnOk::PollMore(MergeSortedFSM::ReceiveEvent {
+            state,
+            outlet_tx,
+            inlet_txs,
+        }))
+    };
+    Ok(TurnOk::PollMore(MergeSortedFSM::SendingToUpstreams {
+        sents: upstream_pull_sents,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }))
+}
+
+/// Cancels the upstreams that are not finished, then sends `downstream_bye_message`.
+fn shutdown(
+    upstream_states: Vec<UpstreamState>,
+    downstream_bye_message: ProducerMessage,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<MergeSortedFSM> {
+    assert!(upstream_states.len() == inlet_txs.len());
+
+    let upstream_cancel_sents = Box::new(future::join_all(
+        upstream_states
+            .into_iter()
+            .zip(inlet_txs.into_iter())
+            .map::<UpstreamSendSingle, _>(|(upstream_state, inlet_tx)| {
+                if upstream_state.is_finished() {
+                    Box::new(future::ok(inlet_tx))
+                } else {
+                    Box::new(inlet_tx.send(ConsumerMessage::Cancel))
+                }
+            }),
+    ));
+
+    let send_downstream_termination = move |_inlet_txs| {
+        let downstream_terminate_sent = Box::new(outlet_tx.send(downstream_bye_message));
+        let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+        Ok(TurnOk::PollMore(MergeSortedFSM::SendingToDownstream {
+            sent: downstream_terminate_sent,
+            and_then: SendBoxFnOnce::from(shutdown),
+        }))
+    };
+
+    Ok(TurnOk::PollMore(MergeSortedFSM::SendingToUpstreams {
+        sents: upstream_cancel_sents,
+        and_then: SendBoxFnOnce::from(send_downstream_termination),
+    }))
+}
+
+fn rxs_into_event_stream(
+    inlet_rxs: Vec<ConsumerRx>,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, MergeSortedError> {
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| MergeSortedError::RxError);
+
+    let inlet_rx_events = inlet_rxs
+        .into_iter()
+        .enumerate()
+        .map(move |(inlet_idx, inlet_rx)| {
+            inlet_rx
+                .map(move |producer_message| Event::ProducerMessage(inlet_idx, producer_message))
+                .map_err(|()| MergeSortedError::RxError)
+        });
+
+    inlet_rx_events.fold::<SendBoxedStream<Event, MergeSortedError>, _>(
+        Box::new(outlet_rx_events),
+        |acc, inlet_rx| Box::new(acc.select(inlet_rx)),
+    )
+}
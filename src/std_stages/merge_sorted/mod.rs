@@ -0,0 +1,12 @@
+use super::*;
+
+mod merge_sorted;
+pub use merge_sorted::MergeSorted;
+
+mod sorted_heads;
+use sorted_heads::SortedHeads;
+
+mod merge_sorted_impl_std_stage;
+
+mod merge_sorted_fsm;
+use merge_sorted_fsm::MergeSortedFSM;
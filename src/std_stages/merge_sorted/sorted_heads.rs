@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use bytes::IntoBuf;
+
+use crate::protocol::{DataItem, Schema};
+
+use super::super::data_item_utils::compare;
+use super::super::field_path::FieldPath;
+
+/// The items received from each of the inlets and not sent downstream yet, along with their keys.
+#[derive(Debug)]
+pub struct SortedHeads {
+    schema: Schema,
+    key: FieldPath,
+    buffers: Vec<VecDeque<(DataItem, Vec<u8>)>>,
+}
+
+impl SortedHeads {
+    pub fn new(schema: Schema, key: FieldPath, inlets_count: usize) -> Self {
+        Self {
+            schema,
+            key,
+            buffers: (0..inlets_count).map(|_| VecDeque::new()).collect(),
+        }
+    }
+
+    pub fn push(&mut self, inlet_idx: usize, items: Vec<Vec<u8>>) -> Result<(), failure::Error> {
+        for item in items {
+            let data_item =
+                avro_rs::from_avro_datum(&self.schema, &mut (&item[..]).into_buf(), None)?;
+            // an absent key sorts first
+            let key = self.key.get(&data_item).cloned().unwrap_or(DataItem::Null);
+            self.buffers[inlet_idx].push_back((key, item));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self, inlet_idx: usize) -> bool {
+        self.buffers[inlet_idx].is_empty()
+    }
+
+    pub fn is_drained(&self) -> bool {
+        self.buffers.iter().all(VecDeque::is_empty)
+    }
+
+    /// Takes up to `max_items` smallest items, as long as every inlet not `finished` has an item to compare;
+    /// the equal items are taken in the order of the inlets.
+    pub fn pop_sorted(&mut self, max_items: usize, finished: &[bool]) -> Vec<Vec<u8>> {
+        let mut items = Vec::new();
+        while items.len() < max_items {
+            let starving = self
+                .buffers
+                .iter()
+                .zip(finished)
+                .any(|(buffer, finished)| buffer.is_empty() && !finished);
+            if starving {
+                break;
+            }
+
+            let smallest = self
+                .buffers
+                .iter()
+                .enumerate()
+                .filter_map(|(inlet_idx, buffer)| buffer.front().map(|(key, _)| (inlet_idx, key)))
+                .fold(
+                    None,
+                    |acc: Option<(usize, &DataItem)>, (inlet_idx, key)| match acc {
+                        Some((_, smallest_key)) if compare(key, smallest_key) != Ordering::Less => {
+                            acc
+                        }
+                        _ => Some((inlet_idx, key)),
+                    },
+                )
+                .map(|(inlet_idx, _)| inlet_idx);
+
+            match smallest {
+                Some(inlet_idx) => {
+                    let (_, item) = self.buffers[inlet_idx].pop_front().unwrap();
+                    items.push(item);
+                }
+                None => break,
+            }
+        }
+        items
+    }
+}
+
+#[test]
+fn sorted_heads_test() {
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "event", "fields": [{"name": "ts", "type": "long"}]}"#,
+    )
+    .unwrap();
+    let key = FieldPath::new("ts", &schema).unwrap();
+    let datum = |ts: i64| {
+        avro_rs::to_avro_datum(
+            &schema,
+            DataItem::Record(vec![("ts".to_owned(), DataItem::Long(ts))]),
+        )
+        .unwrap()
+    };
+
+    let mut heads = SortedHeads::new(schema.clone(), key, 2);
+    heads.push(0, vec![datum(1), datum(4)]).unwrap();
+    heads.push(1, vec![datum(2), datum(4)]).unwrap();
+
+    assert_eq!(
+        heads.pop_sorted(10, &[false, false]),
+        vec![datum(1), datum(2), datum(4)]
+    );
+    // the inlet #0 is to be pulled before going on
+    assert!(heads.is_empty(0));
+    assert_eq!(heads.pop_sorted(10, &[false, false]), Vec::<Vec<u8>>::new());
+    assert_eq!(heads.pop_sorted(10, &[true, false]), vec![datum(4)]);
+    assert!(heads.is_drained());
+}
@@ -6,8 +6,67 @@ pub use std_stage::from_spec;
 pub use std_stage::RunningFuture;
 pub use std_stage::{BoxedStdStage, StdStage};
 
+pub mod registry;
+
+mod avro_encoding;
+mod blocking;
+mod data_item_utils;
+mod field_path;
+mod rng;
+
 mod tee;
 use tee::Tee;
 
 mod merge;
 use merge::Merge;
+
+mod route;
+use route::Route;
+
+mod balance;
+use balance::Balance;
+
+mod zip;
+use zip::Zip;
+
+mod batch;
+use batch::Batch;
+
+mod throttle;
+use throttle::Throttle;
+
+mod buffer;
+use buffer::Buffer;
+
+mod avro_file_source;
+use avro_file_source::AvroFileSource;
+
+mod avro_file_sink;
+use avro_file_sink::AvroFileSink;
+
+mod jsonl_source;
+use jsonl_source::JsonlSource;
+
+mod jsonl_sink;
+use jsonl_sink::JsonlSink;
+
+mod slice;
+use slice::Slice;
+
+mod dedup;
+use dedup::Dedup;
+
+mod concat;
+use concat::Concat;
+
+mod generate;
+use generate::Generate;
+
+mod count;
+use count::Count;
+
+mod merge_sorted;
+use merge_sorted::MergeSorted;
+
+mod project;
+use project::Project;
@@ -0,0 +1,12 @@
+use super::*;
+
+mod project;
+pub use project::Project;
+
+mod projector;
+use projector::{Projection, Projector};
+
+mod project_impl_std_stage;
+
+mod project_fsm;
+use project_fsm::ProjectFSM;
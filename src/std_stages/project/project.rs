@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+
+use serde_json::json;
+use serde_json::Value as JsonValue;
+
+use crate::protocol::transcode::default_value;
+use crate::protocol::{DataItem, Schema};
+use crate::spec::ProjectionSpec;
+
+use super::super::field_path::FieldPath;
+use super::*;
+
+#[derive(Debug)]
+pub struct Project {
+    pub inlet_schema: Schema,
+    pub outlet_schema: Schema,
+    pub projections: Vec<Projection>,
+}
+
+impl Project {
+    pub fn new(
+        inlet_schema: Schema,
+        record_name: &str,
+        fields: Vec<ProjectionSpec>,
+        keep_others: bool,
+        drop: Vec<String>,
+    ) -> Result<Self, StdStageError> {
+        let inlet_fields = match &inlet_schema {
+            Schema::Record { fields, .. } => fields
+                .iter()
+                .map(|field| field.name.to_owned())
+                .collect::<Vec<_>>(),
+            _ => return Err(StdStageError::InvalidParameter("schema")),
+        };
+        if !keep_others && !drop.is_empty() {
+            return Err(StdStageError::InvalidParameter("drop"));
+        }
+        if let Some(unknown) = drop.iter().find(|name| !inlet_fields.contains(name)) {
+            return Err(StdStageError::UnknownField(unknown.to_owned()));
+        }
+
+        let mut taken = HashSet::new();
+        let mut projected = fields
+            .into_iter()
+            .map(
+                |ProjectionSpec {
+                     name,
+                     from,
+                     default,
+                 }| {
+                    let from = from.unwrap_or_else(|| name.to_owned());
+                    let path = FieldPath::new(&from, &inlet_schema)?;
+                    taken.insert(from);
+                    taken.insert(name.to_owned());
+                    Ok((name, path, default))
+                },
+            )
+            .collect::<Result<Vec<_>, StdStageError>>()?;
+        if keep_others {
+            for name in inlet_fields {
+                if !taken.contains(&name) && !drop.contains(&name) {
+                    let path = FieldPath::new(&name, &inlet_schema)?;
+                    projected.push((name, path, None));
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        if projected.is_empty() || !projected.iter().all(|(name, _, _)| names.insert(name)) {
+            return Err(StdStageError::InvalidParameter("fields"));
+        }
+
+        let outlet_schema = outlet_schema(&inlet_schema, &projected, record_name)?;
+        let outlet_fields = match &outlet_schema {
+            Schema::Record { fields, .. } => fields,
+            _ => unreachable!("the outlet schema is parsed from a record"),
+        };
+
+        let projections = projected
+            .into_iter()
+            .zip(outlet_fields)
+            .map(|((name, path, default), outlet_field)| {
+                let absent = match default {
+                    Some(json) => Some(
+                        default_value(&json, &outlet_field.schema)
+                            .map_err(|_| StdStageError::InvalidFieldDefault(name.to_owned()))?,
+                    ),
+                    None if path.is_optional(&inlet_schema) => {
+                        Some(DataItem::Union(Box::new(DataItem::Null)))
+                    }
+                    None => None,
+                };
+                let wraps_union = match outlet_field.schema {
+                    Schema::Union(_) => true,
+                    _ => false,
+                };
+                Ok(Projection {
+                    name,
+                    path,
+                    wraps_union,
+                    absent,
+                })
+            })
+            .collect::<Result<Vec<_>, StdStageError>>()?;
+
+        Ok(Self {
+            inlet_schema,
+            outlet_schema,
+            projections,
+        })
+    }
+}
+
+/// The fields that may be absent from the inlet's records and have no default become nullable.
+fn outlet_schema(
+    inlet_schema: &Schema,
+    projected: &[(String, FieldPath, Option<JsonValue>)],
+    record_name: &str,
+) -> Result<Schema, StdStageError> {
+    let fields_json = projected
+        .iter()
+        .map(|(name, path, default)| {
+            let field_schema = path
+                .schema(inlet_schema)
+                .expect("checked by FieldPath::new");
+            let type_json = serde_json::to_value(field_schema)
+                .map_err(|err| StdStageError::SchemaParseError(err.into()))?;
+
+            Ok(match default {
+                Some(default) => json!({"name": name, "type": type_json, "default": default}),
+                None if path.is_optional(inlet_schema) => {
+                    json!({"name": name, "type": nullable(type_json)})
+                }
+                None => json!({"name": name, "type": type_json}),
+            })
+        })
+        .collect::<Result<Vec<_>, StdStageError>>()?;
+
+    Schema::parse(&json!({
+        "type": "record",
+        "name": record_name,
+        "fields": fields_json,
+    }))
+    .map_err(|err| StdStageError::SchemaParseError(err))
+}
+
+fn nullable(type_json: JsonValue) -> JsonValue {
+    match type_json {
+        JsonValue::Array(mut variants) => {
+            if !variants.contains(&json!("null")) {
+                variants.insert(0, json!("null"));
+            }
+            JsonValue::Array(variants)
+        }
+        as_is => json!(["null", as_is]),
+    }
+}
@@ -0,0 +1,234 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<ProjectFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum ProjectError {
+    #[fail(display = "ProjectError::RxError")]
+    RxError,
+
+    #[fail(display = "ProjectError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "ProjectError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, ProjectError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum ProjectFSM {
+    ReceiveEvent {
+        projector: Projector,
+        rx_events: RxEventStream,
+        inlet_tx: ConsumerTx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl ProjectFSM {
+    pub fn new(
+        projector: Projector,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_tx, outlet_rx) = outlet;
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rx);
+
+        ProjectFSM::ReceiveEvent {
+            projector,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for ProjectFSM {
+    type Item = ();
+    type Error = ProjectError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            ProjectFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| ProjectError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ProjectFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            ProjectFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| ProjectError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ProjectFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            ProjectFSM::ReceiveEvent {
+                projector,
+                mut rx_events,
+                inlet_tx,
+                outlet_tx,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(ProjectFSM::ReceiveEvent {
+                    projector,
+                    rx_events,
+                    inlet_tx,
+                    outlet_tx,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => {
+                    handle_rx_event(event, projector, rx_events, inlet_tx, outlet_tx)
+                }
+            }),
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    projector: Projector,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<ProjectFSM> {
+    match event {
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => {
+            pull_upstream(projector, max_items, rx_events, inlet_tx, outlet_tx)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => {
+            let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+            let complete_downstream = move |_inlet_tx| {
+                let sent = Box::new(outlet_tx.send(ProducerMessage::Complete));
+                let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+                Ok(TurnOk::PollMore(ProjectFSM::SendingToDownstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(shutdown),
+                }))
+            };
+            Ok(TurnOk::PollMore(ProjectFSM::SendingToUpstream {
+                sent,
+                and_then: SendBoxFnOnce::from(complete_downstream),
+            }))
+        }
+
+        Event::ProducerMessage(ProducerMessage::Push { items }) => match projector.project(items) {
+            Err(reason) => {
+                let failure = PortFailure::from(reason);
+                let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+                let into_failing_downstream = move |_inlet_tx| {
+                    let sent = Box::new(outlet_tx.send(ProducerMessage::Fail { failure }));
+                    let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+                    Ok(TurnOk::PollMore(ProjectFSM::SendingToDownstream {
+                        sent,
+                        and_then: SendBoxFnOnce::from(shutdown),
+                    }))
+                };
+                Ok(TurnOk::PollMore(ProjectFSM::SendingToUpstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(into_failing_downstream),
+                }))
+            }
+
+            Ok(items) => {
+                let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+                let into_receiving_events = move |outlet_tx| {
+                    Ok(TurnOk::PollMore(ProjectFSM::ReceiveEvent {
+                        projector,
+                        rx_events,
+                        inlet_tx,
+                        outlet_tx,
+                    }))
+                };
+                Ok(TurnOk::PollMore(ProjectFSM::SendingToDownstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(into_receiving_events),
+                }))
+            }
+        },
+
+        Event::ProducerMessage(bye_message) => {
+            let sent = Box::new(outlet_tx.send(bye_message));
+            let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+            Ok(TurnOk::PollMore(ProjectFSM::SendingToDownstream {
+                sent,
+                and_then: SendBoxFnOnce::from(shutdown),
+            }))
+        }
+    }
+}
+
+fn pull_upstream(
+    projector: Projector,
+    max_items: usize,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<ProjectFSM> {
+    let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items }));
+    let into_receiving_events = move |inlet_tx| {
+        Ok(TurnOk::PollMore(ProjectFSM::ReceiveEvent {
+            projector,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }))
+    };
+    Ok(TurnOk::PollMore(ProjectFSM::SendingToUpstream {
+        sent,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }))
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, ProjectError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| ProjectError::RxError);
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| ProjectError::RxError);
+
+    Box::new(inlet_rx_events.select(outlet_rx_events))
+}
@@ -0,0 +1,38 @@
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for Project {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.inlet_schema]
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.outlet_schema]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        mut inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        mut outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(inlets.len() == 1);
+        assert!(outlets.len() == 1);
+        let inlet = inlets.pop().unwrap();
+        let outlet = outlets.pop().unwrap();
+
+        let projector = Projector::new(self.inlet_schema, self.outlet_schema, self.projections);
+
+        Box::new(
+            ProjectFSM::new(projector, inlet, outlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
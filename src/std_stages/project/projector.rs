@@ -0,0 +1,225 @@
+use bytes::IntoBuf;
+
+use crate::protocol::{DataItem, Schema};
+
+use super::super::field_path::FieldPath;
+
+#[derive(Fail, Debug)]
+pub enum ProjectError {
+    #[fail(display = "ProjectError::MissingField: {}", _0)]
+    MissingField(String),
+}
+
+/// A field of the outlet's records.
+#[derive(Debug)]
+pub struct Projection {
+    pub name: String,
+    pub path: FieldPath,
+    /// Whether the field's outlet schema is a union (`FieldPath::get` unwraps the inlet's unions).
+    pub wraps_union: bool,
+    /// The value for the inlet's records that lack the field.
+    pub absent: Option<DataItem>,
+}
+
+impl Projection {
+    fn value(&self, data_item: &DataItem) -> Result<DataItem, ProjectError> {
+        match self.path.get(data_item) {
+            Some(value) if self.wraps_union => Ok(DataItem::Union(Box::new(value.clone()))),
+            Some(value) => Ok(value.clone()),
+            None => self
+                .absent
+                .clone()
+                .ok_or_else(|| ProjectError::MissingField(self.name.to_owned())),
+        }
+    }
+}
+
+/// Re-encodes the inlet's records as the outlet's ones.
+#[derive(Debug)]
+pub struct Projector {
+    inlet_schema: Schema,
+    outlet_schema: Schema,
+    projections: Vec<Projection>,
+}
+
+impl Projector {
+    pub fn new(inlet_schema: Schema, outlet_schema: Schema, projections: Vec<Projection>) -> Self {
+        Self {
+            inlet_schema,
+            outlet_schema,
+            projections,
+        }
+    }
+
+    pub fn project(&self, items: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, failure::Error> {
+        items
+            .into_iter()
+            .map(|item| {
+                let data_item = avro_rs::from_avro_datum(
+                    &self.inlet_schema,
+                    &mut (&item[..]).into_buf(),
+                    None,
+                )?;
+                let fields = self
+                    .projections
+                    .iter()
+                    .map(|projection| {
+                        projection
+                            .value(&data_item)
+                            .map(|value| (projection.name.to_owned(), value))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(avro_rs::to_avro_datum(
+                    &self.outlet_schema,
+                    DataItem::Record(fields),
+                )?)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn projector_test() {
+    use super::Project;
+    use crate::spec::ProjectionSpec;
+
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "event", "fields": [
+            {"name": "kind", "type": "string"},
+            {"name": "ts", "type": "long"},
+            {"name": "user", "type": ["null", {"type": "record", "name": "user", "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "name", "type": "string"}
+            ]}]}
+        ]}"#,
+    )
+    .unwrap();
+    let projection =
+        |name: &str, from: Option<&str>, default: Option<serde_json::Value>| ProjectionSpec {
+            name: name.to_owned(),
+            from: from.map(|from| from.to_owned()),
+            default,
+        };
+    let project = Project::new(
+        schema.clone(),
+        "projected",
+        vec![
+            projection("event_kind", Some("kind"), None),
+            projection("user_id", Some("user.id"), None),
+            projection(
+                "user_name",
+                Some("user.name"),
+                Some(serde_json::json!("anonymous")),
+            ),
+        ],
+        true,
+        vec!["ts".to_owned()],
+    )
+    .unwrap();
+
+    let fields = match &project.outlet_schema {
+        Schema::Record { fields, .. } => fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.schema.clone()))
+            .collect::<Vec<_>>(),
+        _ => panic!("not a record"),
+    };
+    let user_schema = match &schema {
+        Schema::Record { fields, .. } => fields[2].schema.clone(),
+        _ => panic!("not a record"),
+    };
+    assert_eq!(
+        fields,
+        vec![
+            ("event_kind", Schema::String),
+            ("user_id", Schema::parse_str(r#"["null", "long"]"#).unwrap()),
+            ("user_name", Schema::String),
+            ("user", user_schema),
+        ]
+    );
+
+    let outlet_schema = project.outlet_schema.clone();
+    let projector = Projector::new(
+        project.inlet_schema,
+        project.outlet_schema,
+        project.projections,
+    );
+    let datum = |user: Option<(i64, &str)>| {
+        let user = match user {
+            Some((id, name)) => DataItem::Record(vec![
+                ("id".to_owned(), DataItem::Long(id)),
+                ("name".to_owned(), DataItem::String(name.to_owned())),
+            ]),
+            None => DataItem::Null,
+        };
+        let data_item = DataItem::Record(vec![
+            ("kind".to_owned(), DataItem::String("click".to_owned())),
+            ("ts".to_owned(), DataItem::Long(1)),
+            ("user".to_owned(), DataItem::Union(Box::new(user))),
+        ]);
+        avro_rs::to_avro_datum(&schema, data_item).unwrap()
+    };
+    let projected = projector
+        .project(vec![datum(Some((7, "jane"))), datum(None)])
+        .unwrap()
+        .into_iter()
+        .map(|item| {
+            avro_rs::from_avro_datum(&outlet_schema, &mut (&item[..]).into_buf(), None).unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        projected,
+        vec![
+            DataItem::Record(vec![
+                (
+                    "event_kind".to_owned(),
+                    DataItem::String("click".to_owned())
+                ),
+                (
+                    "user_id".to_owned(),
+                    DataItem::Union(Box::new(DataItem::Long(7)))
+                ),
+                ("user_name".to_owned(), DataItem::String("jane".to_owned())),
+                (
+                    "user".to_owned(),
+                    DataItem::Union(Box::new(DataItem::Record(vec![
+                        ("id".to_owned(), DataItem::Long(7)),
+                        ("name".to_owned(), DataItem::String("jane".to_owned())),
+                    ])))
+                ),
+            ]),
+            DataItem::Record(vec![
+                (
+                    "event_kind".to_owned(),
+                    DataItem::String("click".to_owned())
+                ),
+                (
+                    "user_id".to_owned(),
+                    DataItem::Union(Box::new(DataItem::Null))
+                ),
+                (
+                    "user_name".to_owned(),
+                    DataItem::String("anonymous".to_owned())
+                ),
+                ("user".to_owned(), DataItem::Union(Box::new(DataItem::Null))),
+            ]),
+        ]
+    );
+
+    assert!(Project::new(
+        schema.clone(),
+        "projected",
+        vec![projection("user_email", Some("user.email"), None)],
+        false,
+        vec![],
+    )
+    .is_err());
+    assert!(Project::new(
+        schema.clone(),
+        "projected",
+        vec![projection("ts", Some("kind"), Some(serde_json::json!(0)))],
+        false,
+        vec![],
+    )
+    .is_err());
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::de::DeserializeOwned;
+
+use super::*;
+
+type Factory = dyn Fn(serde_json::Value) -> Result<BoxedStdStage, StdStageError> + Send + Sync;
+
+lazy_static! {
+    static ref REGISTRY: RwLock<HashMap<String, Arc<Factory>>> = RwLock::new(HashMap::new());
+}
+
+/// Makes a stage available to the specs as `std: {custom: {name: <name>, config: ...}}`.
+/// The `config` is deserialized into `C` before being passed to the factory.
+pub fn register<C, F>(name: &str, factory: F) -> Result<(), StdStageError>
+where
+    C: DeserializeOwned,
+    F: Fn(C) -> Result<BoxedStdStage, StdStageError> + Send + Sync + 'static,
+{
+    let factory = move |config: serde_json::Value| {
+        serde_json::from_value::<C>(config)
+            .map_err(|err| StdStageError::ConfigError(err.into()))
+            .and_then(|config| factory(config))
+    };
+
+    let mut registry = REGISTRY.write().expect("std-stage registry lock poisoned");
+    if registry.contains_key(name) {
+        Err(StdStageError::DuplicateCustomStage(name.to_owned()))
+    } else {
+        registry.insert(name.to_owned(), Arc::new(factory));
+        Ok(())
+    }
+}
+
+pub fn create(name: &str, config: serde_json::Value) -> Result<BoxedStdStage, StdStageError> {
+    let factory = REGISTRY
+        .read()
+        .expect("std-stage registry lock poisoned")
+        .get(name)
+        .cloned()
+        .ok_or_else(|| StdStageError::UnknownCustomStage(name.to_owned()))?;
+
+    factory(config)
+}
+
+#[test]
+fn registry_test() {
+    use crate::spec::StdStageSpec;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    struct FanOutConfig {
+        schema: serde_json::Value,
+        width: usize,
+    }
+
+    register("fan_out", |config: FanOutConfig| {
+        let schema = crate::protocol::Schema::parse(&config.schema)
+            .map_err(|err| StdStageError::SchemaParseError(err))?;
+        Ok(Box::new(Tee::new(schema, config.width)) as BoxedStdStage)
+    })
+    .unwrap();
+
+    assert!(
+        register("fan_out", |()| Err(StdStageError::UnknownCustomStage(
+            "".to_owned()
+        )))
+        .is_err()
+    );
+
+    let stage = from_spec(StdStageSpec::Custom {
+        name: "fan_out".to_owned(),
+        config: json!({"schema": "string", "width": 3}),
+    })
+    .unwrap();
+    assert_eq!(stage.outlet_schemas().len(), 3);
+
+    assert!(create("no_such_stage", serde_json::Value::Null).is_err());
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod route;
+pub use route::Route;
+
+mod router;
+use router::Router;
+
+mod route_impl_std_stage;
+
+mod route_fsm;
+use route_fsm::RouteFSM;
@@ -0,0 +1,27 @@
+use crate::protocol::Schema;
+use crate::spec::RouteRuleSpec;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct Route {
+    pub schema: Schema,
+    pub outlets_count: usize,
+    pub router: Router,
+}
+
+impl Route {
+    pub fn new(
+        schema: Schema,
+        outlets_count: usize,
+        rules: Vec<RouteRuleSpec>,
+        default_outlet: Option<usize>,
+    ) -> Result<Self, StdStageError> {
+        let router = Router::new(&schema, outlets_count, rules, default_outlet)?;
+        Ok(Self {
+            schema,
+            outlets_count,
+            router,
+        })
+    }
+}
@@ -0,0 +1,388 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::future;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::command::Failure as PortFailure;
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<RouteFSM>>;
+
+pub enum Event {
+    ConsumerMessage(usize, ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum RouteError {
+    #[fail(display = "RouteError::RxError")]
+    RxError,
+
+    #[fail(display = "RouteError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "RouteError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, RouteError>;
+
+#[derive(Debug, Clone)]
+pub enum DownstreamState {
+    Busy,
+    Ready(usize),
+}
+
+pub type DownstreamsSend = SendBoxedFuture<Vec<ProducerTx>, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum RouteFSM {
+    SendingToDownstreams {
+        sents: DownstreamsSend,
+        and_then: Continue<(Vec<ProducerTx>,)>,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    ReceiveEvent {
+        router: Router,
+        rx_events: RxEventStream,
+        inlet_tx: ConsumerTx,
+        outlet_txs: Vec<ProducerTx>,
+        downstream_states: Vec<DownstreamState>,
+    },
+}
+
+impl FSM for RouteFSM {
+    type Item = ();
+    type Error = RouteError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            RouteFSM::ReceiveEvent {
+                router,
+                mut rx_events,
+                inlet_tx,
+                outlet_txs,
+                downstream_states,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(RouteFSM::ReceiveEvent {
+                    router,
+                    rx_events,
+                    inlet_tx,
+                    outlet_txs,
+                    downstream_states,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => handle_rx_event(
+                    event,
+                    router,
+                    rx_events,
+                    inlet_tx,
+                    outlet_txs,
+                    downstream_states,
+                ),
+            }),
+
+            RouteFSM::SendingToDownstreams {
+                mut sents,
+                and_then,
+            } => sents
+                .poll()
+                .map_err(|err| RouteError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(RouteFSM::SendingToDownstreams {
+                        sents,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_txs) => and_then.call(outlet_txs),
+                }),
+
+            RouteFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| RouteError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(RouteFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+        }
+    }
+}
+
+impl RouteFSM {
+    pub fn new(
+        router: Router,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_txs, outlet_rxs): (Vec<_>, Vec<_>) = outlets.into_iter().unzip();
+        let downstream_states = outlet_txs.iter().map(|_| DownstreamState::Busy).collect();
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rxs);
+
+        RouteFSM::ReceiveEvent {
+            router,
+            rx_events,
+            inlet_tx,
+            outlet_txs,
+            downstream_states,
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    router: Router,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_txs: Vec<ProducerTx>,
+    downstream_states: Vec<DownstreamState>,
+) -> TurnResult<RouteFSM> {
+    match event {
+        Event::ProducerMessage(producer_message) => handle_rx_event_producer_message(
+            producer_message,
+            router,
+            rx_events,
+            inlet_tx,
+            outlet_txs,
+            downstream_states,
+        ),
+
+        Event::ConsumerMessage(consumer_idx, consumer_message) => handle_rx_event_consumer_message(
+            consumer_idx,
+            consumer_message,
+            router,
+            rx_events,
+            inlet_tx,
+            outlet_txs,
+            downstream_states,
+        ),
+    }
+}
+
+fn handle_rx_event_producer_message(
+    producer_message: ProducerMessage,
+    router: Router,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_txs: Vec<ProducerTx>,
+    mut downstream_states: Vec<DownstreamState>,
+) -> TurnResult<RouteFSM> {
+    match producer_message {
+        ProducerMessage::Fail { failure } => {
+            Ok(TurnOk::PollMore(failing_downstreams(outlet_txs, failure)))
+        }
+
+        ProducerMessage::Complete => {
+            let shutdown = |_outlet_txs| Ok(TurnOk::Ready(()));
+            let sents = Box::new(future::join_all(
+                outlet_txs
+                    .into_iter()
+                    .map(|outlet_tx| outlet_tx.send(ProducerMessage::Complete)),
+            ));
+            Ok(TurnOk::PollMore(RouteFSM::SendingToDownstreams {
+                sents,
+                and_then: SendBoxFnOnce::from(shutdown),
+            }))
+        }
+
+        ProducerMessage::Push { items } => {
+            let routed = items
+                .into_iter()
+                .map(|item| router.route(&item).map(|outlet_idx| (outlet_idx, item)))
+                .collect::<Result<Vec<_>, _>>();
+
+            match routed {
+                Err(reason) => {
+                    let failure = PortFailure::from(Into::<failure::Error>::into(reason));
+                    let cancel_upstream_sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+                    let into_failing_downstreams = move |_inlet_tx| {
+                        Ok(TurnOk::PollMore(failing_downstreams(outlet_txs, failure)))
+                    };
+                    Ok(TurnOk::PollMore(RouteFSM::SendingToUpstream {
+                        sent: cancel_upstream_sent,
+                        and_then: SendBoxFnOnce::from(into_failing_downstreams),
+                    }))
+                }
+
+                Ok(routed) => {
+                    let mut outlet_items =
+                        outlet_txs.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+                    for (outlet_idx, item) in routed {
+                        outlet_items[outlet_idx].push(item);
+                        downstream_states[outlet_idx] = DownstreamState::Busy;
+                    }
+
+                    let into_pulling_upstream = move |outlet_txs| {
+                        maybe_pull_upstream(
+                            router,
+                            rx_events,
+                            inlet_tx,
+                            outlet_txs,
+                            downstream_states,
+                        )
+                    };
+                    let sents = Box::new(future::join_all(
+                        outlet_txs
+                            .into_iter()
+                            .zip(outlet_items)
+                            .map(|(outlet_tx, items)| {
+                                if items.is_empty() {
+                                    future::Either::A(future::ok(outlet_tx))
+                                } else {
+                                    future::Either::B(
+                                        outlet_tx.send(ProducerMessage::Push { items }),
+                                    )
+                                }
+                            }),
+                    ));
+                    Ok(TurnOk::PollMore(RouteFSM::SendingToDownstreams {
+                        sents,
+                        and_then: SendBoxFnOnce::from(into_pulling_upstream),
+                    }))
+                }
+            }
+        }
+    }
+}
+
+fn handle_rx_event_consumer_message(
+    consumer_idx: usize,
+    consumer_message: ConsumerMessage,
+    router: Router,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_txs: Vec<ProducerTx>,
+    mut downstream_states: Vec<DownstreamState>,
+) -> TurnResult<RouteFSM> {
+    assert!(consumer_idx < downstream_states.len());
+    match consumer_message {
+        ConsumerMessage::Cancel => {
+            let cancel_upstream_sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+            let into_completing_downstreams = move |_inlet_tx| {
+                let shutdown = |_outlet_txs| Ok(TurnOk::Ready(()));
+                let complete_downstreams_sent = Box::new(future::join_all(
+                    outlet_txs
+                        .into_iter()
+                        .map(|outlet_tx| outlet_tx.send(ProducerMessage::Complete)),
+                ));
+
+                Ok(TurnOk::PollMore(RouteFSM::SendingToDownstreams {
+                    sents: complete_downstreams_sent,
+                    and_then: SendBoxFnOnce::from(shutdown),
+                }))
+            };
+            Ok(TurnOk::PollMore(RouteFSM::SendingToUpstream {
+                sent: cancel_upstream_sent,
+                and_then: SendBoxFnOnce::from(into_completing_downstreams),
+            }))
+        }
+
+        ConsumerMessage::Pull { max_items } => {
+            downstream_states[consumer_idx] = DownstreamState::Ready(max_items);
+            maybe_pull_upstream(router, rx_events, inlet_tx, outlet_txs, downstream_states)
+        }
+    }
+}
+
+/// Pulls upstream once every downstream is ready to accept the items, whichever outlets they get routed to.
+fn maybe_pull_upstream(
+    router: Router,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_txs: Vec<ProducerTx>,
+    downstream_states: Vec<DownstreamState>,
+) -> TurnResult<RouteFSM> {
+    match should_pull_upstream(&downstream_states) {
+        Some(non_zero) if non_zero > 0 => {
+            let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull {
+                max_items: non_zero,
+            }));
+            let into_receiving_events = move |inlet_tx| {
+                Ok(TurnOk::PollMore(RouteFSM::ReceiveEvent {
+                    router,
+                    rx_events,
+                    inlet_tx,
+                    outlet_txs,
+                    downstream_states,
+                }))
+            };
+            Ok(TurnOk::PollMore(RouteFSM::SendingToUpstream {
+                sent,
+                and_then: SendBoxFnOnce::from(into_receiving_events),
+            }))
+        }
+        _ => Ok(TurnOk::PollMore(RouteFSM::ReceiveEvent {
+            router,
+            rx_events,
+            inlet_tx,
+            outlet_txs,
+            downstream_states,
+        })),
+    }
+}
+
+fn failing_downstreams(outlet_txs: Vec<ProducerTx>, failure: PortFailure) -> RouteFSM {
+    let shutdown = |_outlet_txs| Ok(TurnOk::Ready(()));
+    let sents = Box::new(future::join_all(outlet_txs.into_iter().map(
+        move |outlet_tx| {
+            outlet_tx.send(ProducerMessage::Fail {
+                failure: failure.clone(),
+            })
+        },
+    )));
+    RouteFSM::SendingToDownstreams {
+        sents,
+        and_then: SendBoxFnOnce::from(shutdown),
+    }
+}
+
+fn should_pull_upstream(downstream_states: &Vec<DownstreamState>) -> Option<usize> {
+    downstream_states
+        .iter()
+        .fold(None, |acc, state| match (acc, state) {
+            (_, &DownstreamState::Busy) => Some(0),
+            (None, &DownstreamState::Ready(ref max_items)) => Some(*max_items),
+            (Some(acc), &DownstreamState::Ready(ref max_items)) => {
+                Some(std::cmp::min(acc, *max_items))
+            }
+        })
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rxs: Vec<ProducerRx>,
+) -> SendBoxedStream<Event, RouteError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| RouteError::RxError);
+    let outlet_rx_events =
+        outlet_rxs
+            .into_iter()
+            .enumerate()
+            .map(move |(outlet_idx, outlet_rx)| {
+                outlet_rx
+                    .map(move |consumer_message| {
+                        Event::ConsumerMessage(outlet_idx, consumer_message)
+                    })
+                    .map_err(|()| RouteError::RxError)
+            });
+
+    outlet_rx_events.fold::<SendBoxedStream<Event, RouteError>, _>(
+        Box::new(inlet_rx_events),
+        |acc, outlet_rx| Box::new(acc.select(outlet_rx)),
+    )
+}
@@ -0,0 +1,177 @@
+use bytes::IntoBuf;
+
+use crate::protocol::Schema;
+use crate::spec::RouteRuleSpec;
+
+use super::super::data_item_utils::{eq_json, stable_hash};
+use super::super::field_path::FieldPath;
+use super::*;
+
+#[derive(Fail, Debug)]
+pub enum RoutingError {
+    #[fail(display = "RoutingError::DecodeError")]
+    DecodeError(#[cause] failure::Error),
+
+    #[fail(display = "RoutingError::Unroutable")]
+    Unroutable,
+}
+
+#[derive(Debug)]
+enum Rule {
+    FieldEquals {
+        field: FieldPath,
+        value: serde_json::Value,
+        outlet: usize,
+    },
+    HashMod {
+        field: FieldPath,
+        outlets: Vec<usize>,
+    },
+}
+
+/// Chooses an outlet for each item: the first matching rule wins,
+/// the items matched by none of the rules go to the default outlet.
+#[derive(Debug)]
+pub struct Router {
+    schema: Schema,
+    rules: Vec<Rule>,
+    default_outlet: Option<usize>,
+}
+
+impl Router {
+    pub fn new(
+        schema: &Schema,
+        outlets_count: usize,
+        rules: Vec<RouteRuleSpec>,
+        default_outlet: Option<usize>,
+    ) -> Result<Self, StdStageError> {
+        let check_outlet = |outlet: usize| {
+            if outlet < outlets_count {
+                Ok(outlet)
+            } else {
+                Err(StdStageError::OutletOutOfRange {
+                    outlet,
+                    outlets_count,
+                })
+            }
+        };
+
+        let rules = rules
+            .into_iter()
+            .map(|rule_spec| match rule_spec {
+                RouteRuleSpec::FieldEquals {
+                    field,
+                    value,
+                    outlet,
+                } => Ok(Rule::FieldEquals {
+                    field: FieldPath::new(&field, schema)?,
+                    value,
+                    outlet: check_outlet(outlet)?,
+                }),
+                RouteRuleSpec::HashMod { field, outlets } => {
+                    let outlets = if outlets.is_empty() {
+                        (0..outlets_count).collect()
+                    } else {
+                        outlets
+                            .into_iter()
+                            .map(check_outlet)
+                            .collect::<Result<Vec<_>, _>>()?
+                    };
+                    Ok(Rule::HashMod {
+                        field: FieldPath::new(&field, schema)?,
+                        outlets,
+                    })
+                }
+            })
+            .collect::<Result<Vec<_>, StdStageError>>()?;
+        let default_outlet = default_outlet.map(check_outlet).transpose()?;
+
+        Ok(Self {
+            schema: schema.clone(),
+            rules,
+            default_outlet,
+        })
+    }
+
+    pub fn route(&self, datum: &[u8]) -> Result<usize, RoutingError> {
+        let data_item = avro_rs::from_avro_datum(&self.schema, &mut datum.into_buf(), None)
+            .map_err(|err| RoutingError::DecodeError(err))?;
+
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                Rule::FieldEquals {
+                    field,
+                    value,
+                    outlet,
+                } => field
+                    .get(&data_item)
+                    .filter(|field_value| eq_json(field_value, value))
+                    .map(|_| *outlet),
+                Rule::HashMod { field, outlets } if !outlets.is_empty() => {
+                    field.get(&data_item).map(|field_value| {
+                        outlets[(stable_hash(field_value) % outlets.len() as u64) as usize]
+                    })
+                }
+                Rule::HashMod { .. } => None,
+            })
+            .next()
+            .or(self.default_outlet)
+            .ok_or(RoutingError::Unroutable)
+    }
+}
+
+#[test]
+fn router_test() {
+    use crate::protocol::DataItem;
+
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "event", "fields": [
+            {"name": "kind", "type": "string"},
+            {"name": "user", "type": {"type": "record", "name": "user", "fields": [
+                {"name": "id", "type": "long"}
+            ]}}
+        ]}"#,
+    )
+    .unwrap();
+    let rules = vec![
+        RouteRuleSpec::FieldEquals {
+            field: "kind".to_owned(),
+            value: serde_json::json!("audit"),
+            outlet: 0,
+        },
+        RouteRuleSpec::HashMod {
+            field: "user.id".to_owned(),
+            outlets: vec![1, 2],
+        },
+    ];
+    let router = Router::new(&schema, 3, rules.clone(), None).unwrap();
+
+    let datum = |kind: &str, id: i64| {
+        let data_item = DataItem::Record(vec![
+            ("kind".to_owned(), DataItem::String(kind.to_owned())),
+            (
+                "user".to_owned(),
+                DataItem::Record(vec![("id".to_owned(), DataItem::Long(id))]),
+            ),
+        ]);
+        avro_rs::to_avro_datum(&schema, data_item).unwrap()
+    };
+
+    assert_eq!(router.route(&datum("audit", 1)).unwrap(), 0);
+    let hashed = router.route(&datum("click", 1)).unwrap();
+    assert!(hashed == 1 || hashed == 2);
+    assert_eq!(router.route(&datum("click", 1)).unwrap(), hashed);
+
+    assert!(Router::new(&schema, 3, rules.clone(), Some(3)).is_err());
+    assert!(Router::new(
+        &schema,
+        3,
+        vec![RouteRuleSpec::HashMod {
+            field: "user.name".to_owned(),
+            outlets: vec![],
+        }],
+        None
+    )
+    .is_err());
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod slice;
+pub use slice::Slice;
+
+mod slicer;
+use slicer::Slicer;
+
+mod slice_impl_std_stage;
+
+mod slice_fsm;
+use slice_fsm::SliceFSM;
@@ -0,0 +1,41 @@
+use crate::protocol::Schema;
+
+use super::super::rng::Rng;
+use super::*;
+
+/// Backs the `take`, `skip` and `sample` std stages.
+#[derive(Debug)]
+pub struct Slice {
+    pub schema: Schema,
+    pub slicer: Slicer,
+}
+
+impl Slice {
+    pub fn take(schema: Schema, count: usize) -> Self {
+        Self {
+            schema,
+            slicer: Slicer::Take { remaining: count },
+        }
+    }
+
+    pub fn skip(schema: Schema, count: usize) -> Self {
+        Self {
+            schema,
+            slicer: Slicer::Skip { remaining: count },
+        }
+    }
+
+    pub fn sample(schema: Schema, fraction: f64, seed: u64) -> Result<Self, StdStageError> {
+        if !(fraction >= 0.0 && fraction <= 1.0) {
+            return Err(StdStageError::InvalidParameter("fraction"));
+        }
+
+        Ok(Self {
+            schema,
+            slicer: Slicer::Sample {
+                fraction,
+                rng: Rng::new(seed),
+            },
+        })
+    }
+}
@@ -0,0 +1,254 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<SliceFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum SliceError {
+    #[fail(display = "SliceError::RxError")]
+    RxError,
+
+    #[fail(display = "SliceError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "SliceError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, SliceError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+pub enum SliceFSM {
+    ReceiveEvent {
+        slicer: Slicer,
+        /// The downstream's pending pull.
+        demand: usize,
+        rx_events: RxEventStream,
+        inlet_tx: ConsumerTx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl SliceFSM {
+    pub fn new(
+        slicer: Slicer,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_tx, outlet_rx) = outlet;
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rx);
+
+        SliceFSM::ReceiveEvent {
+            slicer,
+            demand: 0,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for SliceFSM {
+    type Item = ();
+    type Error = SliceError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            SliceFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| SliceError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(SliceFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            SliceFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| SliceError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(SliceFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            SliceFSM::ReceiveEvent {
+                slicer,
+                demand,
+                mut rx_events,
+                inlet_tx,
+                outlet_tx,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(SliceFSM::ReceiveEvent {
+                    slicer,
+                    demand,
+                    rx_events,
+                    inlet_tx,
+                    outlet_tx,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => {
+                    handle_rx_event(event, slicer, demand, rx_events, inlet_tx, outlet_tx)
+                }
+            }),
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    mut slicer: Slicer,
+    demand: usize,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<SliceFSM> {
+    match event {
+        Event::ConsumerMessage(ConsumerMessage::Pull { .. }) if slicer.is_done() => {
+            complete_early(vec![], inlet_tx, outlet_tx)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => {
+            pull_upstream(slicer, max_items, rx_events, inlet_tx, outlet_tx)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => {
+            complete_early(vec![], inlet_tx, outlet_tx)
+        }
+
+        Event::ProducerMessage(ProducerMessage::Push { items }) => {
+            let items = slicer.slice(items);
+
+            if slicer.is_done() {
+                complete_early(items, inlet_tx, outlet_tx)
+            } else if items.is_empty() && demand > 0 {
+                // all of the pushed items have been dropped: the downstream still awaits its items
+                pull_upstream(slicer, demand, rx_events, inlet_tx, outlet_tx)
+            } else {
+                let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+                let into_receiving_events = move |outlet_tx| {
+                    Ok(TurnOk::PollMore(SliceFSM::ReceiveEvent {
+                        slicer,
+                        demand: 0,
+                        rx_events,
+                        inlet_tx,
+                        outlet_tx,
+                    }))
+                };
+                Ok(TurnOk::PollMore(SliceFSM::SendingToDownstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(into_receiving_events),
+                }))
+            }
+        }
+
+        Event::ProducerMessage(bye_message) => {
+            let sent = Box::new(outlet_tx.send(bye_message));
+            let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+            Ok(TurnOk::PollMore(SliceFSM::SendingToDownstream {
+                sent,
+                and_then: SendBoxFnOnce::from(shutdown),
+            }))
+        }
+    }
+}
+
+fn pull_upstream(
+    slicer: Slicer,
+    max_items: usize,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<SliceFSM> {
+    let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull {
+        max_items: slicer.pull_size(max_items),
+    }));
+    let into_receiving_events = move |inlet_tx| {
+        Ok(TurnOk::PollMore(SliceFSM::ReceiveEvent {
+            slicer,
+            demand: max_items,
+            rx_events,
+            inlet_tx,
+            outlet_tx,
+        }))
+    };
+    Ok(TurnOk::PollMore(SliceFSM::SendingToUpstream {
+        sent,
+        and_then: SendBoxFnOnce::from(into_receiving_events),
+    }))
+}
+
+/// Cancels the upstream, then pushes the last `items` (if any) and completes the downstream.
+fn complete_early(
+    items: Vec<Vec<u8>>,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<SliceFSM> {
+    let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+    let and_then = move |_inlet_tx| {
+        let sent: DownstreamSend = if items.is_empty() {
+            Box::new(outlet_tx.send(ProducerMessage::Complete))
+        } else {
+            Box::new(
+                outlet_tx
+                    .send(ProducerMessage::Push { items })
+                    .and_then(|outlet_tx| outlet_tx.send(ProducerMessage::Complete)),
+            )
+        };
+        let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+        Ok(TurnOk::PollMore(SliceFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(shutdown),
+        }))
+    };
+    Ok(TurnOk::PollMore(SliceFSM::SendingToUpstream {
+        sent,
+        and_then: SendBoxFnOnce::from(and_then),
+    }))
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, SliceError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| SliceError::RxError);
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| SliceError::RxError);
+
+    Box::new(inlet_rx_events.select(outlet_rx_events))
+}
@@ -0,0 +1,79 @@
+use super::super::rng::Rng;
+
+#[derive(Debug, Clone)]
+pub enum Slicer {
+    /// Passes the items through until `remaining` drops to zero.
+    Take { remaining: usize },
+    /// Drops the items until `remaining` drops to zero.
+    Skip { remaining: usize },
+    /// Passes each item through with the probability `fraction`.
+    Sample { fraction: f64, rng: Rng },
+}
+
+impl Slicer {
+    /// How many items to pull upstream to fulfil a downstream's pull of `max_items`:
+    /// no more than `take` needs, and enough for `skip` to get past the skipped items at once.
+    pub fn pull_size(&self, max_items: usize) -> usize {
+        match *self {
+            Slicer::Take { remaining } => max_items.min(remaining),
+            Slicer::Skip { remaining } => max_items.saturating_add(remaining),
+            Slicer::Sample { .. } => max_items,
+        }
+    }
+
+    pub fn slice(&mut self, mut items: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        match self {
+            Slicer::Take { remaining } => {
+                items.truncate(*remaining);
+                *remaining -= items.len();
+                items
+            }
+            Slicer::Skip { remaining } => {
+                let skipped = items.len().min(*remaining);
+                *remaining -= skipped;
+                items.split_off(skipped)
+            }
+            Slicer::Sample { fraction, rng } => {
+                let fraction = *fraction;
+                items.retain(|_| rng.next_f64() < fraction);
+                items
+            }
+        }
+    }
+
+    /// Whether no more items would pass through.
+    pub fn is_done(&self) -> bool {
+        match *self {
+            Slicer::Take { remaining } => remaining == 0,
+            _ => false,
+        }
+    }
+}
+
+#[test]
+fn slicer_test() {
+    let items = |range: std::ops::Range<u8>| range.map(|i| vec![i]).collect::<Vec<_>>();
+
+    let mut take = Slicer::Take { remaining: 3 };
+    assert_eq!(take.pull_size(10), 3);
+    assert_eq!(take.slice(items(0..2)), items(0..2));
+    assert!(!take.is_done());
+    assert_eq!(take.slice(items(2..4)), items(2..3));
+    assert!(take.is_done());
+
+    let mut skip = Slicer::Skip { remaining: 3 };
+    assert_eq!(skip.pull_size(2), 5);
+    assert_eq!(skip.slice(items(0..2)), items(0..0));
+    assert_eq!(skip.slice(items(2..5)), items(3..5));
+    assert_eq!(skip.pull_size(2), 2);
+
+    let sample = |fraction, seed| Slicer::Sample {
+        fraction,
+        rng: Rng::new(seed),
+    };
+    assert_eq!(sample(0.0, 1).slice(items(0..100)), items(0..0));
+    assert_eq!(sample(1.0, 1).slice(items(0..100)), items(0..100));
+    let sampled = sample(0.5, 42).slice(items(0..200));
+    assert!(sampled.len() > 50 && sampled.len() < 150);
+    assert_eq!(sample(0.5, 42).slice(items(0..200)), sampled);
+}
@@ -1,5 +1,6 @@
 use futures::sync::mpsc;
 use std::fmt;
+use std::time::Duration;
 
 use crate::futures::SendBoxedFuture;
 use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
@@ -71,12 +72,209 @@ pub fn from_spec(spec: StdStageSpec) -> Result<BoxedStdStage, StdStageError> {
             inlets_count,
             eagerly_complete,
             eagerly_fail,
+            mode,
         } => Ok(Box::new(Merge::new(
             parse_schema(schema)?,
             inlets_count,
             eagerly_complete,
             eagerly_fail,
+            mode,
+        )?)),
+
+        StdStageSpec::MergeSorted {
+            schema,
+            inlets_count,
+            key,
+            eagerly_complete,
+            eagerly_fail,
+        } => Ok(Box::new(MergeSorted::new(
+            parse_schema(schema)?,
+            inlets_count,
+            &key,
+            eagerly_complete,
+            eagerly_fail,
+        )?)),
+
+        StdStageSpec::Concat {
+            schema,
+            inlets_count,
+            eagerly_fail,
+        } => Ok(Box::new(Concat::new(
+            parse_schema(schema)?,
+            inlets_count,
+            eagerly_fail,
+        ))),
+
+        StdStageSpec::Route {
+            schema,
+            outlets_count,
+            rules,
+            default_outlet,
+        } => Ok(Box::new(Route::new(
+            parse_schema(schema)?,
+            outlets_count,
+            rules,
+            default_outlet,
+        )?)),
+
+        StdStageSpec::Balance {
+            schema,
+            outlets_count,
+            mode,
+            on_outlet_cancel,
+            on_outlet_failure,
+        } => Ok(Box::new(Balance::new(
+            parse_schema(schema)?,
+            outlets_count,
+            mode,
+            on_outlet_cancel,
+            on_outlet_failure,
+        ))),
+
+        StdStageSpec::Zip {
+            schemas,
+            fields,
+            record_name,
+            eagerly_complete,
+            eagerly_fail,
+        } => Ok(Box::new(Zip::new(
+            schemas
+                .into_iter()
+                .map(parse_schema)
+                .collect::<Result<Vec<_>, _>>()?,
+            fields,
+            &record_name,
+            eagerly_complete,
+            eagerly_fail,
+        )?)),
+
+        StdStageSpec::Batch {
+            schema,
+            max_size,
+            max_latency_ms,
+        } => Ok(Box::new(Batch::new(
+            parse_schema(schema)?,
+            max_size,
+            max_latency_ms.map(Duration::from_millis),
+        )?)),
+
+        StdStageSpec::Throttle {
+            schema,
+            items_per_second,
+            burst,
+        } => Ok(Box::new(Throttle::new(
+            parse_schema(schema)?,
+            items_per_second,
+            burst,
+        )?)),
+
+        StdStageSpec::Buffer {
+            schema,
+            capacity,
+            overflow,
+        } => Ok(Box::new(Buffer::new(
+            parse_schema(schema)?,
+            capacity,
+            overflow,
+        )?)),
+
+        StdStageSpec::AvroFileSource { schema, path } => {
+            Ok(Box::new(AvroFileSource::new(parse_schema(schema)?, path)))
+        }
+
+        StdStageSpec::AvroFileSink {
+            schema,
+            path,
+            codec,
+            sync_interval,
+        } => Ok(Box::new(AvroFileSink::new(
+            parse_schema(schema)?,
+            path,
+            codec,
+            sync_interval,
+        ))),
+
+        StdStageSpec::Take { schema, count } => {
+            Ok(Box::new(Slice::take(parse_schema(schema)?, count)))
+        }
+
+        StdStageSpec::Skip { schema, count } => {
+            Ok(Box::new(Slice::skip(parse_schema(schema)?, count)))
+        }
+
+        StdStageSpec::Sample {
+            schema,
+            fraction,
+            seed,
+        } => Ok(Box::new(Slice::sample(
+            parse_schema(schema)?,
+            fraction,
+            seed,
+        )?)),
+
+        StdStageSpec::Dedup {
+            schema,
+            keys,
+            window,
+        } => Ok(Box::new(Dedup::new(parse_schema(schema)?, keys, window)?)),
+
+        StdStageSpec::Project {
+            schema,
+            record_name,
+            fields,
+            keep_others,
+            drop,
+        } => Ok(Box::new(Project::new(
+            parse_schema(schema)?,
+            &record_name,
+            fields,
+            keep_others,
+            drop,
+        )?)),
+
+        StdStageSpec::Generate {
+            schema,
+            seed,
+            count,
+            duration_ms,
+            items_per_second,
+            overrides,
+        } => Ok(Box::new(Generate::new(
+            parse_schema(schema)?,
+            seed,
+            count,
+            duration_ms.map(Duration::from_millis),
+            items_per_second,
+            overrides,
+        )?)),
+
+        StdStageSpec::Count {
+            schema,
+            report_interval_ms,
+            expected_min,
+            expected_max,
+        } => Ok(Box::new(Count::new(
+            parse_schema(schema)?,
+            Duration::from_millis(report_interval_ms),
+            expected_min,
+            expected_max,
+        )?)),
+
+        StdStageSpec::JsonlSource {
+            schema,
+            path,
+            on_malformed,
+        } => Ok(Box::new(JsonlSource::new(
+            parse_schema(schema)?,
+            path,
+            on_malformed,
         ))),
+
+        StdStageSpec::JsonlSink { schema, path } => {
+            Ok(Box::new(JsonlSink::new(parse_schema(schema)?, path)))
+        }
+
+        StdStageSpec::Custom { name, config } => registry::create(&name, config),
     }
 }
 
@@ -3,6 +3,39 @@ pub enum StdStageError {
     #[fail(display = "StdStageError::SchemaParseError")]
     SchemaParseError(#[cause] failure::Error),
 
+    #[fail(display = "StdStageError::UnknownField: {}", _0)]
+    UnknownField(String),
+
+    #[fail(
+        display = "StdStageError::OutletOutOfRange [outlet: {}; outlets-count: {}]",
+        outlet, outlets_count
+    )]
+    OutletOutOfRange { outlet: usize, outlets_count: usize },
+
+    #[fail(
+        display = "StdStageError::FieldsCountMismatch [fields-count: {}; inlets-count: {}]",
+        fields_count, inlets_count
+    )]
+    FieldsCountMismatch {
+        fields_count: usize,
+        inlets_count: usize,
+    },
+
+    #[fail(display = "StdStageError::InvalidParameter: {}", _0)]
+    InvalidParameter(&'static str),
+
+    #[fail(display = "StdStageError::InvalidFieldDefault: {}", _0)]
+    InvalidFieldDefault(String),
+
+    #[fail(display = "StdStageError::UnknownCustomStage: {}", _0)]
+    UnknownCustomStage(String),
+
+    #[fail(display = "StdStageError::DuplicateCustomStage: {}", _0)]
+    DuplicateCustomStage(String),
+
+    #[fail(display = "StdStageError::ConfigError")]
+    ConfigError(#[cause] failure::Error),
+
     #[fail(display = "StdStageError::Generic")]
     Generic(#[cause] failure::Error),
 }
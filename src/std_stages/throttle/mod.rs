@@ -0,0 +1,12 @@
+use super::*;
+
+mod throttle;
+pub use throttle::Throttle;
+
+mod token_bucket;
+use token_bucket::TokenBucket;
+
+mod throttle_impl_std_stage;
+
+mod throttle_fsm;
+use throttle_fsm::ThrottleFSM;
@@ -0,0 +1,27 @@
+use crate::protocol::Schema;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct Throttle {
+    pub schema: Schema,
+    pub items_per_second: f64,
+    pub burst: usize,
+}
+
+impl Throttle {
+    pub fn new(schema: Schema, items_per_second: f64, burst: usize) -> Result<Self, StdStageError> {
+        if !(items_per_second.is_finite() && items_per_second > 0.0) {
+            return Err(StdStageError::InvalidParameter("items_per_second"));
+        }
+        if burst == 0 {
+            return Err(StdStageError::InvalidParameter("burst"));
+        }
+
+        Ok(Self {
+            schema,
+            items_per_second,
+            burst,
+        })
+    }
+}
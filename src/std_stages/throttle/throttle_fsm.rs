@@ -0,0 +1,298 @@
+use std::time::Instant;
+
+use boxfnonce::SendBoxFnOnce;
+use futures::prelude::*;
+use futures::sync::mpsc;
+use tokio::timer::Delay;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<ThrottleFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(ProducerMessage),
+    TokenAvailable,
+}
+
+#[derive(Fail, Debug)]
+pub enum ThrottleError {
+    #[fail(display = "ThrottleError::RxError")]
+    RxError,
+
+    #[fail(display = "ThrottleError::TimerError")]
+    TimerError(#[cause] tokio::timer::Error),
+
+    #[fail(display = "ThrottleError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "ThrottleError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, ThrottleError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSend = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+
+#[derive(Debug, Clone)]
+pub enum DemandState {
+    /// Nothing is pulled.
+    Idle,
+    /// The downstream has pulled, the upstream is not pulled yet for the lack of tokens.
+    Throttled(usize),
+    /// The upstream has been pulled for that many items.
+    Pulled(usize),
+}
+
+pub enum ThrottleFSM {
+    ReceiveEvent {
+        token_bucket: TokenBucket,
+        demand_state: DemandState,
+        rx_events: RxEventStream,
+        delay: Option<Delay>,
+        inlet_tx: ConsumerTx,
+        outlet_tx: ProducerTx,
+    },
+    SendingToUpstream {
+        sent: UpstreamSend,
+        and_then: Continue<(ConsumerTx,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl ThrottleFSM {
+    pub fn new(
+        token_bucket: TokenBucket,
+        inlet: (ConsumerTx, ConsumerRx),
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (inlet_tx, inlet_rx) = inlet;
+        let (outlet_tx, outlet_rx) = outlet;
+
+        let rx_events = rxs_into_event_stream(inlet_rx, outlet_rx);
+
+        ThrottleFSM::ReceiveEvent {
+            token_bucket,
+            demand_state: DemandState::Idle,
+            rx_events,
+            delay: None,
+            inlet_tx,
+            outlet_tx,
+        }
+    }
+}
+
+impl FSM for ThrottleFSM {
+    type Item = ();
+    type Error = ThrottleError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            ThrottleFSM::SendingToUpstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| ThrottleError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ThrottleFSM::SendingToUpstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(inlet_tx) => and_then.call(inlet_tx),
+                }),
+
+            ThrottleFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| ThrottleError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ThrottleFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            ThrottleFSM::ReceiveEvent {
+                token_bucket,
+                demand_state,
+                mut rx_events,
+                mut delay,
+                inlet_tx,
+                outlet_tx,
+            } => {
+                let token_available = match delay.as_mut() {
+                    Some(delay) => delay
+                        .poll()
+                        .map_err(|err| ThrottleError::TimerError(err))?
+                        .is_ready(),
+                    None => false,
+                };
+                if token_available {
+                    return handle_rx_event(
+                        Event::TokenAvailable,
+                        token_bucket,
+                        demand_state,
+                        rx_events,
+                        inlet_tx,
+                        outlet_tx,
+                    );
+                }
+
+                rx_events.poll().and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ThrottleFSM::ReceiveEvent {
+                        token_bucket,
+                        demand_state,
+                        rx_events,
+                        delay,
+                        inlet_tx,
+                        outlet_tx,
+                    })),
+
+                    Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                    Async::Ready(Some(event)) => handle_rx_event(
+                        event,
+                        token_bucket,
+                        demand_state,
+                        rx_events,
+                        inlet_tx,
+                        outlet_tx,
+                    ),
+                })
+            }
+        }
+    }
+}
+
+/// A pending delay is dropped on every event: `pull_upstream` sets up a new one if still needed.
+fn handle_rx_event(
+    event: Event,
+    mut token_bucket: TokenBucket,
+    demand_state: DemandState,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<ThrottleFSM> {
+    match (event, demand_state) {
+        (Event::ConsumerMessage(ConsumerMessage::Pull { max_items }), _)
+        | (Event::TokenAvailable, DemandState::Throttled(max_items)) => {
+            pull_upstream(max_items, token_bucket, rx_events, inlet_tx, outlet_tx)
+        }
+
+        (Event::TokenAvailable, demand_state) => Ok(TurnOk::PollMore(ThrottleFSM::ReceiveEvent {
+            token_bucket,
+            demand_state,
+            rx_events,
+            delay: None,
+            inlet_tx,
+            outlet_tx,
+        })),
+
+        (Event::ConsumerMessage(ConsumerMessage::Cancel), _) => {
+            let sent = Box::new(inlet_tx.send(ConsumerMessage::Cancel));
+            let complete_downstream = move |_inlet_tx| {
+                let sent = Box::new(outlet_tx.send(ProducerMessage::Complete));
+                let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+                Ok(TurnOk::PollMore(ThrottleFSM::SendingToDownstream {
+                    sent,
+                    and_then: SendBoxFnOnce::from(shutdown),
+                }))
+            };
+            Ok(TurnOk::PollMore(ThrottleFSM::SendingToUpstream {
+                sent,
+                and_then: SendBoxFnOnce::from(complete_downstream),
+            }))
+        }
+
+        (Event::ProducerMessage(ProducerMessage::Push { items }), demand_state) => {
+            if let DemandState::Pulled(max_items) = demand_state {
+                token_bucket.refund(max_items.saturating_sub(items.len()));
+            }
+
+            let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+            let into_receiving_events = move |outlet_tx| {
+                Ok(TurnOk::PollMore(ThrottleFSM::ReceiveEvent {
+                    token_bucket,
+                    demand_state: DemandState::Idle,
+                    rx_events,
+                    delay: None,
+                    inlet_tx,
+                    outlet_tx,
+                }))
+            };
+            Ok(TurnOk::PollMore(ThrottleFSM::SendingToDownstream {
+                sent,
+                and_then: SendBoxFnOnce::from(into_receiving_events),
+            }))
+        }
+
+        (Event::ProducerMessage(bye_message), _) => {
+            let sent = Box::new(outlet_tx.send(bye_message));
+            let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+            Ok(TurnOk::PollMore(ThrottleFSM::SendingToDownstream {
+                sent,
+                and_then: SendBoxFnOnce::from(shutdown),
+            }))
+        }
+    }
+}
+
+/// Pulls as many items as there are tokens for, or waits for the next token.
+fn pull_upstream(
+    max_items: usize,
+    mut token_bucket: TokenBucket,
+    rx_events: RxEventStream,
+    inlet_tx: ConsumerTx,
+    outlet_tx: ProducerTx,
+) -> TurnResult<ThrottleFSM> {
+    match token_bucket.take(max_items, Instant::now()) {
+        Ok(max_items) => {
+            let sent = Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items }));
+            let into_receiving_events = move |inlet_tx| {
+                Ok(TurnOk::PollMore(ThrottleFSM::ReceiveEvent {
+                    token_bucket,
+                    demand_state: DemandState::Pulled(max_items),
+                    rx_events,
+                    delay: None,
+                    inlet_tx,
+                    outlet_tx,
+                }))
+            };
+            Ok(TurnOk::PollMore(ThrottleFSM::SendingToUpstream {
+                sent,
+                and_then: SendBoxFnOnce::from(into_receiving_events),
+            }))
+        }
+
+        Err(next_token_at) => Ok(TurnOk::PollMore(ThrottleFSM::ReceiveEvent {
+            token_bucket,
+            demand_state: DemandState::Throttled(max_items),
+            rx_events,
+            delay: Some(Delay::new(next_token_at)),
+            inlet_tx,
+            outlet_tx,
+        })),
+    }
+}
+
+fn rxs_into_event_stream(
+    inlet_rx: ConsumerRx,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, ThrottleError> {
+    let inlet_rx_events = inlet_rx
+        .map(|producer_message| Event::ProducerMessage(producer_message))
+        .map_err(|()| ThrottleError::RxError);
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| ThrottleError::RxError);
+
+    Box::new(inlet_rx_events.select(outlet_rx_events))
+}
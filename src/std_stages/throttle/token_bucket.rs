@@ -0,0 +1,63 @@
+use std::time::{Duration, Instant};
+
+/// Holds up to `burst` tokens, refilled at `rate` tokens per second; an item pulled takes a token.
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, burst: usize, now: Instant) -> Self {
+        Self {
+            rate,
+            burst: burst as f64,
+            tokens: burst as f64,
+            refilled_at: now,
+        }
+    }
+
+    /// Takes up to `max_items` tokens; `Err` tells when the next token is available.
+    pub fn take(&mut self, max_items: usize, now: Instant) -> Result<usize, Instant> {
+        self.refill(now);
+
+        let available = self.tokens.floor() as usize;
+        if available > 0 {
+            let taken = std::cmp::min(available, max_items);
+            self.tokens -= taken as f64;
+            Ok(taken)
+        } else {
+            let wait = (1.0 - self.tokens) / self.rate;
+            Err(now + Duration::from_micros((wait * 1_000_000.0).ceil() as u64))
+        }
+    }
+
+    /// Returns the tokens taken for the items that the upstream did not push.
+    pub fn refund(&mut self, tokens: usize) {
+        self.tokens = (self.tokens + tokens as f64).min(self.burst);
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled_at);
+        let elapsed_secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+        self.tokens = (self.tokens + elapsed_secs * self.rate).min(self.burst);
+        self.refilled_at = now;
+    }
+}
+
+#[test]
+fn token_bucket_test() {
+    let t0 = Instant::now();
+    let mut bucket = TokenBucket::new(10.0, 3, t0);
+
+    assert_eq!(bucket.take(5, t0), Ok(3));
+    assert_eq!(bucket.take(5, t0), Err(t0 + Duration::from_millis(100)));
+
+    let t1 = t0 + Duration::from_millis(250);
+    assert_eq!(bucket.take(5, t1), Ok(2));
+
+    bucket.refund(10);
+    assert_eq!(bucket.take(5, t1), Ok(3));
+}
@@ -0,0 +1,12 @@
+use super::*;
+
+mod zip;
+pub use zip::Zip;
+
+mod zipper;
+use zipper::Zipper;
+
+mod zip_impl_std_stage;
+
+mod zip_fsm;
+use zip_fsm::ZipFSM;
@@ -0,0 +1,69 @@
+use serde_json::json;
+
+use crate::protocol::Schema;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct Zip {
+    pub inlet_schemas: Vec<Schema>,
+    pub outlet_schema: Schema,
+    pub eagerly_complete: bool,
+    pub eagerly_fail: bool,
+}
+
+impl Zip {
+    pub fn new(
+        inlet_schemas: Vec<Schema>,
+        fields: Vec<String>,
+        record_name: &str,
+        eagerly_complete: bool,
+        eagerly_fail: bool,
+    ) -> Result<Self, StdStageError> {
+        let fields = if fields.is_empty() {
+            (0..inlet_schemas.len())
+                .map(|idx| format!("_{}", idx))
+                .collect()
+        } else if fields.len() == inlet_schemas.len() {
+            fields
+        } else {
+            return Err(StdStageError::FieldsCountMismatch {
+                fields_count: fields.len(),
+                inlets_count: inlet_schemas.len(),
+            });
+        };
+
+        let outlet_schema = outlet_schema(&inlet_schemas, &fields, record_name)?;
+
+        Ok(Self {
+            inlet_schemas,
+            outlet_schema,
+            eagerly_complete,
+            eagerly_fail,
+        })
+    }
+}
+
+/// Goes through JSON so that the named types shared by several inlets get reported as redefined.
+fn outlet_schema(
+    inlet_schemas: &[Schema],
+    fields: &[String],
+    record_name: &str,
+) -> Result<Schema, StdStageError> {
+    let fields_json = fields
+        .iter()
+        .zip(inlet_schemas)
+        .map(|(field, schema)| {
+            serde_json::to_value(schema)
+                .map(|schema_json| json!({"name": field, "type": schema_json}))
+                .map_err(|err| StdStageError::SchemaParseError(err.into()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Schema::parse(&json!({
+        "type": "record",
+        "name": record_name,
+        "fields": fields_json,
+    }))
+    .map_err(|err| StdStageError::SchemaParseError(err))
+}
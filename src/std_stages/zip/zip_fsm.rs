@@ -0,0 +1,296 @@
+use boxfnonce::SendBoxFnOnce;
+use futures::future;
+use futures::prelude::*;
+use futures::sync::mpsc;
+
+use crate::futures::fsm::*;
+use crate::futures::{SendBoxedFuture, SendBoxedStream};
+use crate::protocol::messages::{ConsumerMessage, ProducerMessage};
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+
+use super::zipper::UpstreamState;
+use super::*;
+
+type Continue<Args> = SendBoxFnOnce<'static, Args, TurnResult<ZipFSM>>;
+
+pub enum Event {
+    ConsumerMessage(ConsumerMessage),
+    ProducerMessage(usize, ProducerMessage),
+}
+
+#[derive(Fail, Debug)]
+pub enum ZipError {
+    #[fail(display = "ZipError::RxError")]
+    RxError,
+
+    #[fail(display = "ZipError::InletTxError")]
+    InletTxError(#[cause] mpsc::SendError<ConsumerMessage>),
+
+    #[fail(display = "ZipError::OutletTxError")]
+    OutletTxError(#[cause] mpsc::SendError<ProducerMessage>),
+}
+
+pub type RxEventStream = SendBoxedStream<Event, ZipError>;
+
+pub type DownstreamSend = SendBoxedFuture<ProducerTx, mpsc::SendError<ProducerMessage>>;
+pub type UpstreamSendSingle = SendBoxedFuture<ConsumerTx, mpsc::SendError<ConsumerMessage>>;
+pub type UpstreamsSend = SendBoxedFuture<Vec<ConsumerTx>, mpsc::SendError<ConsumerMessage>>;
+
+pub enum ZipFSM {
+    ReceiveEvent {
+        zipper: Zipper,
+        rx_events: RxEventStream,
+        outlet_tx: ProducerTx,
+        inlet_txs: Vec<ConsumerTx>,
+    },
+    SendingToUpstreams {
+        sents: UpstreamsSend,
+        and_then: Continue<(Vec<ConsumerTx>,)>,
+    },
+    SendingToDownstream {
+        sent: DownstreamSend,
+        and_then: Continue<(ProducerTx,)>,
+    },
+}
+
+impl ZipFSM {
+    pub fn new(
+        zipper: Zipper,
+        inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        outlet: (ProducerTx, ProducerRx),
+    ) -> Self {
+        let (outlet_tx, outlet_rx) = outlet;
+        let (inlet_txs, inlet_rxs): (Vec<_>, Vec<_>) = inlets.into_iter().unzip();
+
+        let rx_events = rxs_into_event_stream(inlet_rxs, outlet_rx);
+
+        ZipFSM::ReceiveEvent {
+            zipper,
+            rx_events,
+            outlet_tx,
+            inlet_txs,
+        }
+    }
+}
+
+impl FSM for ZipFSM {
+    type Item = ();
+    type Error = ZipError;
+
+    fn turn(self) -> TurnResult<Self> {
+        match self {
+            ZipFSM::SendingToUpstreams {
+                mut sents,
+                and_then,
+            } => sents
+                .poll()
+                .map_err(|err| ZipError::InletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ZipFSM::SendingToUpstreams {
+                        sents,
+                        and_then,
+                    })),
+
+                    Async::Ready(inlet_txs) => and_then.call(inlet_txs),
+                }),
+
+            ZipFSM::SendingToDownstream { mut sent, and_then } => sent
+                .poll()
+                .map_err(|err| ZipError::OutletTxError(err))
+                .and_then(|poll| match poll {
+                    Async::NotReady => Ok(TurnOk::Suspend(ZipFSM::SendingToDownstream {
+                        sent,
+                        and_then,
+                    })),
+
+                    Async::Ready(outlet_tx) => and_then.call(outlet_tx),
+                }),
+
+            ZipFSM::ReceiveEvent {
+                zipper,
+                mut rx_events,
+                outlet_tx,
+                inlet_txs,
+            } => rx_events.poll().and_then(|poll| match poll {
+                Async::NotReady => Ok(TurnOk::Suspend(ZipFSM::ReceiveEvent {
+                    zipper,
+                    rx_events,
+                    outlet_tx,
+                    inlet_txs,
+                })),
+
+                Async::Ready(None) => Ok(TurnOk::Ready(())),
+
+                Async::Ready(Some(event)) => {
+                    handle_rx_event(event, zipper, rx_events, outlet_tx, inlet_txs)
+                }
+            }),
+        }
+    }
+}
+
+fn handle_rx_event(
+    event: Event,
+    mut zipper: Zipper,
+    rx_events: RxEventStream,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<ZipFSM> {
+    match event {
+        Event::ConsumerMessage(ConsumerMessage::Pull { max_items }) => {
+            zipper.pulled(max_items);
+            proceed(zipper, rx_events, outlet_tx, inlet_txs)
+        }
+
+        Event::ConsumerMessage(ConsumerMessage::Cancel) => shutdown(
+            zipper.upstream_states,
+            ProducerMessage::Complete,
+            outlet_tx,
+            inlet_txs,
+        ),
+
+        Event::ProducerMessage(producer_idx, ProducerMessage::Push { items }) => {
+            zipper.pushed(producer_idx, items);
+            proceed(zipper, rx_events, outlet_tx, inlet_txs)
+        }
+
+        Event::ProducerMessage(producer_idx, ProducerMessage::Complete) => {
+            zipper.completed(producer_idx);
+            proceed(zipper, rx_events, outlet_tx, inlet_txs)
+        }
+
+        Event::ProducerMessage(producer_idx, ProducerMessage::Fail { failure }) => {
+            zipper.failed(producer_idx, failure.clone());
+            if zipper.eagerly_fail {
+                shutdown(
+                    zipper.upstream_states,
+                    ProducerMessage::Fail { failure },
+                    outlet_tx,
+                    inlet_txs,
+                )
+            } else {
+                proceed(zipper, rx_events, outlet_tx, inlet_txs)
+            }
+        }
+    }
+}
+
+/// Pushes the records made up so far, pulls the inlets to make up more,
+/// or shuts down once there will be none.
+fn proceed(
+    mut zipper: Zipper,
+    rx_events: RxEventStream,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<ZipFSM> {
+    if let Some(items) = zipper.next_push() {
+        let sent = Box::new(outlet_tx.send(ProducerMessage::Push { items }));
+        let into_proceeding = move |outlet_tx| proceed(zipper, rx_events, outlet_tx, inlet_txs);
+
+        Ok(TurnOk::PollMore(ZipFSM::SendingToDownstream {
+            sent,
+            and_then: SendBoxFnOnce::from(into_proceeding),
+        }))
+    } else if zipper.is_exhausted() && (zipper.eagerly_complete || zipper.is_finished()) {
+        let downstream_bye_message = match zipper.failure.take() {
+            Some(failure) => ProducerMessage::Fail { failure },
+            None => ProducerMessage::Complete,
+        };
+        shutdown(
+            zipper.upstream_states,
+            downstream_bye_message,
+            outlet_tx,
+            inlet_txs,
+        )
+    } else {
+        let pulls = zipper.pulls();
+        if pulls.iter().all(Option::is_none) {
+            return Ok(TurnOk::PollMore(ZipFSM::ReceiveEvent {
+                zipper,
+                rx_events,
+                outlet_tx,
+                inlet_txs,
+            }));
+        }
+
+        let upstream_pull_sents = pulls
+            .into_iter()
+            .zip(inlet_txs.into_iter())
+            .map::<UpstreamSendSingle, _>(|(pull, inlet_tx)| match pull {
+                Some(max_items) => Box::new(inlet_tx.send(ConsumerMessage::Pull { max_items })),
+                None => Box::new(future::ok(inlet_tx)),
+            });
+        let into_receiving_events = move |inlet_txs| {
+            Ok(TurnOk::PollMore(ZipFSM::ReceiveEvent {
+                zipper,
+                rx_events,
+                outlet_tx,
+                inlet_txs,
+            }))
+        };
+
+        Ok(TurnOk::PollMore(ZipFSM::SendingToUpstreams {
+            sents: Box::new(future::join_all(upstream_pull_sents)),
+            and_then: SendBoxFnOnce::from(into_receiving_events),
+        }))
+    }
+}
+
+fn shutdown(
+    upstream_states: Vec<UpstreamState>,
+    downstream_bye_message: ProducerMessage,
+    outlet_tx: ProducerTx,
+    inlet_txs: Vec<ConsumerTx>,
+) -> TurnResult<ZipFSM> {
+    assert!(upstream_states.len() == inlet_txs.len());
+
+    let upstream_cancel_sents =
+        upstream_states
+            .into_iter()
+            .zip(inlet_txs.into_iter())
+            .map::<UpstreamSendSingle, _>(|(upstream_state, inlet_tx)| match upstream_state {
+                UpstreamState::Idle | UpstreamState::Pulled => {
+                    Box::new(inlet_tx.send(ConsumerMessage::Cancel))
+                }
+                UpstreamState::Complete | UpstreamState::Failed => Box::new(future::ok(inlet_tx)),
+            });
+
+    let send_downstream_termination = move |_inlet_txs| {
+        let downstream_terminate_sent = Box::new(outlet_tx.send(downstream_bye_message));
+        let shutdown = |_outlet_tx| Ok(TurnOk::Ready(()));
+
+        Ok(TurnOk::PollMore(ZipFSM::SendingToDownstream {
+            sent: downstream_terminate_sent,
+            and_then: SendBoxFnOnce::from(shutdown),
+        }))
+    };
+
+    Ok(TurnOk::PollMore(ZipFSM::SendingToUpstreams {
+        sents: Box::new(future::join_all(upstream_cancel_sents)),
+        and_then: SendBoxFnOnce::from(send_downstream_termination),
+    }))
+}
+
+fn rxs_into_event_stream(
+    inlet_rxs: Vec<ConsumerRx>,
+    outlet_rx: ProducerRx,
+) -> SendBoxedStream<Event, ZipError> {
+    let outlet_rx_events = outlet_rx
+        .map(|consumer_message| Event::ConsumerMessage(consumer_message))
+        .map_err(|()| ZipError::RxError);
+
+    let inlet_rx_events = inlet_rxs
+        .into_iter()
+        .enumerate()
+        .map(move |(inlet_idx, inlet_rx)| {
+            inlet_rx
+                .map(move |producer_message| Event::ProducerMessage(inlet_idx, producer_message))
+                .map_err(|()| ZipError::RxError)
+        });
+
+    inlet_rx_events
+        .fold::<SendBoxedStream<Event, ZipError>, _>(Box::new(outlet_rx_events), |acc, inlet_rx| {
+            Box::new(acc.select(inlet_rx))
+        })
+}
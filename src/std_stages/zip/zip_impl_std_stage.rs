@@ -0,0 +1,36 @@
+use futures::prelude::*;
+
+use crate::futures::fsm::FSM;
+
+use crate::protocol::streams::{ConsumerRx, ConsumerTx};
+use crate::protocol::streams::{ProducerRx, ProducerTx};
+use crate::protocol::Schema;
+
+use super::*;
+
+impl StdStage for Zip {
+    fn inlet_schemas(&self) -> Vec<&Schema> {
+        self.inlet_schemas.iter().collect()
+    }
+
+    fn outlet_schemas(&self) -> Vec<&Schema> {
+        vec![&self.outlet_schema]
+    }
+
+    fn run_with_channels(
+        self: Box<Self>,
+        inlets: Vec<(ConsumerTx, ConsumerRx)>,
+        mut outlets: Vec<(ProducerTx, ProducerRx)>,
+    ) -> RunningFuture {
+        assert!(outlets.len() == 1);
+        let outlet = outlets.pop().unwrap();
+
+        let zipper = Zipper::new(inlets.len(), self.eagerly_complete, self.eagerly_fail);
+
+        Box::new(
+            ZipFSM::new(zipper, inlets, outlet)
+                .into_fsm_future()
+                .map_err(|err| StdStageError::Generic(err.into())),
+        )
+    }
+}
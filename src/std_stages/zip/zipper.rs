@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+use crate::protocol::command::Failure as PortFailure;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamState {
+    Idle,
+    Pulled,
+    Complete,
+    Failed,
+}
+
+/// Lines up the items received from the inlets.
+///
+/// The Avro binary encoding of a record is the concatenation of its fields' encodings,
+/// hence a zipped datum is made of the inlets' datums as they are.
+#[derive(Debug)]
+pub struct Zipper {
+    pub eagerly_complete: bool,
+    pub eagerly_fail: bool,
+    pub upstream_states: Vec<UpstreamState>,
+    pub failure: Option<PortFailure>,
+    buffers: Vec<VecDeque<Vec<u8>>>,
+    downstream_ready: Option<usize>,
+    last_max_items: usize,
+}
+
+impl Zipper {
+    pub fn new(inlets_count: usize, eagerly_complete: bool, eagerly_fail: bool) -> Self {
+        Self {
+            eagerly_complete,
+            eagerly_fail,
+            upstream_states: (0..inlets_count).map(|_| UpstreamState::Idle).collect(),
+            failure: None,
+            buffers: (0..inlets_count).map(|_| VecDeque::new()).collect(),
+            downstream_ready: None,
+            last_max_items: 1,
+        }
+    }
+
+    pub fn pushed(&mut self, inlet_idx: usize, items: Vec<Vec<u8>>) {
+        self.upstream_states[inlet_idx] = UpstreamState::Idle;
+        if !self.is_exhausted() {
+            self.buffers[inlet_idx].extend(items);
+        }
+    }
+
+    pub fn completed(&mut self, inlet_idx: usize) {
+        self.upstream_states[inlet_idx] = UpstreamState::Complete;
+    }
+
+    pub fn failed(&mut self, inlet_idx: usize, failure: PortFailure) {
+        self.upstream_states[inlet_idx] = UpstreamState::Failed;
+        self.failure.get_or_insert(failure);
+    }
+
+    pub fn pulled(&mut self, max_items: usize) {
+        self.downstream_ready = Some(max_items);
+        self.last_max_items = std::cmp::max(max_items, 1);
+    }
+
+    /// No more records can be made up: one of the inlets is done and its items are used up.
+    pub fn is_exhausted(&self) -> bool {
+        self.upstream_states
+            .iter()
+            .zip(&self.buffers)
+            .any(|(upstream_state, buffer)| is_done(upstream_state) && buffer.is_empty())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.upstream_states.iter().all(is_done)
+    }
+
+    /// Takes the records for the next push, if the downstream has pulled and there are any.
+    pub fn next_push(&mut self) -> Option<Vec<Vec<u8>>> {
+        let available = self.buffers.iter().map(|buffer| buffer.len()).min()?;
+        match self.downstream_ready {
+            Some(max_items) if available > 0 && max_items > 0 => {
+                self.downstream_ready = None;
+                let records = (0..std::cmp::min(available, max_items))
+                    .map(|_| {
+                        self.buffers
+                            .iter_mut()
+                            .flat_map(|buffer| buffer.pop_front().unwrap_or_default())
+                            .collect()
+                    })
+                    .collect();
+                Some(records)
+            }
+            _ => None,
+        }
+    }
+
+    /// How many items to pull from each of the inlets (if any), marking them pulled.
+    ///
+    /// Once exhausted, the remaining inlets are pulled (and their items discarded) until they are done.
+    pub fn pulls(&mut self) -> Vec<Option<usize>> {
+        let exhausted = self.is_exhausted();
+        let downstream_ready = self.downstream_ready;
+        let last_max_items = self.last_max_items;
+
+        self.upstream_states
+            .iter_mut()
+            .zip(&self.buffers)
+            .map(|(upstream_state, buffer)| {
+                let max_items = match (&upstream_state, exhausted, downstream_ready) {
+                    (UpstreamState::Idle, true, _) => Some(last_max_items),
+                    (UpstreamState::Idle, false, Some(max_items)) if buffer.is_empty() => {
+                        Some(max_items)
+                    }
+                    (_, _, _) => None,
+                };
+                if max_items.is_some() {
+                    *upstream_state = UpstreamState::Pulled;
+                }
+                max_items
+            })
+            .collect()
+    }
+}
+
+fn is_done(upstream_state: &UpstreamState) -> bool {
+    match upstream_state {
+        UpstreamState::Complete | UpstreamState::Failed => true,
+        UpstreamState::Idle | UpstreamState::Pulled => false,
+    }
+}
+
+#[test]
+fn zipper_test() {
+    let mut zipper = Zipper::new(2, true, true);
+
+    zipper.pulled(2);
+    assert_eq!(zipper.pulls(), vec![Some(2), Some(2)]);
+    zipper.pushed(0, vec![vec![1], vec![2]]);
+    assert_eq!(zipper.next_push(), None);
+    zipper.pushed(1, vec![vec![10]]);
+    assert_eq!(zipper.next_push(), Some(vec![vec![1, 10]]));
+
+    zipper.pulled(2);
+    assert_eq!(zipper.pulls(), vec![None, Some(2)]);
+    zipper.completed(1);
+    assert!(zipper.is_exhausted());
+    assert!(!zipper.is_finished());
+}